@@ -0,0 +1,67 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+#[cfg(feature = "dylib")]
+use wasmer::*;
+
+/// A module exporting `count` functions, with as many symbols to look up
+/// when loading it.
+#[cfg(feature = "dylib")]
+fn many_functions_wat(count: usize) -> String {
+    let mut wat = String::from("(module\n");
+    for index in 0..count {
+        wat.push_str(&format!(
+            "    (func (export \"f{}\") (result i32) (i32.const {}))\n",
+            index, index
+        ));
+    }
+    wat.push(')');
+    wat
+}
+
+#[cfg(feature = "dylib")]
+pub fn run_deserialize(store: &Store, compiler_name: &str, c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    // Below and well above the number of symbols from which they are
+    // read from the symbol table at once.
+    for &count in &[16, 10_000] {
+        let module = Module::new(&store, many_functions_wat(count)).unwrap();
+        let path = dir.path().join(format!("module_{}.so", count));
+        module.serialize_to_file(&path).unwrap();
+
+        c.bench_function(
+            &format!("dylib deserialize {} functions {}", count, compiler_name),
+            |b| b.iter(|| unsafe { Module::deserialize_from_file(&store, &path).unwrap() }),
+        );
+    }
+}
+
+fn run_deserialize_benchmarks(_c: &mut Criterion) {
+    #[cfg(all(feature = "dylib", feature = "llvm"))]
+    {
+        let store = Store::new(
+            &wasmer_engine_dylib::Dylib::new(wasmer_compiler_llvm::LLVM::new()).engine(),
+        );
+        run_deserialize(&store, "llvm", _c);
+    }
+
+    #[cfg(all(feature = "dylib", feature = "cranelift"))]
+    {
+        let store = Store::new(
+            &wasmer_engine_dylib::Dylib::new(wasmer_compiler_cranelift::Cranelift::new()).engine(),
+        );
+        run_deserialize(&store, "cranelift", _c);
+    }
+
+    #[cfg(all(feature = "dylib", feature = "singlepass"))]
+    {
+        let store = Store::new(
+            &wasmer_engine_dylib::Dylib::new(wasmer_compiler_singlepass::Singlepass::new())
+                .engine(),
+        );
+        run_deserialize(&store, "singlepass", _c);
+    }
+}
+
+criterion_group!(benches, run_deserialize_benchmarks);
+
+criterion_main!(benches);
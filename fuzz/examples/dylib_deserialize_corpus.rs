@@ -0,0 +1,45 @@
+//! Generate the seed corpus of the `dylib_deserialize` fuzzer: the
+//! serialized artifacts of a few small modules.
+
+use std::fs;
+use std::path::Path;
+use wasmer::{Module, Store};
+use wasmer_compiler_cranelift::Cranelift;
+use wasmer_engine_dylib::Dylib;
+
+const MODULES: &[(&str, &str)] = &[
+    ("empty", "(module)"),
+    (
+        "function",
+        r#"(module (func (export "run") (result i32) (i32.const 42)))"#,
+    ),
+    (
+        "imports",
+        r#"(module
+            (import "env" "f" (func $f (param i32) (result i32)))
+            (func (export "run") (result i32) (call $f (i32.const 1))))"#,
+    ),
+    (
+        "memory",
+        r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 16) "hello")
+            (table 2 funcref)
+            (global (mut i64) (i64.const 0)))"#,
+    ),
+];
+
+fn main() -> anyhow::Result<()> {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus/dylib_deserialize");
+    fs::create_dir_all(&corpus)?;
+    let engine = Dylib::new(Cranelift::default())
+        .data_initializers_symbol("WASMER_DATA_INITIALIZERS")
+        .engine();
+    let store = Store::new(&engine);
+    for (name, wat) in MODULES {
+        let module = Module::new(&store, wat)?;
+        fs::write(corpus.join(name), module.serialize()?)?;
+    }
+    println!("Wrote {} artifacts to {}", MODULES.len(), corpus.display());
+    Ok(())
+}
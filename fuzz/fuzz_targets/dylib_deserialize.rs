@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasmer_engine_dylib::DylibArtifact;
+
+// The seed corpus of valid artifacts is generated with the
+// `dylib_deserialize_corpus` example (see the README).
+fuzz_target!(|bytes: &[u8]| {
+    // Every step of `DylibArtifact::deserialize` before the shared
+    // object is handed to `dlopen`: a corrupted shared object can crash
+    // the dynamic loader itself, which is out of our hands. Invalid
+    // bytes must only ever give a `DeserializeError`.
+    if !DylibArtifact::is_deserializable(bytes) {
+        return;
+    }
+    let _ = unsafe { DylibArtifact::read_metadata_from_bytes(bytes) };
+    let _ = DylibArtifact::read_data_initializers(bytes, "WASMER_DATA_INITIALIZERS");
+});
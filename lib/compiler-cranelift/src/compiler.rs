@@ -64,6 +64,12 @@ impl Compiler for CraneliftCompiler {
         &self.config.middlewares
     }
 
+    /// Get a fingerprint of the configuration of this compiler, from its
+    /// `Debug` representation (which includes the middlewares).
+    fn config_fingerprint(&self) -> String {
+        format!("{:?}", self.config)
+    }
+
     /// Compile the module using Cranelift, producing a compilation result with
     /// associated relocations.
     fn compile_module(
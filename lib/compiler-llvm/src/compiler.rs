@@ -194,6 +194,12 @@ impl Compiler for LLVMCompiler {
         &self.config.middlewares
     }
 
+    /// Get a fingerprint of the configuration of this compiler, from its
+    /// `Debug` representation (which includes the middlewares).
+    fn config_fingerprint(&self) -> String {
+        format!("{:?}", self.config)
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
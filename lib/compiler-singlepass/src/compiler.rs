@@ -48,6 +48,12 @@ impl Compiler for SinglepassCompiler {
         &self.config.middlewares
     }
 
+    /// Get a fingerprint of the configuration of this compiler, from its
+    /// `Debug` representation (which includes the middlewares).
+    fn config_fingerprint(&self) -> String {
+        format!("{:?}", self.config)
+    }
+
     /// Compile the module using Singlepass, producing a compilation result with
     /// associated relocations.
     fn compile_module(
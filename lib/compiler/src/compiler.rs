@@ -139,6 +139,12 @@ pub trait Compiler: Send + MemoryUsage {
 
     /// Get the middlewares for this compiler
     fn get_middlewares(&self) -> &[Arc<dyn ModuleMiddleware>];
+
+    /// Get a fingerprint of the configuration of this compiler (its
+    /// backend, optimization level, middlewares, ...), for the caches of
+    /// compiled code: compilers with the same fingerprint are expected
+    /// to generate the same code for a given module and target.
+    fn config_fingerprint(&self) -> String;
 }
 
 /// The kinds of wasmer_types objects that might be found in a native object file.
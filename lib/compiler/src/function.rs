@@ -138,7 +138,7 @@ impl TrampolinesSection {
 
 /// The result of compiling a WebAssembly module's functions.
 #[cfg_attr(feature = "enable-serde", derive(Deserialize, Serialize))]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Compilation {
     /// Compiled code for the function bodies.
     functions: Functions,
@@ -212,6 +212,11 @@ impl Compilation {
         &self.functions[func]
     }
 
+    /// Gets a mutable reference to a single function
+    pub fn get_mut(&mut self, func: LocalFunctionIndex) -> &mut CompiledFunction {
+        &mut self.functions[func]
+    }
+
     /// Gets the number of functions defined.
     pub fn len(&self) -> usize {
         self.functions.len()
@@ -1,51 +1,115 @@
 //! Define `DylibArtifact` to allow compiling and instantiating
 //! to be done as separate steps.
 
+#[cfg(feature = "compiler")]
+use crate::cache::{digest, CacheDir};
+#[cfg(feature = "compiler")]
+use crate::cancellation::CancellationToken;
+#[cfg(feature = "compiler")]
+use crate::engine::{is_android, LinkProfile, Linker};
 use crate::engine::{DylibEngine, DylibEngineInner};
-use crate::serialize::{ArchivedModuleMetadata, ModuleMetadata};
+use crate::integrity::{self, INTEGRITY_RECORD_LEN, INTEGRITY_SYMBOL};
+use crate::loader::{page_size, LoadedObject};
+use crate::metrics::CompilationMetrics;
+#[cfg(feature = "compiler")]
+use crate::metrics::LinkerUsage;
+#[cfg(feature = "compiler")]
+use crate::serialize::encode_data_initializers;
+#[cfg(any(feature = "compiler", feature = "component-model"))]
+use crate::serialize::ComponentAdapter;
+use crate::serialize::{
+    decode_data_initializers, ArchivedModuleMetadata, CAbiExport, ModuleMetadata,
+};
 use libloading::{Library, Symbol as LibrarySymbol};
 use loupe::MemoryUsage;
+use object::{Object, ObjectSection, ObjectSymbol};
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+#[cfg(feature = "compiler")]
+use std::collections::HashSet;
 use std::error::Error;
-use std::fs::{self, File};
-use std::io::{Read, Write};
+#[cfg(feature = "compiler")]
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::Write;
+#[cfg(feature = "compiler")]
+use std::io::{self, Read};
+#[cfg(unix)]
+use std::mem::ManuallyDrop;
+use std::ops::Range;
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+#[cfg(all(feature = "compiler", unix))]
+use std::os::unix::process::ExitStatusExt;
 use std::path::{Path, PathBuf};
 #[cfg(feature = "compiler")]
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::str::FromStr;
+#[cfg(feature = "compiler")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "compiler")]
+use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "compiler")]
+use std::thread;
+#[cfg(feature = "compiler")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "compiler")]
 use tempfile::NamedTempFile;
 use tracing::log::error;
 #[cfg(feature = "compiler")]
 use tracing::trace;
+#[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    Architecture, CompileError, CompiledFunctionFrameInfo, Features, FunctionAddressMap,
-    OperatingSystem, Symbol, SymbolRegistry, Triple,
+    wptype_to_type, BinaryFormat, Compiler, Environment, FunctionBody, FunctionBodyData,
+    ModuleEnvironment, ModuleMiddlewareChain, ModuleTranslationState, Relocation, Target,
+    WasmError,
 };
-#[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    CompileModuleInfo, Compiler, FunctionBodyData, ModuleEnvironment, ModuleMiddlewareChain,
-    ModuleTranslationState,
+    Architecture, Compilation, CompileError, CompileModuleInfo, CompiledFunctionFrameInfo,
+    CpuFeature, Features, FunctionAddressMap, OperatingSystem, Symbol, SymbolRegistry, Triple,
 };
 use wasmer_engine::{
     register_frame_info, Artifact, DeserializeError, FunctionExtent, GlobalFrameInfoRegistration,
-    InstantiationError, SerializeError,
+    InstantiationError, LinkError, RuntimeError, SerializeError,
 };
 #[cfg(feature = "compiler")]
 use wasmer_engine::{Engine, Tunables};
+use wasmer_object::RelocationRecord;
 #[cfg(feature = "compiler")]
-use wasmer_object::{emit_compilation, emit_data, get_object_for_target};
-use wasmer_types::entity::{BoxedSlice, PrimaryMap};
+use wasmer_object::{
+    emit_alias, emit_call_wrapper, emit_compilation_with_options, emit_data, get_object_for_target,
+    EmitOptions, RelocationModel,
+};
 #[cfg(feature = "compiler")]
-use wasmer_types::DataInitializer;
+use wasmer_types::entity::EntityRef;
+use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 use wasmer_types::{
-    FunctionIndex, LocalFunctionIndex, MemoryIndex, ModuleInfo, OwnedDataInitializer,
-    SignatureIndex, TableIndex,
+    DataInitializer, ExportIndex, FunctionIndex, LocalFunctionIndex, MemoryIndex, ModuleInfo,
+    OwnedDataInitializer, SignatureIndex, TableIndex,
 };
+#[cfg(feature = "compiler")]
+use wasmer_types::{MemoryType, Pages, TableType};
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, TableStyle, VMFunctionBody,
-    VMSharedSignatureIndex, VMTrampoline,
+    FuncDataRegistry, FunctionBodyPtr, InstanceHandle, MemoryStyle, SignatureRegistry, TableStyle,
+    TrapHandler, VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// A compiled Wasm module, ready to be instantiated.
+///
+/// # Thread safety
+///
+/// One artifact can back any number of instances, including ones
+/// created and used concurrently from several threads. The function
+/// and trampoline pointers are only read after the artifact is built,
+/// and point into the code of the shared object, which is never
+/// written to. The only state set lazily on instantiation is
+/// synchronized: the deferred signature registration (see
+/// `DylibEngine::set_defer_signature_registration`) happens once
+/// through a `OnceCell`, and the frame info registration is behind a
+/// `Mutex`.
 #[derive(MemoryUsage)]
 pub struct DylibArtifact {
     dylib_path: PathBuf,
@@ -56,15 +120,73 @@ pub struct DylibArtifact {
     finished_function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     func_data_registry: Arc<FuncDataRegistry>,
-    signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    /// The shared signature indices, set once the signatures are
+    /// registered in the engine.
+    #[loupe(skip)]
+    signatures: OnceCell<BoxedSlice<SignatureIndex, VMSharedSignatureIndex>>,
+    /// The registry to register the signatures in on instantiation, when
+    /// their registration is deferred.
+    #[loupe(skip)]
+    deferred_signature_registry: Option<Arc<SignatureRegistry>>,
     frame_info_registration: Mutex<Option<GlobalFrameInfoRegistration>>,
+    /// Whether the code has unwind or debug info sections (see
+    /// `has_frame_info`).
+    unwind_sections: bool,
+    /// The loaded shared object, if any (cross-compiled artifacts are
+    /// never loaded), kept loaded by the engine too.
+    #[loupe(skip)]
+    library: Option<Arc<LoadedCode<Library>>>,
+    /// The object file loaded in process instead of a shared object, if
+    /// any (see `DylibEngine::set_in_process_linking`), kept loaded by
+    /// the engine too.
+    #[loupe(skip)]
+    loaded_object: Option<Arc<LoadedCode<LoadedObject>>>,
+    /// The object file the shared object was linked from, if kept (see
+    /// `DylibEngine::set_keep_object_files`).
+    object: Option<Vec<u8>>,
+    /// The data initializers borrowed from the mapped file the artifact
+    /// was deserialized from, if any (see `deserialize_from_file`).
+    #[cfg(unix)]
+    #[loupe(skip)]
+    mapped_data_initializers: Option<MappedDataInitializers>,
+    #[loupe(skip)]
+    compilation_metrics: Option<CompilationMetrics>,
+    /// The import library generated alongside the `.dll`, if any.
+    import_library: Option<PathBuf>,
+    /// The debug info split from the `.so`, if any.
+    debug_info: Option<PathBuf>,
+    #[loupe(skip)]
+    relocation_records: Option<Vec<RelocationRecord>>,
+    /// The trampolines of the component adapters, in the order of
+    /// `ModuleMetadata::component_adapters`.
+    component_adapter_trampolines: Vec<FunctionBodyPtr>,
+    /// The target triple of the code, parsed from the metadata.
+    #[loupe(skip)]
+    target_triple: Triple,
+    /// The compiled code, kept to replace functions without recompiling
+    /// the module (see `DylibEngine::set_incremental_linking`).
+    #[loupe(skip)]
+    compilation: Option<Compilation>,
 }
 
 impl Drop for DylibArtifact {
     fn drop(&mut self) {
+        // The frame info points into the code, which is unregistered
+        // with the artifact even though the engine keeps the code loaded.
+        self.frame_info_registration.lock().unwrap().take();
+
         if self.is_temporary {
-            if let Err(err) = std::fs::remove_file(&self.dylib_path) {
-                error!("cannot delete the temporary dylib artifact: {}", err);
+            let files = std::iter::once(self.dylib_path.clone())
+                .chain(self.import_library.clone())
+                .chain(self.debug_info.clone());
+            // The files of the loaded code are removed once the engine
+            // unloads it.
+            if let Some(library) = &self.library {
+                library.remove_on_unload(files);
+            } else if let Some(loaded_object) = &self.loaded_object {
+                loaded_object.remove_on_unload(files);
+            } else {
+                remove_temporary_files(files);
             }
         }
     }
@@ -74,7 +196,673 @@ fn to_compile_error(err: impl Error) -> CompileError {
     CompileError::Codegen(err.to_string())
 }
 
-const WASMER_METADATA_SYMBOL: &[u8] = b"WASMER_METADATA";
+/// Classify an error of the compiler backend.
+///
+/// Compilers report some of their failures as `CompileError::Wasm`
+/// errors, which are otherwise used for malformed WebAssembly (when the
+/// module is translated, or its function bodies are parsed). Generic
+/// errors are failures of the code generation, and unsupported
+/// features are limits of the compiler, not of the input, so they are
+/// reported as such to let callers tell invalid modules from compiler
+/// failures.
+#[cfg(feature = "compiler")]
+fn backend_error(err: CompileError) -> CompileError {
+    match err {
+        CompileError::Wasm(WasmError::Generic(message)) => CompileError::Codegen(message),
+        CompileError::Wasm(WasmError::Unsupported(feature)) => {
+            CompileError::UnsupportedFeature(feature)
+        }
+        err => err,
+    }
+}
+
+const SIGNATURE_REGISTRY_FULL: &str = "the engine signature registry is full";
+
+/// A cell holding signatures that are already registered.
+fn signatures_cell(
+    signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+) -> OnceCell<BoxedSlice<SignatureIndex, VMSharedSignatureIndex>> {
+    let cell = OnceCell::new();
+    let _ = cell.set(signatures);
+    cell
+}
+
+/// A file mapped read-only in memory.
+#[cfg(unix)]
+struct FileMapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only, so it can be shared between threads.
+#[cfg(unix)]
+unsafe impl Send for FileMapping {}
+#[cfg(unix)]
+unsafe impl Sync for FileMapping {}
+
+#[cfg(unix)]
+impl FileMapping {
+    /// Map the whole file.
+    fn map(file: &fs::File) -> io::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The file is empty",
+            ));
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for FileMapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+/// The data initializers of an artifact deserialized from a mapped file,
+/// borrowed from the mapping instead of being copied into the metadata.
+#[cfg(unix)]
+struct MappedDataInitializers {
+    /// The data initializers, pointing into `mapping` (so they must be
+    /// dropped first).
+    initializers: Vec<DataInitializer<'static>>,
+    /// The data initializers copied out of the mapping, if they were
+    /// asked for as owned ones (see `Artifact::data_initializers`).
+    owned: OnceCell<Box<[OwnedDataInitializer]>>,
+    mapping: FileMapping,
+}
+
+/// The code of an artifact loaded in process: its shared object, or
+/// its object file with in-process linking.
+///
+/// The code is shared with the engine, which keeps it loaded for as
+/// long as it lives (like the code memory of the universal engine): the
+/// functions exported by the instances and the funcrefs in their tables
+/// only keep the engine alive, so they can still be called once the
+/// artifact is dropped. The files of the artifact to remove (see
+/// `remove_on_unload`) are only removed once the code is unloaded.
+pub(crate) struct LoadedCode<T> {
+    /// The code, only taken to unload it before removing the files.
+    code: Option<T>,
+    files_to_remove: Mutex<Vec<PathBuf>>,
+}
+
+impl<T> LoadedCode<T> {
+    fn new(code: T) -> Arc<Self> {
+        Arc::new(Self {
+            code: Some(code),
+            files_to_remove: Mutex::new(vec![]),
+        })
+    }
+
+    /// Removes the `files` once the code is unloaded.
+    fn remove_on_unload(&self, files: impl IntoIterator<Item = PathBuf>) {
+        self.files_to_remove.lock().unwrap().extend(files);
+    }
+}
+
+impl<T> std::ops::Deref for LoadedCode<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.code
+            .as_ref()
+            .expect("the code is only unloaded on drop")
+    }
+}
+
+impl<T> Drop for LoadedCode<T> {
+    fn drop(&mut self) {
+        // The code must be unloaded before its backing file is removed
+        // (on Windows a loaded `.dll` can't be deleted at all).
+        self.code.take();
+        remove_temporary_files(self.files_to_remove.get_mut().unwrap().drain(..));
+    }
+}
+
+fn remove_temporary_files(files: impl IntoIterator<Item = PathBuf>) {
+    for file in files {
+        if let Err(err) = std::fs::remove_file(&file) {
+            error!(
+                "cannot delete the temporary file {}: {}",
+                file.display(),
+                err
+            );
+        }
+    }
+}
+
+/// The default name of the symbol holding the module metadata.
+pub(crate) const DEFAULT_METADATA_SYMBOL: &str = "WASMER_METADATA";
+
+/// The prefix of the names of the temporary files created by the engine.
+pub(crate) const TEMP_FILE_PREFIX: &str = "wasmer_dylib_";
+
+/// The size below which a linked shared object is considered truncated
+/// (a valid one is always much bigger: the ELF64 header alone is 64 bytes).
+#[cfg(feature = "compiler")]
+const MIN_SHARED_OBJECT_BYTES: u64 = 64;
+
+/// How many times the creation of a temporary file is attempted.
+#[cfg(feature = "compiler")]
+const TEMP_FILE_ATTEMPTS: u32 = 5;
+
+/// Create a temporary file for the compilation.
+///
+/// The creation can fail transiently when many modules are compiled
+/// in parallel (e.g. with `EMFILE`), so it is retried a few times with
+/// an exponential backoff before giving up.
+#[cfg(feature = "compiler")]
+fn create_temp_file(suffix: &str) -> Result<NamedTempFile, CompileError> {
+    let mut backoff = Duration::from_millis(1);
+    let mut attempt = 1;
+    loop {
+        match tempfile::Builder::new()
+            .prefix(TEMP_FILE_PREFIX)
+            .suffix(suffix)
+            .tempfile()
+        {
+            Ok(file) => return Ok(file),
+            Err(err) if attempt < TEMP_FILE_ATTEMPTS => {
+                trace!(
+                    "creating a temporary file failed (attempt {}): {}",
+                    attempt,
+                    err
+                );
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(err) => {
+                return Err(CompileError::Resource(format!(
+                    "cannot create a temporary file after {} attempts: {}",
+                    TEMP_FILE_ATTEMPTS, err
+                )))
+            }
+        }
+    }
+}
+
+/// Counter making the temporary paths of `temp_path_next_to` unique
+/// within the process.
+#[cfg(feature = "compiler")]
+static TEMP_PATH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A path for a temporary file in the same directory as `path` (so it
+/// can be atomically renamed to `path`).
+#[cfg(feature = "compiler")]
+fn temp_path_next_to(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{}` is not a file path", path.display()),
+        )
+    })?;
+    let mut temp_file_name = OsString::from(".");
+    temp_file_name.push(file_name);
+    temp_file_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_PATH_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    Ok(path.with_file_name(temp_file_name))
+}
+
+/// Write a serialized artifact to `path`, and check that the file has
+/// the expected size and header once flushed to disk.
+#[cfg(feature = "compiler")]
+fn write_and_check(path: &Path, serialized: &[u8]) -> Result<(), SerializeError> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(serialized)?;
+    file.sync_all()?;
+    drop(file);
+
+    let mut header = vec![0; serialized.len().min(16)];
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    file.read_exact(&mut header)?;
+    if size != serialized.len() as u64 || !serialized.starts_with(&header) {
+        return Err(SerializeError::Generic(format!(
+            "the artifact written to `{}` is corrupted ({} bytes instead of {})",
+            path.display(),
+            size,
+            serialized.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Fix the install name of a `.dylib` written to `temp_path`, before it's
+/// renamed to `path`.
+#[cfg(feature = "compiler")]
+fn fix_install_name(path: &Path, temp_path: &Path) -> Result<(), SerializeError> {
+    /*
+    When you write the artifact to a new file it still has the 'Mach-O Identifier'
+    of the original file, and so this can causes linker issues when adding
+    the new file to an XCode project.
+
+    The below code renames the ID of the file so that it references itself through
+    an @executable_path prefix. Basically it tells XCode to find this file
+    inside of the projects' list of 'linked executables'.
+
+    You need to be running MacOS for the following to actually work though.
+    */
+    let has_extension = path.extension().is_some();
+    if has_extension && path.extension().unwrap() == "dylib" {
+        let filename = path.file_name().unwrap().to_str().unwrap();
+
+        Command::new("install_name_tool")
+            .arg("-id")
+            .arg(format!("@executable_path/{}", &filename))
+            .arg(temp_path)
+            .output()?;
+    }
+
+    Ok(())
+}
+
+/// Options of a compilation, beyond the configuration of the engine.
+#[cfg(feature = "compiler")]
+#[derive(Default)]
+struct CompileOptions<'a> {
+    /// The token to abort the compilation with.
+    cancellation: Option<&'a CancellationToken>,
+    /// A writer to also write the generated object file to.
+    object_writer: Option<&'a mut dyn Write>,
+    /// The component-model adapters to generate trampolines for.
+    component_adapters: &'a [ComponentAdapter],
+    /// A buffer to store the serialized artifact in.
+    serialized: Option<&'a mut Vec<u8>>,
+}
+
+/// The object file of a compiled module, before it's linked.
+#[cfg(feature = "compiler")]
+struct CompiledObject {
+    metadata: ModuleMetadata,
+    obj_bytes: Vec<u8>,
+    metrics: CompilationMetrics,
+    relocation_records: Option<Vec<RelocationRecord>>,
+    /// The compiled code, if kept for incremental linking.
+    compilation: Option<Compilation>,
+}
+
+/// The artifacts compiled by `DylibArtifact::new_batch`, with the index
+/// of their module, in the order their linking finishes.
+///
+/// The next module is only compiled when no linking has finished yet, so
+/// the modules are compiled as the iterator is advanced.
+#[cfg(feature = "compiler")]
+pub struct BatchArtifacts<'a> {
+    engine: &'a DylibEngine,
+    modules: std::iter::Enumerate<std::slice::Iter<'a, &'a [u8]>>,
+    tunables: &'a dyn Tunables,
+    /// The sender of the compiled object files to the linking threads,
+    /// dropped once all the modules are compiled so that they stop.
+    object_sender: Option<mpsc::Sender<(usize, CompiledObject)>>,
+    results: mpsc::Receiver<(usize, Result<DylibArtifact, CompileError>)>,
+}
+
+#[cfg(feature = "compiler")]
+impl<'a> Iterator for BatchArtifacts<'a> {
+    type Item = (usize, Result<DylibArtifact, CompileError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Ok(result) = self.results.try_recv() {
+                return Some(result);
+            }
+            let (index, data) = match self.modules.next() {
+                Some(module) => module,
+                None => {
+                    self.object_sender = None;
+                    // The results end once the linking threads are done.
+                    return self.results.recv().ok();
+                }
+            };
+            let prefix = self.engine.inner().get_prefix(data);
+            let options = CompileOptions::default();
+            match DylibArtifact::compile_object(self.engine, data, self.tunables, prefix, &options)
+            {
+                Ok(compiled) => {
+                    if let Some(object_sender) = &self.object_sender {
+                        let _ = object_sender.send((index, compiled));
+                    }
+                }
+                Err(err) => return Some((index, Err(err))),
+            }
+        }
+    }
+}
+
+/// How often a cancellable linker invocation checks for cancellation.
+#[cfg(feature = "compiler")]
+const LINKER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Run the linker command to completion like `Command::output`, unless
+/// the `cancellation` token is cancelled first, in which case the command
+/// is killed and `None` is returned.
+///
+/// The resources used by the linker are returned too, when available.
+#[cfg(feature = "compiler")]
+fn run_linker(
+    mut command: Command,
+    cancellation: Option<&CancellationToken>,
+) -> io::Result<Option<(Output, Option<LinkerUsage>)>> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // The pipes are drained while waiting, so the command can't block
+    // on a full pipe.
+    let stdout = read_in_thread(child.stdout.take());
+    let stderr = read_in_thread(child.stderr.take());
+    let (status, usage) = match wait_linker(&mut child, cancellation)? {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let join = |reader: thread::JoinHandle<io::Result<Vec<u8>>>| {
+        reader.join().expect("the pipe reader panicked")
+    };
+    let output = Output {
+        status,
+        stdout: join(stdout)?,
+        stderr: join(stderr)?,
+    };
+    Ok(Some((output, usage)))
+}
+
+/// Wait for the linker to exit, collecting its resource usage with
+/// `wait4`.
+#[cfg(all(feature = "compiler", unix))]
+fn wait_linker(
+    child: &mut Child,
+    cancellation: Option<&CancellationToken>,
+) -> io::Result<Option<(ExitStatus, Option<LinkerUsage>)>> {
+    let pid = child.id() as libc::pid_t;
+    loop {
+        let cancelled = cancellation.map_or(false, CancellationToken::is_cancelled);
+        if cancelled {
+            // The command may have exited in the meantime.
+            let _ = child.kill();
+        }
+        // Only poll if the command may have to be killed.
+        let options = if cancellation.is_some() && !cancelled {
+            libc::WNOHANG
+        } else {
+            0
+        };
+        let mut status = 0;
+        let mut rusage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+        let result = unsafe { libc::wait4(pid, &mut status, options, rusage.as_mut_ptr()) };
+        if result == -1 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+        if cancelled {
+            return Ok(None);
+        }
+        if result == pid {
+            let rusage = unsafe { rusage.assume_init() };
+            let usage = LinkerUsage::from_rusage(&rusage);
+            return Ok(Some((ExitStatus::from_raw(status), Some(usage))));
+        }
+        thread::sleep(LINKER_POLL_INTERVAL);
+    }
+}
+
+/// Wait for the linker to exit (its resource usage is not available).
+#[cfg(all(feature = "compiler", not(unix)))]
+fn wait_linker(
+    child: &mut Child,
+    cancellation: Option<&CancellationToken>,
+) -> io::Result<Option<(ExitStatus, Option<LinkerUsage>)>> {
+    let cancellation = match cancellation {
+        Some(cancellation) => cancellation,
+        None => return Ok(Some((child.wait()?, None))),
+    };
+    loop {
+        if cancellation.is_cancelled() {
+            // The command may have exited in the meantime.
+            let _ = child.kill();
+            child.wait()?;
+            return Ok(None);
+        }
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some((status, None)));
+        }
+        thread::sleep(LINKER_POLL_INTERVAL);
+    }
+}
+
+/// The free space needed in the temporary directory to link an object
+/// file, as a multiple of its size: the object file and the shared
+/// object are both written there, with some margin.
+#[cfg(feature = "compiler")]
+const LINK_DISK_SPACE_FACTOR: u64 = 3;
+
+/// Fail early if the temporary directory doesn't have enough free space
+/// to link an object file of the given size, as the linker would
+/// otherwise fail with a cryptic `No space left on device` error.
+#[cfg(all(feature = "compiler", unix))]
+fn check_temp_disk_space(object_size: usize) -> Result<(), CompileError> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let temp_dir = std::env::temp_dir();
+    let path = CString::new(temp_dir.as_os_str().as_bytes()).map_err(to_compile_error)?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::zeroed();
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        // The free space is unknown, the linker will tell.
+        return Ok(());
+    }
+    let stat = unsafe { stat.assume_init() };
+    let available = stat.f_bavail as u64 * stat.f_frsize as u64;
+    let required = object_size as u64 * LINK_DISK_SPACE_FACTOR;
+    if available < required {
+        return Err(CompileError::Resource(format!(
+            "the temporary directory `{}` has {} bytes free, but linking a {} bytes object file needs about {} bytes",
+            temp_dir.display(),
+            available,
+            object_size,
+            required
+        )));
+    }
+    Ok(())
+}
+
+/// The free space can't be checked on this platform.
+#[cfg(all(feature = "compiler", not(unix)))]
+fn check_temp_disk_space(_object_size: usize) -> Result<(), CompileError> {
+    Ok(())
+}
+
+/// The Android API level linked against: the oldest one that
+/// supports 64-bit targets.
+#[cfg(feature = "compiler")]
+const ANDROID_API_LEVEL: u32 = 21;
+
+/// The combined length of the linker arguments above which they are
+/// passed in a response file (`@file`), well below the command line
+/// length limits (e.g. 32 KiB on Windows).
+#[cfg(feature = "compiler")]
+const LINKER_RESPONSE_FILE_THRESHOLD: usize = 8 * 1024;
+
+/// Write the linker arguments to a response file if they are too long
+/// to be passed on the command line.
+///
+/// The arguments are quoted with the GNU syntax of response files, as
+/// understood by GCC and Clang. Returns `None` if the arguments are
+/// short enough, or not all valid UTF-8.
+#[cfg(feature = "compiler")]
+fn linker_response_file(args: &[OsString]) -> Result<Option<NamedTempFile>, CompileError> {
+    let length: usize = args.iter().map(|arg| arg.len() + 1).sum();
+    if length <= LINKER_RESPONSE_FILE_THRESHOLD {
+        return Ok(None);
+    }
+    let args = match args
+        .iter()
+        .map(|arg| arg.to_str())
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(args) => args,
+        None => return Ok(None),
+    };
+    let mut contents = String::with_capacity(length * 2);
+    for arg in args {
+        contents.push('"');
+        for c in arg.chars() {
+            if c == '"' || c == '\\' {
+                contents.push('\\');
+            }
+            contents.push(c);
+        }
+        contents.push_str("\"\n");
+    }
+    let mut file = create_temp_file(".rsp")?;
+    file.write_all(contents.as_bytes())
+        .map_err(to_compile_error)?;
+    Ok(Some(file))
+}
+
+/// Read a pipe to the end in a new thread.
+#[cfg(feature = "compiler")]
+fn read_in_thread(
+    pipe: Option<impl Read + Send + 'static>,
+) -> thread::JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut bytes = vec![];
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut bytes)?;
+        }
+        Ok(bytes)
+    })
+}
+
+/// The number of symbols to look up in a shared object above which they
+/// are all read from its symbol table at once, rather than looked up
+/// one by one with `dlsym`.
+///
+/// Reading the symbol table costs reading the whole file, which is
+/// slower than a few `dlsym` calls for small modules.
+const SYMBOL_TABLE_LOOKUP_THRESHOLD: usize = 128;
+
+/// The base address of the shared object (or executable) mapping
+/// `address`, if any.
+#[cfg(unix)]
+fn mapped_object_base(address: usize) -> Option<usize> {
+    let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+    if unsafe { libc::dladdr(address as *const libc::c_void, &mut info) } == 0 {
+        return None;
+    }
+    Some(info.dli_fbase as usize)
+}
+
+/// The base address of the shared object mapping `address`, which can't
+/// be looked up outside of Unix: every address is assumed to be mapped.
+#[cfg(not(unix))]
+fn mapped_object_base(_address: usize) -> Option<usize> {
+    Some(0)
+}
+
+/// Strip the version from the name of a symbol in the symbol table of a
+/// shared object (`name@VERSION` or `name@@VERSION`).
+fn strip_symbol_version(name: &str) -> &str {
+    name.find('@').map_or(name, |index| &name[..index])
+}
+
+/// Resolves the addresses of the symbols of a loaded shared object.
+///
+/// The symbols are looked up with `dlsym`, or, for many symbols (see
+/// `SYMBOL_TABLE_LOOKUP_THRESHOLD`), in the symbol table of the shared
+/// object read once (see `DylibArtifact::symbol_addresses`). Symbols
+/// that are not exported (see
+/// `DylibEngine::set_export_module_functions_only`) are also found in
+/// the symbol table, which is then read on the first of them.
+struct SymbolResolver<'a> {
+    lib: &'a Library,
+    path: &'a Path,
+    metadata_symbol: &'a str,
+    /// The addresses read from the symbol table, if it was read.
+    addresses: Option<HashMap<String, usize>>,
+}
+
+impl<'a> SymbolResolver<'a> {
+    fn new(lib: &'a Library, path: &'a Path, metadata_symbol: &'a str, count: usize) -> Self {
+        // A shared object without a usable symbol table (e.g. a
+        // Windows DLL) falls back to `dlsym`.
+        let addresses = if count > SYMBOL_TABLE_LOOKUP_THRESHOLD {
+            DylibArtifact::symbol_addresses(lib, path, metadata_symbol).ok()
+        } else {
+            None
+        };
+        Self {
+            lib,
+            path,
+            metadata_symbol,
+            addresses,
+        }
+    }
+
+    /// Resolve the address of the symbol named `name`.
+    fn resolve(&mut self, name: &str) -> Result<usize, CompileError> {
+        if let Some(address) = self.addresses.as_ref().and_then(|a| a.get(name)) {
+            return Ok(*address);
+        }
+        // We use a fake function signature `fn()` because we just want
+        // to get the symbol address.
+        let symbol: Result<LibrarySymbol<unsafe extern "C" fn()>, _> =
+            unsafe { self.lib.get(name.as_bytes()) };
+        match symbol {
+            Ok(symbol) => Ok(unsafe { symbol.into_raw().into_raw() as usize }),
+            Err(err) => {
+                if self.addresses.is_none() {
+                    self.addresses = Some(DylibArtifact::symbol_addresses(
+                        self.lib,
+                        self.path,
+                        self.metadata_symbol,
+                    )?);
+                }
+                self.addresses
+                    .as_ref()
+                    .and_then(|addresses| addresses.get(name))
+                    .copied()
+                    .ok_or_else(|| {
+                        CompileError::Codegen(format!(
+                            "Can't find the symbol `{}` in `{}`: tried `dlsym` on `{}` ({}) \
+                             and the symbol table with symbol versions stripped",
+                            name,
+                            self.path.display(),
+                            name,
+                            err
+                        ))
+                    })
+            }
+        }
+    }
+}
 
 impl DylibArtifact {
     // Mach-O header in iOS/Mac
@@ -93,19 +881,38 @@ impl DylibArtifact {
     #[allow(dead_code)]
     const MAGIC_HEADER_COFF_64: &'static [u8] = &[b'M', b'Z'];
 
+    // Magic header of the portable container, see `serialize_portable`
+    const MAGIC_HEADER_PORTABLE: &'static [u8] = b"\0wasmer-dylib\0";
+
     /// Check if the provided bytes look like `DylibArtifact`.
     ///
     /// This means, if the bytes look like a shared object file in the target
-    /// system.
+    /// system, or a portable container (see `serialize_portable`) of one
+    /// compiled for the host.
     pub fn is_deserializable(bytes: &[u8]) -> bool {
+        if bytes.starts_with(Self::MAGIC_HEADER_PORTABLE) {
+            // The containers are not nested (see `split_portable`).
+            return match Self::split_portable(bytes) {
+                Ok((target_triple, object_bytes)) => {
+                    target_triple == Triple::host().to_string()
+                        && Self::is_host_object(object_bytes)
+                }
+                Err(_) => false,
+            };
+        }
+        Self::is_host_object(bytes)
+    }
+
+    /// Check if the provided bytes look like an object file of the host.
+    fn is_host_object(bytes: &[u8]) -> bool {
         cfg_if::cfg_if! {
             if #[cfg(all(target_pointer_width = "64", target_vendor="apple"))] {
                 bytes.starts_with(Self::MAGIC_HEADER_MH_CIGAM_64)
             }
-            else if #[cfg(all(target_pointer_width = "64", target_os="linux"))] {
+            else if #[cfg(all(target_pointer_width = "64", any(target_os="linux", target_os="android")))] {
                 bytes.starts_with(Self::MAGIC_HEADER_ELF_64)
             }
-            else if #[cfg(all(target_pointer_width = "32", target_os="linux"))] {
+            else if #[cfg(all(target_pointer_width = "32", any(target_os="linux", target_os="android")))] {
                 bytes.starts_with(Self::MAGIC_HEADER_ELF_32)
             }
             else if #[cfg(all(target_pointer_width = "64", target_os="windows"))] {
@@ -117,6 +924,65 @@ impl DylibArtifact {
         }
     }
 
+    /// Split a portable container (see `serialize_portable`) into the
+    /// target triple it records and the shared object it contains.
+    fn split_portable(bytes: &[u8]) -> Result<(&str, &[u8]), DeserializeError> {
+        let corrupted =
+            || DeserializeError::CorruptedBinary("The portable container is truncated".to_string());
+        let rest = bytes
+            .strip_prefix(Self::MAGIC_HEADER_PORTABLE)
+            .ok_or_else(|| {
+                DeserializeError::Incompatible(
+                    "The provided bytes are not a portable container".to_string(),
+                )
+            })?;
+        if rest.len() < 4 {
+            return Err(corrupted());
+        }
+        let (length, rest) = rest.split_at(4);
+        let mut length_bytes: [u8; 4] = Default::default();
+        length_bytes.copy_from_slice(length);
+        let length = u32::from_le_bytes(length_bytes) as usize;
+        if rest.len() < length {
+            return Err(corrupted());
+        }
+        let (target_triple, object_bytes) = rest.split_at(length);
+        if object_bytes.starts_with(Self::MAGIC_HEADER_PORTABLE) {
+            return Err(DeserializeError::CorruptedBinary(
+                "The portable container contains another one".to_string(),
+            ));
+        }
+        let target_triple = std::str::from_utf8(target_triple).map_err(|_| {
+            DeserializeError::CorruptedBinary(
+                "The target triple of the portable container is not valid UTF-8".to_string(),
+            )
+        })?;
+        Ok((target_triple, object_bytes))
+    }
+
+    /// Detect the target of an artifact by peeking at its object file
+    /// header.
+    ///
+    /// Returns the architecture, binary format and endianness of the
+    /// object file, or `None` if the bytes are not a supported object
+    /// file. Unlike `is_deserializable`, this works for artifacts of any
+    /// target, so it can be used to route artifacts to the right engine.
+    pub fn detect_target(
+        bytes: &[u8],
+    ) -> Option<(
+        object::Architecture,
+        object::BinaryFormat,
+        object::Endianness,
+    )> {
+        let file = object::File::parse(bytes).ok()?;
+        let endianness = if file.is_little_endian() {
+            object::Endianness::Little
+        } else {
+            object::Endianness::Big
+        };
+        Some((file.architecture(), file.format(), endianness))
+    }
+
     #[cfg(feature = "compiler")]
     /// Generate a compilation
     fn generate_metadata<'data>(
@@ -141,6 +1007,9 @@ impl DylibArtifact {
         let middlewares = compiler.get_middlewares();
         middlewares.apply_on_module_info(&mut module);
 
+        // The styles cover the imported memories and tables too (they come
+        // first in the index spaces), as the imports resolution checks
+        // the imported memories against them.
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = module
             .memories
             .values()
@@ -166,27 +1035,550 @@ impl DylibArtifact {
         ))
     }
 
+    /// Time the compilation of the functions of a module in `batches`
+    /// batches of consecutive functions, slowest first.
+    ///
+    /// Compilers compile all the functions of a module at once, so each
+    /// batch is timed by compiling the module with the bodies of the other
+    /// functions replaced by `unreachable` (valid whatever the signature),
+    /// minus the time to compile the module with only such bodies.
+    #[cfg(feature = "compiler")]
+    fn time_function_batches(
+        compiler: &dyn Compiler,
+        target: &Target,
+        compile_info: &CompileModuleInfo,
+        module_translation: &ModuleTranslationState,
+        function_body_inputs: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+        batches: usize,
+        check_cancelled: &dyn Fn() -> Result<(), CompileError>,
+    ) -> Result<Vec<(Range<LocalFunctionIndex>, Duration)>, CompileError> {
+        // No locals, `unreachable` and `end`.
+        const UNREACHABLE_BODY: &[u8] = &[0x00, 0x00, 0x0b];
+
+        let time_compilation = |batch: Range<usize>| -> Result<Duration, CompileError> {
+            let inputs = function_body_inputs
+                .iter()
+                .map(|(index, input)| FunctionBodyData {
+                    data: if batch.contains(&index.index()) {
+                        input.data
+                    } else {
+                        UNREACHABLE_BODY
+                    },
+                    module_offset: input.module_offset,
+                })
+                .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+            let start = Instant::now();
+            compiler
+                .compile_module(target, compile_info, module_translation, inputs)
+                .map_err(backend_error)?;
+            Ok(start.elapsed())
+        };
+
+        let function_count = function_body_inputs.len();
+        if function_count == 0 {
+            return Ok(vec![]);
+        }
+        let batches = batches.min(function_count);
+        let batch_size = (function_count + batches - 1) / batches;
+        let baseline = time_compilation(0..0)?;
+        let mut timings = vec![];
+        for start in (0..function_count).step_by(batch_size) {
+            check_cancelled()?;
+            let end = (start + batch_size).min(function_count);
+            let time = time_compilation(start..end)?
+                .checked_sub(baseline)
+                .unwrap_or_default();
+            timings.push((
+                LocalFunctionIndex::new(start)..LocalFunctionIndex::new(end),
+                time,
+            ));
+        }
+        timings.sort_by(|(_, time1), (_, time2)| time2.cmp(time1));
+        Ok(timings)
+    }
+
     /// Compile a data buffer into a `DylibArtifact`, which may
     /// then be instantiated.
+    ///
+    /// Malformed WebAssembly is reported as `CompileError::Wasm`,
+    /// whether it's found when translating the module or when compiling
+    /// its function bodies, and failures of the compiler itself as
+    /// `CompileError::Codegen` (or `CompileError::UnsupportedFeature`).
     #[cfg(feature = "compiler")]
     pub fn new(
         engine: &DylibEngine,
         data: &[u8],
         tunables: &dyn Tunables,
     ) -> Result<Self, CompileError> {
-        let mut engine_inner = engine.inner_mut();
-        let target = engine.target();
-        let compiler = engine_inner.compiler()?;
-        let (compile_info, function_body_inputs, data_initializers, module_translation) =
-            Self::generate_metadata(data, engine_inner.features(), compiler, tunables)?;
+        Self::compile(engine, data, tunables, CompileOptions::default())
+    }
 
-        let data_initializers = data_initializers
-            .iter()
-            .map(OwnedDataInitializer::new)
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+    /// Compile a data buffer into a `DylibArtifact` like `new`, aborting
+    /// with `CompileError::Cancelled` if the `cancellation` token is
+    /// cancelled before the compilation completes.
+    #[cfg(feature = "compiler")]
+    pub fn new_cancellable(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+        cancellation: &CancellationToken,
+    ) -> Result<Self, CompileError> {
+        let options = CompileOptions {
+            cancellation: Some(cancellation),
+            ..CompileOptions::default()
+        };
+        Self::compile(engine, data, tunables, options)
+    }
 
-        let target_triple = target.triple();
+    /// Compile a data buffer into a `DylibArtifact` like `new`, also
+    /// returning it serialized (as by `Artifact::serialize`).
+    ///
+    /// The shared object is read once when it's linked, instead of
+    /// compiling and then serializing the artifact, which reads it again.
+    #[cfg(feature = "compiler")]
+    pub fn new_and_serialize(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+    ) -> Result<(Self, Vec<u8>), CompileError> {
+        let mut serialized = Vec::new();
+        let options = CompileOptions {
+            serialized: Some(&mut serialized),
+            ..CompileOptions::default()
+        };
+        let artifact = Self::compile(engine, data, tunables, options)?;
+        Ok((artifact, serialized))
+    }
+
+    /// Compile many data buffers into `DylibArtifact`s, linking them on
+    /// up to `max_concurrent_links` background threads.
+    ///
+    /// The modules are compiled one after the other on the thread
+    /// iterating the returned `BatchArtifacts` (the compilers already
+    /// compile the functions of a module in parallel), and each object
+    /// file is handed to a linking thread as soon as it's compiled, so
+    /// that the linkers run while the next modules are compiled. The
+    /// limit bounds the number of linker processes running at once.
+    ///
+    /// The artifacts are yielded with the index of their module in
+    /// `modules`, as soon as their linking finishes (or their compilation
+    /// fails). The artifact cache of the engine (see
+    /// `DylibEngine::set_artifact_cache_capacity`) is not used.
+    #[cfg(feature = "compiler")]
+    pub fn new_batch<'a>(
+        engine: &'a DylibEngine,
+        modules: &'a [&'a [u8]],
+        tunables: &'a dyn Tunables,
+        max_concurrent_links: usize,
+    ) -> BatchArtifacts<'a> {
+        let (result_sender, results) = mpsc::channel();
+        let (object_sender, objects) = mpsc::channel::<(usize, CompiledObject)>();
+        let objects = Arc::new(Mutex::new(objects));
+        for _ in 0..max_concurrent_links.max(1).min(modules.len()) {
+            let engine = engine.clone();
+            let objects = objects.clone();
+            let result_sender = result_sender.clone();
+            thread::spawn(move || loop {
+                // The receiver is unlocked before linking.
+                let next = objects.lock().unwrap().recv();
+                let (index, compiled) = match next {
+                    Ok(next) => next,
+                    // All the modules were compiled.
+                    Err(_) => break,
+                };
+                let artifact =
+                    Self::link_compiled_object(&engine, compiled, CompileOptions::default());
+                if result_sender.send((index, artifact)).is_err() {
+                    break;
+                }
+            });
+        }
+        BatchArtifacts {
+            engine,
+            modules: modules.iter().enumerate(),
+            tunables,
+            object_sender: Some(object_sender),
+            results,
+        }
+    }
+
+    /// Compile a data buffer into a `DylibArtifact` like `new`, also
+    /// writing the generated object file to `object_writer`.
+    ///
+    /// This lets the caller capture the object file (e.g. to keep it in
+    /// memory or in a custom storage) before it's linked into the shared
+    /// object.
+    #[cfg(feature = "compiler")]
+    pub fn new_with_object_writer(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+        object_writer: &mut dyn Write,
+    ) -> Result<Self, CompileError> {
+        let options = CompileOptions {
+            object_writer: Some(object_writer),
+            ..CompileOptions::default()
+        };
+        Self::compile(engine, data, tunables, options)
+    }
+
+    /// Compile a data buffer into a `DylibArtifact` like `new`, also
+    /// generating a trampoline for each of the component-model
+    /// `component_adapters` of the lowered imports of the module.
+    ///
+    /// The adapters are recorded in the `ModuleMetadata`, so their
+    /// trampolines are resolved again when the artifact is
+    /// deserialized (see `DylibArtifact::component_adapter_trampoline`).
+    /// With no adapters, this is the same as `new`.
+    #[cfg(all(feature = "compiler", feature = "component-model"))]
+    pub fn new_with_component_adapters(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+        component_adapters: &[ComponentAdapter],
+    ) -> Result<Self, CompileError> {
+        let options = CompileOptions {
+            component_adapters,
+            ..CompileOptions::default()
+        };
+        Self::compile(engine, data, tunables, options)
+    }
+
+    /// Check that the component adapters have unique names and adapt
+    /// imported functions of `module`.
+    #[cfg(feature = "compiler")]
+    fn validate_component_adapters(
+        component_adapters: &[ComponentAdapter],
+        module: &ModuleInfo,
+    ) -> Result<(), CompileError> {
+        let mut names = HashSet::new();
+        for adapter in component_adapters {
+            if !names.insert(adapter.name.as_str()) {
+                return Err(CompileError::Validate(format!(
+                    "Duplicate component adapter `{}`",
+                    adapter.name
+                )));
+            }
+            if !module.is_imported_function(adapter.import) {
+                return Err(CompileError::Validate(format!(
+                    "The component adapter `{}` adapts function {}, which is not imported",
+                    adapter.name,
+                    adapter.import.index()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The C-ABI wrappers to emit for the exported functions defined by
+    /// `module` (see `DylibEngine::set_c_abi_export_prefix`).
+    #[cfg(feature = "compiler")]
+    fn c_abi_exports(module: &ModuleInfo, prefix: &str) -> Vec<CAbiExport> {
+        module
+            .exports
+            .iter()
+            .filter_map(|(name, export)| match export {
+                ExportIndex::Function(function) if !module.is_imported_function(*function) => {
+                    let sanitized_name = name
+                        .chars()
+                        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                        .collect::<String>();
+                    Some(CAbiExport {
+                        name: name.clone(),
+                        function: *function,
+                        symbol: format!("{}{}", prefix, sanitized_name),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Compile a data buffer into a `DylibArtifact`, or deserialize it
+    /// from the artifact cache of the engine (see
+    /// `DylibEngine::set_artifact_cache_capacity`).
+    #[cfg(feature = "compiler")]
+    fn compile(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+        options: CompileOptions,
+    ) -> Result<Self, CompileError> {
+        let prefix = engine.inner().get_prefix(&data);
+        // Both caches are keyed by the settings too, and malformed modules
+        // are never cached (their compilation fails).
+        let settings = Self::settings_digest(engine, data, tunables);
+        // The artifact cache is keyed by the prefix, which the prefixer
+        // makes unique to the module.
+        let cache_key = settings
+            .as_ref()
+            .filter(|_| {
+                !prefix.is_empty()
+                    && options.object_writer.is_none()
+                    && options.component_adapters.is_empty()
+                    && !engine.inner().incremental_linking()
+                    && engine.inner_mut().artifact_cache().capacity() > 0
+            })
+            .map(|settings| format!("{}/{}", prefix, settings));
+        // The shared objects of the cache directory are loaded in place.
+        let cache_dir = settings
+            .and_then(|settings| Self::cache_dir_path(engine, data, &prefix, &settings, &options));
+        if let Some(cache_key) = &cache_key {
+            let cached = engine.inner_mut().artifact_cache().get(cache_key);
+            if let Some(bytes) = cached {
+                // The bytes were serialized from an artifact compiled by
+                // this engine.
+                match unsafe { Self::deserialize_uncounted(engine, &bytes) } {
+                    Ok(artifact) => {
+                        engine.inner().counters().record_cache_lookup(true);
+                        if let Some(serialized) = options.serialized {
+                            *serialized = bytes.to_vec();
+                        }
+                        return Ok(artifact);
+                    }
+                    Err(err) => {
+                        trace!("Cannot load the cached artifact: {}", err);
+                        engine.inner_mut().artifact_cache().remove(cache_key);
+                    }
+                }
+            }
+            // A miss is only counted once the cache directory is looked
+            // up too, if any.
+            if cache_dir.is_none() {
+                engine.inner().counters().record_cache_lookup(false);
+            }
+        }
+
+        if let Some((_, path)) = &cache_dir {
+            if path.exists() {
+                match unsafe { Self::deserialize_from_file_uncounted(engine, path) } {
+                    Ok(artifact) => {
+                        engine.inner().counters().record_cache_lookup(true);
+                        if let Some(serialized) = options.serialized {
+                            *serialized = fs::read(path).map_err(to_compile_error)?;
+                        }
+                        return Ok(artifact);
+                    }
+                    Err(err) => {
+                        trace!(
+                            "Cannot load the artifact cached at `{}`: {}",
+                            path.display(),
+                            err
+                        );
+                        let _ = fs::remove_file(path);
+                    }
+                }
+            }
+            engine.inner().counters().record_cache_lookup(false);
+        }
+
+        // The serialized artifact is also captured to cache it, so that
+        // the shared object is only read once.
+        let mut captured = Vec::new();
+        let mut serialized = options.serialized;
+        if serialized.is_none() && cache_key.is_some() {
+            serialized = Some(&mut captured);
+        }
+        let options = CompileOptions {
+            serialized: serialized.as_deref_mut(),
+            ..options
+        };
+        let artifact = Self::compile_uncached(engine, data, tunables, prefix, options)?;
+        if let (Some(cache_key), Some(bytes)) = (cache_key, serialized) {
+            if artifact.is_loaded() {
+                engine
+                    .inner_mut()
+                    .artifact_cache()
+                    .insert(cache_key, bytes.as_slice().into());
+            }
+        }
+        if let Some((cache_dir, path)) = cache_dir {
+            if artifact.is_loaded() {
+                let saved = cache_dir
+                    .create()
+                    .map_err(SerializeError::from)
+                    .and_then(|()| artifact.serialize_to_file(&path))
+                    .and_then(|()| cache_dir.evict().map_err(SerializeError::from));
+                if let Err(err) = saved {
+                    trace!("Cannot save the artifact to `{}`: {}", path.display(), err);
+                }
+            }
+        }
+        Ok(artifact)
+    }
+
+    /// The digest of everything the code compiled from the module in
+    /// `data` depends on besides its bytes and prefix: the styles of its
+    /// memories and tables, the target and the settings of the engine
+    /// (see `DylibEngineInner::settings_fingerprint`), or `None` if the
+    /// module can't be parsed.
+    #[cfg(feature = "compiler")]
+    fn settings_digest(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+    ) -> Option<String> {
+        let styles = Self::memory_and_table_styles(data, tunables)?;
+        let target = engine.target();
+        Some(digest(
+            [
+                format!("{:?}", styles),
+                target.triple().to_string(),
+                format!("{:?}", target.cpu_features()),
+                engine.inner().settings_fingerprint(),
+            ]
+            .iter()
+            .map(|part| part.as_bytes()),
+        ))
+    }
+
+    /// The cache directory of the engine and the path of the shared
+    /// object of the module in it, if the compilation can use it (see
+    /// `DylibEngine::set_cache_dir`).
+    ///
+    /// The shared object is keyed by the hash of the module, its prefix
+    /// and the digest of the settings (see `settings_digest`).
+    #[cfg(feature = "compiler")]
+    fn cache_dir_path(
+        engine: &DylibEngine,
+        data: &[u8],
+        prefix: &str,
+        settings: &str,
+        options: &CompileOptions,
+    ) -> Option<(CacheDir, PathBuf)> {
+        if options.object_writer.is_some() || !options.component_adapters.is_empty() {
+            return None;
+        }
+        let engine_inner = engine.inner();
+        if engine_inner.incremental_linking() {
+            return None;
+        }
+        let cache_dir = engine_inner.cache_dir()?;
+        let key = CacheDir::key(data, &[prefix, settings]);
+        let path = cache_dir.path(&key, Self::get_default_extension(engine.target().triple()));
+        Some((cache_dir, path))
+    }
+
+    /// The styles of the memories and tables of the module in `data`
+    /// (the imported ones first, like in their index spaces), read from
+    /// its sections without translating it, or `None` if they can't be
+    /// parsed.
+    #[cfg(feature = "compiler")]
+    fn memory_and_table_styles(
+        data: &[u8],
+        tunables: &dyn Tunables,
+    ) -> Option<(Vec<MemoryStyle>, Vec<TableStyle>)> {
+        use wasmer_compiler::wasmparser::{
+            ImportSectionEntryType, MemoryType as WPMemoryType, Parser, Payload,
+            TableType as WPTableType,
+        };
+
+        let memory_style = |memory: WPMemoryType| match memory {
+            WPMemoryType::M32 { limits, shared } => Some(tunables.memory_style(&MemoryType {
+                minimum: Pages(limits.initial),
+                maximum: limits.maximum.map(Pages),
+                shared,
+            })),
+            WPMemoryType::M64 { .. } => None,
+        };
+        let table_style = |table: WPTableType| {
+            Some(tunables.table_style(&TableType {
+                ty: wptype_to_type(table.element_type).ok()?,
+                minimum: table.limits.initial,
+                maximum: table.limits.maximum,
+            }))
+        };
+        let mut memory_styles = Vec::new();
+        let mut table_styles = Vec::new();
+        for payload in Parser::new(0).parse_all(data) {
+            match payload.ok()? {
+                Payload::ImportSection(imports) => {
+                    for import in imports {
+                        match import.ok()?.ty {
+                            ImportSectionEntryType::Memory(memory) => {
+                                memory_styles.push(memory_style(memory)?)
+                            }
+                            ImportSectionEntryType::Table(table) => {
+                                table_styles.push(table_style(table)?)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::TableSection(tables) => {
+                    for table in tables {
+                        table_styles.push(table_style(table.ok()?)?);
+                    }
+                }
+                Payload::MemorySection(memories) => {
+                    for memory in memories {
+                        memory_styles.push(memory_style(memory.ok()?)?);
+                    }
+                }
+                // The memories and tables are declared before the code.
+                Payload::CodeSectionStart { .. } => break,
+                _ => {}
+            }
+        }
+        Some((memory_styles, table_styles))
+    }
+
+    #[cfg(feature = "compiler")]
+    fn compile_uncached(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+        prefix: String,
+        options: CompileOptions,
+    ) -> Result<Self, CompileError> {
+        let compiled = Self::compile_object(engine, data, tunables, prefix, &options)?;
+        Self::link_compiled_object(engine, compiled, options)
+    }
+
+    /// Compile a data buffer into the object file of a `DylibArtifact`,
+    /// to be linked by `link_compiled_object`.
+    #[cfg(feature = "compiler")]
+    fn compile_object(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+        prefix: String,
+        options: &CompileOptions,
+    ) -> Result<CompiledObject, CompileError> {
+        let cancellation = options.cancellation;
+        let check_cancelled = || cancellation.map_or(Ok(()), CancellationToken::check);
+        // The engine is only locked to read its settings, and not while the
+        // module is translated and compiled, so that the linking threads
+        // (see `new_batch`) can go on meanwhile. The compiler is locked on
+        // its own, and never while the engine is locked.
+        let (compiler, features, metrics_slowest_functions, metrics_batches) = {
+            let engine_inner = engine.inner();
+            engine_inner.check_module_size(data.len())?;
+            (
+                engine_inner.compiler()?,
+                engine_inner.features().clone(),
+                engine_inner.metrics_slowest_functions(),
+                engine_inner.metrics_batches(),
+            )
+        };
+        let target = engine.target();
+        let mut metrics = CompilationMetrics::default();
+
+        let translation_start = Instant::now();
+        let (compile_info, function_body_inputs, data_initializers, module_translation) =
+            Self::generate_metadata(data, &features, &**compiler.lock().unwrap(), tunables)?;
+        engine.inner().validate_module(&compile_info.module, data)?;
+        Self::validate_component_adapters(options.component_adapters, &compile_info.module)?;
+        metrics.translation = translation_start.elapsed();
+        check_cancelled()?;
+
+        let data_initializers = data_initializers
+            .iter()
+            .map(OwnedDataInitializer::new)
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        let target_triple = target.triple();
 
         /*
         // We construct the function body lengths
@@ -206,85 +1598,370 @@ impl DylibArtifact {
             .map(|_function_body| 0u64)
             .collect::<PrimaryMap<LocalFunctionIndex, u64>>();
 
+        let engine_inner = engine.inner();
+        let c_abi_exports = engine_inner
+            .c_abi_export_prefix()
+            .map(|prefix| Self::c_abi_exports(&compile_info.module, prefix))
+            .unwrap_or_default();
+        let symbol_naming = engine_inner.symbol_naming().clone();
+        // Compilers emitting the object file themselves always name the
+        // metadata symbol `WASMER_METADATA`, don't emit any other data
+        // symbol, and don't report their relocations.
+        let native_object_emission = engine_inner.metadata_symbol() == DEFAULT_METADATA_SYMBOL
+            && engine_inner.data_initializers_symbol().is_none()
+            && engine_inner.max_relocations().is_none()
+            && Self::exported_functions(&engine_inner, &compile_info.module, target_triple)
+                .is_none()
+            && options.component_adapters.is_empty()
+            && !engine_inner.incremental_linking()
+            && engine_inner.has_default_alignments()
+            && !engine_inner.verify_relocations()
+            && engine_inner.c_abi_export_prefix().is_none()
+            && !engine_inner.in_process_linking()
+            && !engine_inner.seals_artifacts();
+        drop(engine_inner);
         let mut metadata = ModuleMetadata {
             compile_info,
-            prefix: engine_inner.get_prefix(&data),
+            prefix,
             data_initializers,
             function_body_lengths,
+            trampolines_length: 0,
+            custom_sections_length: 0,
+            cpu_features: target.cpu_features().as_u64(),
+            target_triple: target_triple.to_string(),
+            component_adapters: options.component_adapters.to_vec(),
+            symbol_naming,
+            c_abi_exports,
+            build_id: None,
         };
 
-        let serialized_data = metadata.serialize()?;
+        let metadata_binary = Self::encode_metadata_binary(&mut metadata)?;
 
-        let mut metadata_binary = vec![0; 12];
-        let mut writable = &mut metadata_binary[..];
-        leb128::write::unsigned(&mut writable, serialized_data.len() as u64)
-            .expect("Should write number");
-        metadata_binary.extend(serialized_data);
+        let compile_info = &metadata.compile_info;
+        let symbol_registry = metadata.get_symbol_registry();
 
-        let (compile_info, symbol_registry) = metadata.split();
+        if let Some(count) = metrics_slowest_functions {
+            metrics.slowest_functions = Self::time_function_batches(
+                &**compiler.lock().unwrap(),
+                &target,
+                compile_info,
+                module_translation.as_ref().unwrap(),
+                &function_body_inputs,
+                metrics_batches,
+                &check_cancelled,
+            )?;
+            metrics.slowest_functions.truncate(count);
+        }
 
-        let maybe_obj_bytes = compiler.experimental_native_compile_module(
-            &target,
-            &compile_info,
-            module_translation.as_ref().unwrap(),
-            &function_body_inputs,
-            &symbol_registry,
-            &metadata_binary,
-        );
+        let compilation_start = Instant::now();
+        let mut relocation_records = None;
+        let mut kept_compilation = None;
+        let maybe_obj_bytes = if native_object_emission {
+            compiler.lock().unwrap().experimental_native_compile_module(
+                &target,
+                &compile_info,
+                module_translation.as_ref().unwrap(),
+                &function_body_inputs,
+                &symbol_registry,
+                &metadata_binary,
+            )
+        } else {
+            None
+        };
 
-        let filepath = match maybe_obj_bytes {
+        let obj_bytes = match maybe_obj_bytes {
             Some(obj_bytes) => {
-                let obj_bytes = obj_bytes?;
-                let file = tempfile::Builder::new()
-                    .prefix("wasmer_dylib_")
-                    .suffix(".o")
-                    .tempfile()
-                    .map_err(to_compile_error)?;
-
-                // Re-open it.
-                let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
-                file.write_all(&obj_bytes).map_err(to_compile_error)?;
-                filepath
+                let obj_bytes = obj_bytes.map_err(backend_error)?;
+                metrics.compilation = compilation_start.elapsed();
+                obj_bytes
             }
             None => {
-                let compilation = compiler.compile_module(
-                    &target,
-                    &compile_info,
-                    module_translation.as_ref().unwrap(),
-                    function_body_inputs,
-                )?;
-                let mut obj = get_object_for_target(&target_triple).map_err(to_compile_error)?;
-                emit_data(
-                    &mut obj,
-                    WASMER_METADATA_SYMBOL,
+                let compilation = compiler
+                    .lock()
+                    .unwrap()
+                    .compile_module(
+                        &target,
+                        &compile_info,
+                        module_translation.as_ref().unwrap(),
+                        function_body_inputs,
+                    )
+                    .map_err(backend_error)?;
+                metrics.compilation = compilation_start.elapsed();
+                check_cancelled()?;
+                let relocation_count = (0..compilation.len())
+                    .map(LocalFunctionIndex::new)
+                    .map(|index| compilation.get(index).relocations.len())
+                    .chain(
+                        compilation
+                            .get_custom_section_relocations()
+                            .values()
+                            .map(Vec::len),
+                    )
+                    .sum();
+                let engine_inner = engine.inner();
+                engine_inner.check_relocation_count(relocation_count)?;
+                if engine_inner.incremental_linking() {
+                    kept_compilation = Some(compilation.clone());
+                }
+                let emission_start = Instant::now();
+                let (obj_bytes, records) = Self::write_object_file(
+                    &engine_inner,
+                    target_triple,
+                    &metadata,
                     &metadata_binary,
-                    std::mem::align_of::<ArchivedModuleMetadata>() as u64,
+                    compilation,
+                    options.component_adapters,
+                )?;
+                relocation_records = records;
+                metrics.emission = emission_start.elapsed();
+                obj_bytes
+            }
+        };
+        Ok(CompiledObject {
+            metadata,
+            obj_bytes,
+            metrics,
+            relocation_records,
+            compilation: kept_compilation,
+        })
+    }
+
+    /// Link the object file compiled by `compile_object` into a
+    /// `DylibArtifact`.
+    #[cfg(feature = "compiler")]
+    fn link_compiled_object(
+        engine: &DylibEngine,
+        compiled: CompiledObject,
+        options: CompileOptions,
+    ) -> Result<Self, CompileError> {
+        let mut artifact = Self::link_object(
+            engine,
+            engine.target().triple(),
+            compiled.metadata,
+            compiled.obj_bytes,
+            compiled.metrics,
+            options,
+        )?;
+        artifact.relocation_records = compiled.relocation_records;
+        artifact.compilation = compiled.compilation;
+        Ok(artifact)
+    }
+
+    /// Emit the object file of a compilation, returning it with its
+    /// relocation records (if recorded, see
+    /// `DylibEngine::set_record_relocations`).
+    #[cfg(feature = "compiler")]
+    fn write_object_file(
+        engine_inner: &DylibEngineInner,
+        target_triple: &Triple,
+        metadata: &ModuleMetadata,
+        metadata_binary: &[u8],
+        compilation: Compilation,
+        component_adapters: &[ComponentAdapter],
+    ) -> Result<(Vec<u8>, Option<Vec<RelocationRecord>>), CompileError> {
+        let metadata_symbol = engine_inner.metadata_symbol();
+        let data_initializers_symbol = engine_inner.data_initializers_symbol();
+        let data_initializers_binary =
+            data_initializers_symbol.map(|_| encode_data_initializers(&metadata.data_initializers));
+        let symbol_registry = metadata.get_symbol_registry();
+        let mut obj = get_object_for_target(target_triple).map_err(to_compile_error)?;
+        emit_data(
+            &mut obj,
+            metadata_symbol.as_bytes(),
+            metadata_binary,
+            std::mem::align_of::<ArchivedModuleMetadata>() as u64,
+        )
+        .map_err(to_compile_error)?;
+        if let (Some(symbol), Some(binary)) = (data_initializers_symbol, &data_initializers_binary)
+        {
+            emit_data(&mut obj, symbol.as_bytes(), binary, 1).map_err(to_compile_error)?;
+        }
+        if engine_inner.seals_artifacts() {
+            emit_data(
+                &mut obj,
+                INTEGRITY_SYMBOL.as_bytes(),
+                &integrity::empty_record(),
+                1,
+            )
+            .map_err(to_compile_error)?;
+        }
+        let emit_options = EmitOptions {
+            large_custom_section_threshold: engine_inner.large_custom_section_threshold(),
+            record_relocations: engine_inner.record_relocations(),
+            hide_internal_symbols: engine_inner.export_required_symbols_only(),
+            exported_functions: Self::exported_functions(
+                engine_inner,
+                &metadata.compile_info.module,
+                target_triple,
+            ),
+            relocation_model: engine_inner.relocation_model(),
+            text_alignment: Some(engine_inner.text_alignment()),
+            data_alignment: Some(engine_inner.data_alignment()),
+            verify_relocations: engine_inner.verify_relocations(),
+        };
+        let records = emit_compilation_with_options(
+            &mut obj,
+            compilation,
+            &symbol_registry,
+            target_triple,
+            &emit_options,
+        )
+        .map_err(to_compile_error)?;
+        for (index, adapter) in component_adapters.iter().enumerate() {
+            let trampoline =
+                symbol_registry.symbol_to_name(Symbol::DynamicFunctionTrampoline(adapter.import));
+            emit_alias(
+                &mut obj,
+                symbol_registry
+                    .component_adapter_symbol_name(index)
+                    .as_bytes(),
+                trampoline.as_bytes(),
+            )
+            .map_err(to_compile_error)?;
+        }
+        for export in &metadata.c_abi_exports {
+            let index = metadata
+                .compile_info
+                .module
+                .local_func_index(export.function)
+                .expect("only defined functions have C-ABI wrappers");
+            let signature = metadata.compile_info.module.functions[export.function];
+            emit_call_wrapper(
+                &mut obj,
+                export.symbol.as_bytes(),
+                symbol_registry
+                    .symbol_to_name(Symbol::LocalFunction(index))
+                    .as_bytes(),
+                symbol_registry
+                    .symbol_to_name(Symbol::FunctionCallTrampoline(signature))
+                    .as_bytes(),
+                target_triple,
+            )
+            .map_err(to_compile_error)?;
+        }
+        let obj_bytes = obj.write().map_err(to_compile_error)?;
+        let relocation_records = if emit_options.record_relocations {
+            Some(records)
+        } else {
+            None
+        };
+        Ok((obj_bytes, relocation_records))
+    }
+
+    /// The local functions to export from the shared object, or `None`
+    /// to export all of them (see
+    /// `DylibEngine::set_export_module_functions_only`).
+    #[cfg(feature = "compiler")]
+    fn exported_functions(
+        engine_inner: &DylibEngineInner,
+        module: &ModuleInfo,
+        target_triple: &Triple,
+    ) -> Option<HashSet<LocalFunctionIndex>> {
+        match target_triple.binary_format {
+            BinaryFormat::Elf | BinaryFormat::Macho
+                if engine_inner.export_module_functions_only() =>
+            {
+                Some(
+                    module
+                        .exports
+                        .values()
+                        .filter_map(|export| match export {
+                            ExportIndex::Function(index) => module.local_func_index(*index),
+                            _ => None,
+                        })
+                        .collect(),
                 )
+            }
+            _ => None,
+        }
+    }
+
+    /// Link the object file of a compilation into a shared object and
+    /// load it (or stop after writing the object file, see
+    /// `DylibEngine::set_object_only`).
+    ///
+    /// The engine is not locked while the linker runs, so that several
+    /// modules can be linked at once (see `DylibArtifact::new_batch`).
+    #[cfg(feature = "compiler")]
+    fn link_object(
+        engine: &DylibEngine,
+        target_triple: &Triple,
+        mut metadata: ModuleMetadata,
+        mut obj_bytes: Vec<u8>,
+        mut metrics: CompilationMetrics,
+        options: CompileOptions,
+    ) -> Result<Self, CompileError> {
+        let cancellation = options.cancellation;
+        let check_cancelled = || cancellation.map_or(Ok(()), CancellationToken::check);
+        let mut engine_inner = engine.inner_mut();
+        let metrics_slowest_functions = engine_inner.metrics_slowest_functions();
+        let metadata_symbol = engine_inner.metadata_symbol();
+        let data_initializers_symbol = engine_inner.data_initializers_symbol();
+
+        check_cancelled()?;
+        engine_inner.check_object_size(obj_bytes.len())?;
+        check_temp_disk_space(obj_bytes.len())?;
+        Self::read_code_lengths(&obj_bytes, &mut metadata);
+        // The object file is sealed too, as it's the serialized artifact
+        // when it's not linked.
+        if engine_inner.seals_artifacts() {
+            Self::seal(&engine_inner, &mut obj_bytes)?;
+        }
+
+        if let Some(object_writer) = options.object_writer {
+            object_writer
+                .write_all(&obj_bytes)
                 .map_err(to_compile_error)?;
-                emit_compilation(&mut obj, compilation, &symbol_registry, &target_triple)
-                    .map_err(to_compile_error)?;
-                let file = tempfile::Builder::new()
-                    .prefix("wasmer_dylib_")
-                    .suffix(".o")
-                    .tempfile()
-                    .map_err(to_compile_error)?;
+        }
+
+        let file = create_temp_file(".o")?;
 
-                // Re-open it.
-                let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
-                let obj_bytes = obj.write().map_err(to_compile_error)?;
+        // Re-open it.
+        let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
+        file.write_all(&obj_bytes).map_err(to_compile_error)?;
+
+        if engine_inner.object_only() || engine_inner.relocation_model() == RelocationModel::Static
+        {
+            drop(file);
+            if let Some(serialized) = options.serialized {
+                *serialized = obj_bytes;
+            }
+            let mut artifact = Self::from_parts_crosscompiled(metadata, filepath)?;
+            artifact.is_temporary = true;
+            engine_inner.counters().record_compile();
+            if metrics_slowest_functions.is_some() {
+                artifact.compilation_metrics = Some(metrics);
+            }
+            return Ok(artifact);
+        }
 
-                file.write_all(&obj_bytes).map_err(to_compile_error)?;
-                filepath
+        if engine_inner.in_process_linking() && !engine_inner.is_cross_compiling() {
+            drop(file);
+            let loaded_object = LoadedObject::load(&obj_bytes)?;
+            let unwind_sections = Self::has_unwind_sections(&obj_bytes);
+            if let Some(serialized) = options.serialized {
+                *serialized = obj_bytes;
+            }
+            let mut artifact =
+                Self::from_loaded_object(&mut engine_inner, metadata, filepath, loaded_object)?;
+            artifact.is_temporary = true;
+            artifact.unwind_sections = unwind_sections;
+            engine_inner.counters().record_compile();
+            if metrics_slowest_functions.is_some() {
+                artifact.compilation_metrics = Some(metrics);
             }
+            return Ok(artifact);
+        }
+
+        let object = if engine_inner.keep_object_files() {
+            Some(obj_bytes)
+        } else {
+            None
         };
 
         let output_filepath = {
             let suffix = format!(".{}", Self::get_default_extension(&target_triple));
-            let shared_file = tempfile::Builder::new()
-                .prefix("wasmer_dylib_")
-                .suffix(&suffix)
-                .tempfile()
-                .map_err(to_compile_error)?;
+            let shared_file = create_temp_file(&suffix)?;
             shared_file
                 .into_temp_path()
                 .keep()
@@ -298,43 +1975,50 @@ impl DylibArtifact {
             // Apple's clang will not recognize it.
             if into_str == "aarch64-apple-darwin" {
                 "arm64-apple-darwin".to_string()
+            } else if is_android(target_triple) {
+                // The `clang` of the NDK needs the API level.
+                format!("{}{}", into_str, ANDROID_API_LEVEL)
             } else {
                 into_str
             }
         };
 
-        // Set 'isysroot' clang flag if compiling to iOS target
-        let ios_compile_target = target_triple.operating_system == OperatingSystem::Ios;
-        let ios_sdk_flag = {
-            if ios_compile_target {
-                if target_triple.architecture == Architecture::X86_64 {
-                    "-isysroot/Applications/Xcode.app/Contents/Developer/Platforms/iPhoneSimulator.platform/Developer/SDKs/iPhoneSimulator.sdk"
+        // The cross-compiling arguments are only computed when needed:
+        // looking up the `ld` linker scans the `PATH`, which is a
+        // noticeable part of the compilation time of small modules
+        // (e.g. single-function contracts), where the object file is tiny
+        // and most of the time is spent around the linker invocation.
+        let cross_compiling_args: Vec<String> = if is_cross_compiling {
+            // Set 'isysroot' clang flag if compiling to iOS target
+            let ios_compile_target = target_triple.operating_system == OperatingSystem::Ios;
+            let ios_sdk_flag = {
+                if ios_compile_target {
+                    if target_triple.architecture == Architecture::X86_64 {
+                        "-isysroot/Applications/Xcode.app/Contents/Developer/Platforms/iPhoneSimulator.platform/Developer/SDKs/iPhoneSimulator.sdk"
+                    } else {
+                        "-isysroot/Applications/Xcode.app/Contents/Developer/Platforms/iPhoneOS.platform/Developer/SDKs/iPhoneOS.sdk"
+                    }
                 } else {
-                    "-isysroot/Applications/Xcode.app/Contents/Developer/Platforms/iPhoneOS.platform/Developer/SDKs/iPhoneOS.sdk"
+                    ""
                 }
-            } else {
-                ""
-            }
-        };
-        let ios_sdk_lib = {
-            if ios_compile_target {
-                "-lSystem"
-            } else {
-                ""
-            }
-        };
+            };
+            let ios_sdk_lib = {
+                if ios_compile_target {
+                    "-lSystem"
+                } else {
+                    ""
+                }
+            };
 
-        // Get the location of the 'ld' linker for clang
-        let fuse_linker = {
-            let ld_install = which::which("ld");
-            if ios_compile_target && ld_install.is_ok() {
-                ld_install.unwrap().into_os_string().into_string().unwrap()
-            } else {
-                "lld".to_string()
-            }
-        };
+            // Get the location of the 'ld' linker for clang
+            let fuse_linker = match ios_compile_target {
+                true => match which::which("ld") {
+                    Ok(ld_install) => ld_install.into_os_string().into_string().unwrap(),
+                    Err(_) => "lld".to_string(),
+                },
+                false => "lld".to_string(),
+            };
 
-        let cross_compiling_args: Vec<String> = if is_cross_compiling {
             vec![
                 format!("--target={}", target_triple_str),
                 format!("-fuse-ld={}", fuse_linker),
@@ -352,10 +2036,69 @@ impl DylibArtifact {
                 vec![]
             }
         };
-        let target_args = match (target_triple.operating_system, is_cross_compiling) {
-            (OperatingSystem::Windows, true) => vec!["-Wl,/force:unresolved,/noentry"],
-            (OperatingSystem::Windows, false) => vec!["-Wl,-undefined,dynamic_lookup"],
-            _ => vec!["-nostartfiles", "-Wl,-undefined,dynamic_lookup"],
+        // Libcalls are resolved from the host process at load time,
+        // unless a runtime providing them is linked in or dynamic lookup
+        // is disabled.
+        let runtime_object = engine_inner.runtime_object();
+        let dynamic_lookup = engine_inner.dynamic_lookup() && runtime_object.is_none();
+        let target_args = match (
+            target_triple.operating_system,
+            is_cross_compiling,
+            dynamic_lookup,
+        ) {
+            (OperatingSystem::Windows, true, true) => vec!["-Wl,/force:unresolved,/noentry"],
+            (OperatingSystem::Windows, true, false) => vec!["-Wl,/noentry"],
+            (OperatingSystem::Windows, false, true) => vec!["-Wl,-undefined,dynamic_lookup"],
+            (OperatingSystem::Windows, false, false) => vec![],
+            // Android too: the module needs nothing from the `crtbegin_so.o`
+            // of the NDK.
+            (_, _, true) => vec!["-nostartfiles", "-Wl,-undefined,dynamic_lookup"],
+            (_, _, false) => vec!["-nostartfiles"],
+        };
+        // ELF linkers allow undefined symbols in shared objects unless
+        // told otherwise.
+        let no_undefined_args = match target_triple.binary_format {
+            BinaryFormat::Elf if !engine_inner.dynamic_lookup() => vec!["-Wl,--no-undefined"],
+            _ => vec![],
+        };
+        let apple_args: Vec<String> = match target_triple.operating_system {
+            OperatingSystem::Darwin | OperatingSystem::Ios | OperatingSystem::MacOSX { .. } => {
+                engine_inner
+                    .macos_install_name()
+                    .map(|install_name| format!("-Wl,-install_name,{}", install_name))
+                    .into_iter()
+                    .chain(
+                        engine_inner
+                            .macos_rpaths()
+                            .iter()
+                            .map(|rpath| format!("-Wl,-rpath,{}", rpath)),
+                    )
+                    .collect()
+            }
+            _ => vec![],
+        };
+        let soname_args: Vec<String> = match target_triple.binary_format {
+            BinaryFormat::Elf => {
+                let soname = engine_inner
+                    .elf_soname()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| Self::default_elf_soname(&metadata.prefix));
+                vec![format!("-Wl,-soname,{}", soname)]
+            }
+            _ => vec![],
+        };
+        // The references between the symbols of the shared object are bound
+        // to its own definitions, so that several versions of a module
+        // (with the same symbol names) can be loaded at once, even if one
+        // of them is loaded with `RTLD_GLOBAL`. Mach-O (with its two-level
+        // namespace) and PE already bind them like that.
+        let symbolic_args = match target_triple.binary_format {
+            BinaryFormat::Elf => vec!["-Wl,-Bsymbolic"],
+            _ => vec![],
+        };
+        let sanitizer_args: Vec<String> = match engine_inner.sanitizers() {
+            [] => vec![],
+            sanitizers => vec![format!("-fsanitize={}", sanitizers.join(","))],
         };
         trace!(
             "Compiling for target {} from host {}",
@@ -363,52 +2106,439 @@ impl DylibArtifact {
             Triple::host().to_string(),
         );
 
+        // No libc is linked for musl targets (the libcalls are resolved
+        // from the host process at load time, like on other targets), so
+        // the shared objects don't depend on the libc of the build host
+        // and load on musl-based systems. The cross-compiling arguments
+        // already exclude it.
+        let musl_args = match (target_triple.environment, is_cross_compiling) {
+            (Environment::Musl, false) => vec!["-nostdlib", "-nodefaultlibs"],
+            _ => vec![],
+        };
+
+        // The Android loader rejects text relocations.
         let notext = match (target_triple.operating_system, target_triple.architecture) {
-            (OperatingSystem::Linux, Architecture::X86_64) => vec!["-Wl,-z,notext"],
+            (OperatingSystem::Linux, Architecture::X86_64) if !is_android(target_triple) => {
+                vec!["-Wl,-z,notext"]
+            }
+            _ => vec![],
+        };
+
+        // The loader of older Android versions only supports the SysV
+        // hash table, and recent devices can use 16 KiB pages.
+        let android_args = if is_android(target_triple) {
+            vec!["-Wl,--hash-style=both", "-Wl,-z,max-page-size=16384"]
+        } else {
+            vec![]
+        };
+        let build_id_args = match target_triple.binary_format {
+            BinaryFormat::Elf if engine_inner.build_id() => vec!["-Wl,--build-id"],
+            _ => vec![],
+        };
+
+        // On Windows, optionally generate the import library of the `.dll`.
+        let import_library = match target_triple.operating_system {
+            OperatingSystem::Windows if engine_inner.windows_import_library() => {
+                Some(output_filepath.with_extension("lib"))
+            }
+            _ => None,
+        };
+        let import_library_args: Vec<String> = match &import_library {
+            Some(import_library) if target_triple.environment == Environment::Msvc => {
+                vec![format!("-Wl,/implib:{}", import_library.display())]
+            }
+            Some(import_library) => {
+                vec![format!("-Wl,--out-implib,{}", import_library.display())]
+            }
+            None => vec![],
+        };
+
+        let link_profile = engine_inner.link_profile();
+        let link_profile_args = match (link_profile, target_triple.binary_format) {
+            (LinkProfile::Hardened, BinaryFormat::Elf) => {
+                vec!["-Wl,-z,noexecstack", "-Wl,-z,relro", "-Wl,-z,now"]
+            }
+            (LinkProfile::Debug, BinaryFormat::Elf) => vec!["-g", "-Wl,--eh-frame-hdr"],
+            (LinkProfile::Debug, _) => vec!["-g"],
             _ => vec![],
         };
 
-        let linker = engine_inner.linker().executable();
-        let output = Command::new(linker)
-            .arg(&filepath)
-            .arg("-o")
-            .arg(&output_filepath)
-            .args(&target_args)
-            // .args(&wasmer_symbols)
-            .arg("-shared")
-            .args(&notext)
-            .args(&cross_compiling_args)
-            .arg("-v")
-            .output()
-            .map_err(to_compile_error);
+        // Only export the symbols needed to load the artifact (unless
+        // debugging, where all the symbols are useful).
+        let hide_symbols =
+            engine_inner.export_required_symbols_only() && link_profile != LinkProfile::Debug;
+        let version_script = match target_triple.binary_format {
+            BinaryFormat::Elf if hide_symbols => {
+                let mut file = create_temp_file(".map")?;
+                let integrity_symbol = if engine_inner.seals_artifacts() {
+                    Some(INTEGRITY_SYMBOL)
+                } else {
+                    None
+                };
+                let data_symbols = std::iter::once(metadata_symbol)
+                    .chain(data_initializers_symbol)
+                    .chain(integrity_symbol)
+                    .collect::<Vec<_>>();
+                file.write_all(Self::version_script(&metadata, &data_symbols).as_bytes())
+                    .map_err(to_compile_error)?;
+                Some(file)
+            }
+            _ => None,
+        };
+        let version_script_args: Vec<String> = version_script
+            .iter()
+            .map(|file| format!("-Wl,--version-script={}", file.path().display()))
+            .collect();
+
+        let (linker, linker_command_args) = match engine_inner.linker_command() {
+            Some([program, args @ ..]) => (OsString::from(program), args.to_vec()),
+            _ => match engine_inner.android_ndk_clang() {
+                Some(clang) if is_android(target_triple) => (clang.into_os_string(), vec![]),
+                _ => (OsString::from(engine_inner.linker().executable()), vec![]),
+            },
+        };
+        if linker.is_empty() && is_android(target_triple) {
+            return Err(CompileError::Codegen(
+                "Need the Android NDK (see `DylibEngine::set_android_ndk`) or `clang` installed \
+                 to link for Android"
+                    .to_string(),
+            ));
+        }
+        if linker.is_empty() {
+            let is_musl = target_triple.environment == Environment::Musl;
+            return Err(CompileError::Codegen(format!(
+                "Need {} installed (or a linker set with `DylibEngine::set_linker`) to link \
+                 when {}cross-compiling",
+                Linker::requirements(is_cross_compiling, is_musl),
+                if is_cross_compiling { "" } else { "not " }
+            )));
+        }
+        let linking_start = Instant::now();
+        let mut args: Vec<OsString> = vec![filepath.as_os_str().to_owned()];
+        args.extend(runtime_object.map(|path| path.as_os_str().to_owned()));
+        args.push("-o".into());
+        args.push(output_filepath.as_os_str().to_owned());
+        args.extend(target_args.iter().map(OsString::from));
+        args.extend(no_undefined_args.iter().map(OsString::from));
+        args.extend(apple_args.iter().map(OsString::from));
+        args.extend(soname_args.iter().map(OsString::from));
+        args.extend(symbolic_args.iter().map(OsString::from));
+        args.extend(import_library_args.iter().map(OsString::from));
+        args.push("-shared".into());
+        args.extend(musl_args.iter().map(OsString::from));
+        args.extend(notext.iter().map(OsString::from));
+        args.extend(android_args.iter().map(OsString::from));
+        args.extend(build_id_args.iter().map(OsString::from));
+        args.extend(link_profile_args.iter().map(OsString::from));
+        args.extend(version_script_args.iter().map(OsString::from));
+        args.extend(sanitizer_args.iter().map(OsString::from));
+        args.extend(cross_compiling_args.iter().map(OsString::from));
+        args.extend(engine_inner.extra_link_args().iter().map(OsString::from));
+        args.push("-v".into());
+        // Long arguments are passed in a response file, which must be
+        // kept until the linker is done.
+        let response_file = linker_response_file(&args)?;
+        let mut command = Command::new(linker);
+        command.args(&linker_command_args);
+        match &response_file {
+            Some(response_file) => {
+                let mut arg = OsString::from("@");
+                arg.push(response_file.path());
+                command.arg(arg)
+            }
+            None => command.args(&args),
+        };
+        drop(engine_inner);
+        let output = run_linker(command, cancellation).map_err(to_compile_error);
+        drop(response_file);
+        metrics.linking = linking_start.elapsed();
+        let mut engine_inner = engine.inner_mut();
 
         if fs::metadata(&filepath).is_ok() {
             fs::remove_file(filepath).map_err(to_compile_error)?;
         }
 
-        let output = output?;
+        let output = match output? {
+            Some((output, linker_usage)) => {
+                metrics.linker_usage = linker_usage;
+                output
+            }
+            None => {
+                let _ = fs::remove_file(&output_filepath);
+                return Err(CompileError::Cancelled);
+            }
+        };
 
         if !output.status.success() {
+            // The linker may have left a partial output behind.
+            let _ = fs::remove_file(&output_filepath);
+            engine_inner.counters().record_link_failure();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let unresolved = stderr.contains("undefined") || stderr.contains("unresolved");
+            if !engine_inner.dynamic_lookup() && unresolved {
+                return Err(CompileError::Codegen(format!(
+                    "Some symbols are unresolved, which is an error with dynamic lookup \
+                     disabled (see `DylibEngine::set_dynamic_lookup`):\nstderr:{}",
+                    stderr.trim_end()
+                )));
+            }
             return Err(CompileError::Codegen(format!(
                 "Shared object file generator failed with:\nstderr:{}\nstdout:{}",
-                String::from_utf8_lossy(&output.stderr).trim_end(),
+                stderr.trim_end(),
                 String::from_utf8_lossy(&output.stdout).trim_end()
             )));
         }
 
         trace!("gcc command result {:?}", output);
 
-        let mut artifact = if is_cross_compiling {
+        // The linker can exit successfully while producing an empty or
+        // truncated file (e.g. when the disk is full), which would only
+        // fail later with a confusing error when loading it.
+        let shared_object_size = fs::metadata(&output_filepath)
+            .map_err(to_compile_error)?
+            .len();
+        if shared_object_size < MIN_SHARED_OBJECT_BYTES {
+            let _ = fs::remove_file(&output_filepath);
+            engine_inner.counters().record_link_failure();
+            return Err(CompileError::Codegen(format!(
+                "The linker produced a shared object of only {} bytes at `{}` (is the disk full?)",
+                shared_object_size,
+                output_filepath.display()
+            )));
+        }
+
+        let mut shared_object_bytes = None;
+        if engine_inner.build_id() {
+            let bytes = fs::read(&output_filepath).map_err(to_compile_error)?;
+            metadata.build_id = Self::read_build_id(&bytes);
+            shared_object_bytes = Some(bytes);
+        }
+
+        let debug_info = match target_triple.binary_format {
+            BinaryFormat::Elf if engine_inner.split_debug_info() => {
+                match Self::split_debug_info(&output_filepath) {
+                    Ok(debug_info) => {
+                        // The debug info was stripped from the shared object.
+                        shared_object_bytes = None;
+                        Some(debug_info)
+                    }
+                    Err(err) => {
+                        let _ = fs::remove_file(&output_filepath);
+                        return Err(err);
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        // The shared object is sealed once it's final, in place.
+        if engine_inner.seals_artifacts() {
+            let sealed = match shared_object_bytes.take() {
+                Some(bytes) => Ok(bytes),
+                None => fs::read(&output_filepath).map_err(to_compile_error),
+            }
+            .and_then(|mut bytes| {
+                Self::seal(&engine_inner, &mut bytes)?;
+                fs::write(&output_filepath, &bytes).map_err(to_compile_error)?;
+                Ok(bytes)
+            });
+            match sealed {
+                Ok(bytes) => shared_object_bytes = Some(bytes),
+                Err(err) => {
+                    let _ = fs::remove_file(&output_filepath);
+                    return Err(err);
+                }
+            }
+        }
+
+        let shared_object_bytes = match shared_object_bytes {
+            Some(bytes) => bytes,
+            None => fs::read(&output_filepath).map_err(to_compile_error)?,
+        };
+        let unwind_sections = Self::has_unwind_sections(&shared_object_bytes);
+        if let Some(serialized) = options.serialized {
+            *serialized = shared_object_bytes;
+        }
+
+        // Code using CPU features missing on the host can't be run here,
+        // so it's handled like cross-compiled code.
+        let can_load = !is_cross_compiling && Self::check_cpu_features(&metadata).is_ok();
+        let mut artifact = if !can_load {
             Self::from_parts_crosscompiled(metadata, output_filepath)
         } else {
             let lib = unsafe { Library::new(&output_filepath).map_err(to_compile_error)? };
             Self::from_parts(&mut engine_inner, metadata, output_filepath, lib)
         }?;
         artifact.is_temporary = true;
+        artifact.unwind_sections = unwind_sections;
+        artifact.import_library = import_library.filter(|path| path.exists());
+        artifact.debug_info = debug_info;
+        artifact.object = object;
+        engine_inner.counters().record_compile();
+        if metrics_slowest_functions.is_some() {
+            artifact.compilation_metrics = Some(metrics);
+        }
 
         Ok(artifact)
     }
 
+    /// Replace the code of the local function `index` with `body` (with
+    /// the given relocations), without recompiling the module.
+    ///
+    /// The object file is emitted again from the compiled code kept in
+    /// the artifact (see `DylibEngine::set_incremental_linking`), with
+    /// only that function changed, and linked into a new artifact. This
+    /// artifact is left untouched, so the instances created from it keep
+    /// running the old code.
+    ///
+    /// The engine must be the one that compiled the artifact. The
+    /// replaced function has no trap information, so traps in it are
+    /// reported without a source location.
+    #[cfg(feature = "compiler")]
+    pub fn replace_function(
+        &self,
+        engine: &DylibEngine,
+        index: LocalFunctionIndex,
+        body: FunctionBody,
+        relocations: Vec<Relocation>,
+    ) -> Result<Self, CompileError> {
+        let mut compilation = self.compilation.clone().ok_or_else(|| {
+            CompileError::Codegen(
+                "The compiled code of the artifact was not kept \
+                 (see `DylibEngine::set_incremental_linking`)"
+                    .to_string(),
+            )
+        })?;
+        if index.index() >= compilation.len() {
+            return Err(CompileError::Codegen(format!(
+                "The module has no local function {}",
+                index.index()
+            )));
+        }
+        let target_triple = engine.target().triple();
+        if *target_triple != self.target_triple {
+            return Err(CompileError::Codegen(format!(
+                "The artifact was compiled for `{}`, but the engine targets `{}`",
+                self.target_triple, target_triple
+            )));
+        }
+        let function = compilation.get_mut(index);
+        function.body = body;
+        function.relocations = relocations;
+        function.frame_info = CompiledFunctionFrameInfo::default();
+
+        let engine_inner = engine.inner_mut();
+        let mut metrics = CompilationMetrics::default();
+        let mut metadata = self.embedded_metadata();
+        let metadata_binary = Self::encode_metadata_binary(&mut metadata)?;
+        let emission_start = Instant::now();
+        let (obj_bytes, relocation_records) = Self::write_object_file(
+            &engine_inner,
+            target_triple,
+            &metadata,
+            &metadata_binary,
+            compilation.clone(),
+            &self.metadata.component_adapters,
+        )?;
+        metrics.emission = emission_start.elapsed();
+        drop(engine_inner);
+        let mut artifact = Self::link_object(
+            engine,
+            target_triple,
+            metadata,
+            obj_bytes,
+            metrics,
+            CompileOptions::default(),
+        )?;
+        artifact.relocation_records = relocation_records;
+        artifact.compilation = Some(compilation);
+        Ok(artifact)
+    }
+
+    /// Split the debug info of the `.so` at `path` into a `.debug` file
+    /// next to it, leaving the `.so` stripped with a debug link to it
+    /// (see `DylibEngine::set_split_debug_info`).
+    #[cfg(feature = "compiler")]
+    fn split_debug_info(path: &Path) -> Result<PathBuf, CompileError> {
+        let debug_info = path.with_extension("debug");
+        let mut debug_link = OsString::from("--add-gnu-debuglink=");
+        debug_link.push(&debug_info);
+        let commands: [&[&OsStr]; 2] = [
+            &[
+                OsStr::new("--only-keep-debug"),
+                path.as_os_str(),
+                debug_info.as_os_str(),
+            ],
+            &[OsStr::new("--strip-debug"), &debug_link, path.as_os_str()],
+        ];
+        for args in commands.iter() {
+            let output = Command::new("objcopy").args(*args).output();
+            let failure = match output {
+                Ok(output) if output.status.success() => continue,
+                Ok(output) => String::from_utf8_lossy(&output.stderr)
+                    .trim_end()
+                    .to_string(),
+                Err(err) => err.to_string(),
+            };
+            let _ = fs::remove_file(&debug_info);
+            return Err(CompileError::Codegen(format!(
+                "Splitting the debug info with `objcopy` failed with: {}",
+                failure
+            )));
+        }
+        Ok(debug_info)
+    }
+
+    /// The soname of the `.so`s generated for modules with the given
+    /// prefix, unless set on the engine.
+    #[cfg(feature = "compiler")]
+    fn default_elf_soname(prefix: &str) -> String {
+        if prefix.is_empty() {
+            "libwasmer_module.so".to_string()
+        } else {
+            format!("libwasmer_{}.so", prefix)
+        }
+    }
+
+    /// Generate an ELF version script exporting only the symbols needed
+    /// to load the artifact: the data symbols (like the metadata), the
+    /// functions and the trampolines.
+    #[cfg(feature = "compiler")]
+    fn version_script(metadata: &ModuleMetadata, data_symbols: &[&str]) -> String {
+        let symbol_registry = metadata.get_symbol_registry();
+        let module = &metadata.compile_info.module;
+        let symbols = metadata
+            .function_body_lengths
+            .keys()
+            .map(Symbol::LocalFunction)
+            .chain(module.signatures.keys().map(Symbol::FunctionCallTrampoline))
+            .chain(
+                module
+                    .functions
+                    .keys()
+                    .take(module.num_imported_functions)
+                    .map(Symbol::DynamicFunctionTrampoline),
+            );
+        let mut script = "{\n  global:\n".to_string();
+        for data_symbol in data_symbols {
+            script.push_str(&format!("    {};\n", data_symbol));
+        }
+        for symbol in symbols {
+            script.push_str(&format!(
+                "    {};\n",
+                symbol_registry.symbol_to_name(symbol)
+            ));
+        }
+        for index in 0..metadata.component_adapters.len() {
+            script.push_str(&format!(
+                "    {};\n",
+                symbol_registry.component_adapter_symbol_name(index)
+            ));
+        }
+        script.push_str("  local: *;\n};\n");
+        script
+    }
+
     /// Get the default extension when serializing this artifact
     pub fn get_default_extension(triple: &Triple) -> &'static str {
         match triple.operating_system {
@@ -431,6 +2561,8 @@ impl DylibArtifact {
         let finished_dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBodyPtr> =
             PrimaryMap::new();
         let signatures: PrimaryMap<SignatureIndex, VMSharedSignatureIndex> = PrimaryMap::new();
+        let component_adapter_trampolines = Vec::new();
+        let target_triple = Self::parse_target_triple(&metadata)?;
         Ok(Self {
             dylib_path,
             is_temporary: false,
@@ -441,75 +2573,149 @@ impl DylibArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             func_data_registry: Arc::new(FuncDataRegistry::new()),
-            signatures: signatures.into_boxed_slice(),
+            signatures: signatures_cell(signatures.into_boxed_slice()),
+            deferred_signature_registry: None,
             frame_info_registration: Mutex::new(None),
+            unwind_sections: false,
+            library: None,
+            loaded_object: None,
+            object: None,
+            #[cfg(unix)]
+            mapped_data_initializers: None,
+            compilation_metrics: None,
+            import_library: None,
+            debug_info: None,
+            relocation_records: None,
+            component_adapter_trampolines,
+            target_triple,
+            compilation: None,
+        })
+    }
+
+    /// Parse the target triple recorded in the metadata.
+    fn parse_target_triple(metadata: &ModuleMetadata) -> Result<Triple, CompileError> {
+        Triple::from_str(&metadata.target_triple).map_err(|err| {
+            CompileError::Codegen(format!(
+                "Invalid target triple `{}` in the metadata: {}",
+                metadata.target_triple, err
+            ))
         })
     }
 
     /// Construct a `DylibArtifact` from component parts.
+    ///
+    /// The module signatures are registered in the engine signature
+    /// registry in a single transaction: the registry lock is taken once
+    /// for all of them, so the same signature always gets the same
+    /// `VMSharedSignatureIndex` even when several artifacts are loaded
+    /// concurrently, and on failure no signature is registered.
+    ///
+    /// If the engine defers the signature registration, it happens on
+    /// the first instantiation (or call to `signatures`) instead.
     pub fn from_parts(
         engine_inner: &mut DylibEngineInner,
         metadata: ModuleMetadata,
         dylib_path: PathBuf,
         lib: Library,
     ) -> Result<Self, CompileError> {
+        let module = &metadata.compile_info.module;
+        let metadata_symbol = engine_inner.metadata_symbol().to_string();
+        let resolver_path = dylib_path.clone();
+        let mut resolver = SymbolResolver::new(
+            &lib,
+            &resolver_path,
+            &metadata_symbol,
+            metadata.function_body_lengths.len()
+                + module.signatures.len()
+                + module.num_imported_functions
+                + metadata.component_adapters.len(),
+        );
+        let mut artifact = Self::from_symbols(engine_inner, metadata, dylib_path, &mut |name| {
+            resolver.resolve(name)
+        })?;
+        let library = LoadedCode::new(lib);
+        engine_inner.add_library(library.clone());
+        artifact.library = Some(library);
+        Ok(artifact)
+    }
+
+    /// Construct a `DylibArtifact` from an object file loaded in process
+    /// (see `DylibEngine::set_in_process_linking`).
+    fn from_loaded_object(
+        engine_inner: &mut DylibEngineInner,
+        metadata: ModuleMetadata,
+        dylib_path: PathBuf,
+        loaded_object: LoadedObject,
+    ) -> Result<Self, CompileError> {
+        let mut artifact = Self::from_symbols(engine_inner, metadata, dylib_path, &mut |name| {
+            loaded_object.symbol(name).ok_or_else(|| {
+                CompileError::Codegen(format!(
+                    "Can't find the symbol `{}` in the loaded object file",
+                    name
+                ))
+            })
+        })?;
+        let loaded_object = LoadedCode::new(loaded_object);
+        engine_inner.add_loaded_object(loaded_object.clone());
+        artifact.loaded_object = Some(loaded_object);
+        Ok(artifact)
+    }
+
+    /// Construct a `DylibArtifact` whose symbols are resolved by
+    /// `resolve`, without the code it points into.
+    fn from_symbols(
+        engine_inner: &mut DylibEngineInner,
+        metadata: ModuleMetadata,
+        dylib_path: PathBuf,
+        resolve: &mut dyn FnMut(&str) -> Result<usize, CompileError>,
+    ) -> Result<Self, CompileError> {
+        let module = &metadata.compile_info.module;
+        let num_imported_functions = module.num_imported_functions;
+        let symbol_registry = metadata.get_symbol_registry();
+
         let mut finished_functions: PrimaryMap<LocalFunctionIndex, FunctionBodyPtr> =
-            PrimaryMap::new();
-        for (function_local_index, _function_len) in metadata.function_body_lengths.iter() {
-            let function_name = metadata
-                .get_symbol_registry()
-                .symbol_to_name(Symbol::LocalFunction(function_local_index));
-            unsafe {
-                // We use a fake function signature `fn()` because we just
-                // want to get the function address.
-                let func: LibrarySymbol<unsafe extern "C" fn()> = lib
-                    .get(function_name.as_bytes())
-                    .map_err(to_compile_error)?;
-                finished_functions.push(FunctionBodyPtr(
-                    func.into_raw().into_raw() as *const VMFunctionBody
-                ));
-            }
+            PrimaryMap::with_capacity(metadata.function_body_lengths.len());
+        for function_local_index in metadata.function_body_lengths.keys() {
+            let function_name =
+                symbol_registry.symbol_to_name(Symbol::LocalFunction(function_local_index));
+            let address = resolve(&function_name)?;
+            finished_functions.push(FunctionBodyPtr(address as *const VMFunctionBody));
         }
 
         // Retrieve function call trampolines
         let mut finished_function_call_trampolines: PrimaryMap<SignatureIndex, VMTrampoline> =
-            PrimaryMap::with_capacity(metadata.compile_info.module.signatures.len());
-        for sig_index in metadata.compile_info.module.signatures.keys() {
-            let function_name = metadata
-                .get_symbol_registry()
-                .symbol_to_name(Symbol::FunctionCallTrampoline(sig_index));
-            unsafe {
-                let trampoline: LibrarySymbol<VMTrampoline> = lib
-                    .get(function_name.as_bytes())
-                    .map_err(to_compile_error)?;
-                let raw = *trampoline.into_raw();
-                finished_function_call_trampolines.push(raw);
-            }
+            PrimaryMap::with_capacity(module.signatures.len());
+        for sig_index in module.signatures.keys() {
+            let function_name =
+                symbol_registry.symbol_to_name(Symbol::FunctionCallTrampoline(sig_index));
+            let address = resolve(&function_name)?;
+            finished_function_call_trampolines
+                .push(unsafe { std::mem::transmute::<usize, VMTrampoline>(address) });
         }
 
-        // Retrieve dynamic function trampolines (only for imported functions)
+        // Retrieve dynamic function trampolines (only for imported
+        // functions, so modules without imports have none to look up).
         let mut finished_dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBodyPtr> =
-            PrimaryMap::with_capacity(metadata.compile_info.module.num_imported_functions);
-        for func_index in metadata
-            .compile_info
-            .module
-            .functions
-            .keys()
-            .take(metadata.compile_info.module.num_imported_functions)
-        {
-            let function_name = metadata
-                .get_symbol_registry()
-                .symbol_to_name(Symbol::DynamicFunctionTrampoline(func_index));
-            unsafe {
-                let trampoline: LibrarySymbol<unsafe extern "C" fn()> = lib
-                    .get(function_name.as_bytes())
-                    .map_err(to_compile_error)?;
-                finished_dynamic_function_trampolines.push(FunctionBodyPtr(
-                    trampoline.into_raw().into_raw() as *const VMFunctionBody,
-                ));
-            }
+            PrimaryMap::with_capacity(num_imported_functions);
+        for func_index in module.functions.keys().take(num_imported_functions) {
+            let function_name =
+                symbol_registry.symbol_to_name(Symbol::DynamicFunctionTrampoline(func_index));
+            let address = resolve(&function_name)?;
+            finished_dynamic_function_trampolines
+                .push(FunctionBodyPtr(address as *const VMFunctionBody));
         }
 
+        // Retrieve the component adapter trampolines (none for core
+        // modules).
+        let component_adapter_trampolines = (0..metadata.component_adapters.len())
+            .map(|index| {
+                let name = symbol_registry.component_adapter_symbol_name(index);
+                let address = resolve(&name)?;
+                Ok(FunctionBodyPtr(address as *const VMFunctionBody))
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+        let target_triple = Self::parse_target_triple(&metadata)?;
+
         // Leaving frame infos from now, as they are not yet used
         // however they might be useful for the future.
         // let frame_infos = compilation
@@ -525,18 +2731,24 @@ impl DylibArtifact {
         // );
 
         // Compute indices into the shared signature table.
-        let signatures = {
-            metadata
-                .compile_info
-                .module
-                .signatures
-                .values()
-                .map(|sig| engine_inner.signatures().register(sig))
-                .collect::<PrimaryMap<_, _>>()
+        //
+        // All the signatures are registered at once while holding the
+        // registry lock, so artifacts loaded concurrently can't observe
+        // a partially registered module, and a failure registers nothing.
+        let (signatures, deferred_signature_registry) = if engine_inner
+            .defer_signature_registration()
+        {
+            (
+                OnceCell::new(),
+                Some(engine_inner.signature_registry().clone()),
+            )
+        } else {
+            let signatures =
+                Self::register_signatures(engine_inner.signatures(), &metadata.compile_info.module)
+                    .ok_or_else(|| CompileError::Resource(SIGNATURE_REGISTRY_FULL.to_string()))?;
+            (signatures_cell(signatures), None)
         };
 
-        engine_inner.add_library(lib);
-
         Ok(Self {
             dylib_path,
             is_temporary: false,
@@ -547,11 +2759,53 @@ impl DylibArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             func_data_registry: engine_inner.func_data().clone(),
-            signatures: signatures.into_boxed_slice(),
+            signatures,
+            deferred_signature_registry,
             frame_info_registration: Mutex::new(None),
+            unwind_sections: false,
+            library: None,
+            loaded_object: None,
+            object: None,
+            #[cfg(unix)]
+            mapped_data_initializers: None,
+            compilation_metrics: None,
+            import_library: None,
+            debug_info: None,
+            relocation_records: None,
+            component_adapter_trampolines,
+            target_triple,
+            compilation: None,
         })
     }
 
+    /// Register the signatures of `module` in `registry`, all at once.
+    fn register_signatures(
+        registry: &SignatureRegistry,
+        module: &ModuleInfo,
+    ) -> Option<BoxedSlice<SignatureIndex, VMSharedSignatureIndex>> {
+        let signatures = registry.register_all(module.signatures.values())?;
+        Some(
+            signatures
+                .into_iter()
+                .collect::<PrimaryMap<SignatureIndex, _>>()
+                .into_boxed_slice(),
+        )
+    }
+
+    /// Get the shared signature indices, registering the signatures
+    /// first if their registration was deferred.
+    fn registered_signatures(&self) -> Option<&BoxedSlice<SignatureIndex, VMSharedSignatureIndex>> {
+        self.signatures
+            .get_or_try_init(|| {
+                let registry = self
+                    .deferred_signature_registry
+                    .as_ref()
+                    .expect("the signatures are either registered or deferred");
+                Self::register_signatures(registry, &self.metadata.compile_info.module).ok_or(())
+            })
+            .ok()
+    }
+
     /// Compile a data buffer into a `DylibArtifact`, which may
     /// then be instantiated.
     #[cfg(not(feature = "compiler"))]
@@ -561,8 +2815,20 @@ impl DylibArtifact {
         ))
     }
 
+    /// Whether `bytes` are a relocatable object file rather than a
+    /// shared object.
+    fn is_relocatable_object(bytes: &[u8]) -> bool {
+        object::File::parse(bytes)
+            .map(|file| file.kind() == object::ObjectKind::Relocatable)
+            .unwrap_or(false)
+    }
+
     /// Deserialize a `DylibArtifact` from bytes.
     ///
+    /// The metadata is extracted from the object file before it is
+    /// loaded, so bytes that were not generated by Wasmer are rejected
+    /// before `dlopen` gets to run any of their code.
+    ///
     /// # Safety
     ///
     /// The bytes must represent a serialized WebAssembly module.
@@ -570,42 +2836,409 @@ impl DylibArtifact {
         engine: &DylibEngine,
         bytes: &[u8],
     ) -> Result<Self, DeserializeError> {
+        let result = Self::deserialize_uncounted(engine, bytes);
+        engine
+            .inner()
+            .counters()
+            .record_deserialization(result.is_ok());
+        result
+    }
+
+    /// Deserialize a `DylibArtifact` from a portable container (see
+    /// `serialize_portable`).
+    ///
+    /// The target triple recorded in the container is checked against
+    /// the host before the shared object is looked at.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must represent a serialized WebAssembly module.
+    pub unsafe fn deserialize_portable(
+        engine: &DylibEngine,
+        bytes: &[u8],
+    ) -> Result<Self, DeserializeError> {
+        let result =
+            Self::split_portable(bytes).and_then(|_| Self::deserialize_uncounted(engine, bytes));
+        engine
+            .inner()
+            .counters()
+            .record_deserialization(result.is_ok());
+        result
+    }
+
+    /// The implementation of `deserialize`.
+    unsafe fn deserialize_uncounted(
+        engine: &DylibEngine,
+        bytes: &[u8],
+    ) -> Result<Self, DeserializeError> {
+        let bytes = if bytes.starts_with(Self::MAGIC_HEADER_PORTABLE) {
+            let (target_triple, object_bytes) = Self::split_portable(bytes)?;
+            let host_triple = Triple::host().to_string();
+            if target_triple != host_triple {
+                return Err(DeserializeError::Incompatible(format!(
+                    "The artifact was compiled for `{}`, but the host is `{}`",
+                    target_triple, host_triple
+                )));
+            }
+            object_bytes
+        } else {
+            bytes
+        };
         if !Self::is_deserializable(&bytes) {
             return Err(DeserializeError::Incompatible(
                 "The provided bytes are not in any native format Wasmer can understand".to_string(),
             ));
         }
+        Self::check_integrity(engine, &bytes)?;
+        let metadata_symbol = engine.inner().metadata_symbol().to_string();
+        let metadata = Self::metadata_from_object_bytes(&bytes, &metadata_symbol)?;
+        Self::check_cpu_features(&metadata)?;
+        let is_relocatable_object = Self::is_relocatable_object(&bytes);
+        if is_relocatable_object && !engine.inner().in_process_linking() {
+            return Err(DeserializeError::Incompatible(
+                "The artifact is an object file, which is only loaded with in-process linking \
+                 (see `DylibEngine::set_in_process_linking`)"
+                    .to_string(),
+            ));
+        }
         // Dump the bytes into a file, so we can read it with our `dlopen`
-        let named_file = NamedTempFile::new()?;
+        let named_file = tempfile::Builder::new()
+            .prefix(TEMP_FILE_PREFIX)
+            .tempfile()?;
         let (mut file, path) = named_file.keep().map_err(|e| e.error)?;
         file.write_all(&bytes)?;
-        // We already checked for the header, so we don't need
-        // to check again.
-        let mut artifact = Self::deserialize_from_file_unchecked(&engine, &path)?;
+        if is_relocatable_object {
+            // Artifacts linked in process are serialized as object files
+            // (see `DylibEngine::set_in_process_linking`).
+            drop(file);
+            let mut artifact = LoadedObject::load(&bytes)
+                .and_then(|loaded_object| {
+                    Self::from_loaded_object(
+                        &mut engine.inner_mut(),
+                        metadata,
+                        path.clone(),
+                        loaded_object,
+                    )
+                })
+                .map_err(|error| {
+                    let _ = fs::remove_file(&path);
+                    DeserializeError::CorruptedBinary(error.to_string())
+                })?;
+            artifact.is_temporary = true;
+            artifact.unwind_sections = Self::has_unwind_sections(&bytes);
+            return Ok(artifact);
+        }
+        let mut artifact = Self::load(&engine, metadata, &path)?;
+        if artifact.is_temporary {
+            // It was loaded from a copy of the file.
+            let _ = fs::remove_file(&path);
+        }
         artifact.is_temporary = true;
+        artifact.unwind_sections = Self::has_unwind_sections(&bytes);
+
+        Ok(artifact)
+    }
+
+    /// Deserialize a `DylibArtifact` from a file path.
+    ///
+    /// The metadata is extracted from the object file before it is
+    /// loaded, so files that were not generated by Wasmer are rejected
+    /// before `dlopen` gets to run any of their code.
+    ///
+    /// On Unix, the file is mapped instead of being read, and the data
+    /// initializers are borrowed from the mapping instead of being copied
+    /// into the metadata (so they are missing from `metadata`, and only
+    /// copied if `Artifact::data_initializers` is called), which cuts the
+    /// loading time of modules with large data segments. The file must
+    /// not be modified while the artifact is alive.
+    ///
+    /// The function body lengths are still read from the symbol table of
+    /// the file, as the lengths in the metadata are placeholders written
+    /// before the functions are compiled.
+    ///
+    /// # Safety
+    ///
+    /// The file's content must represent a serialized WebAssembly module.
+    pub unsafe fn deserialize_from_file(
+        engine: &DylibEngine,
+        path: &Path,
+    ) -> Result<Self, DeserializeError> {
+        let result = Self::deserialize_from_file_uncounted(engine, path);
+        engine
+            .inner()
+            .counters()
+            .record_deserialization(result.is_ok());
+        result
+    }
+
+    /// The implementation of `deserialize_from_file`.
+    #[cfg(not(unix))]
+    unsafe fn deserialize_from_file_uncounted(
+        engine: &DylibEngine,
+        path: &Path,
+    ) -> Result<Self, DeserializeError> {
+        let bytes = fs::read(&path)?;
+        Self::deserialize_from_file_bytes(engine, &bytes, path)
+    }
+
+    /// The implementation of `deserialize_from_file`.
+    #[cfg(unix)]
+    unsafe fn deserialize_from_file_uncounted(
+        engine: &DylibEngine,
+        path: &Path,
+    ) -> Result<Self, DeserializeError> {
+        let mapping = FileMapping::map(&fs::File::open(&path)?)?;
+        let bytes = mapping.as_slice();
+        // The artifacts whose integrity is checked are checked and loaded
+        // from a private copy, as the mapping changes with the file.
+        if Self::checks_integrity(engine, bytes) {
+            return Self::deserialize_uncounted(engine, &bytes.to_vec());
+        }
+        // Portable containers and object files are loaded from a copy
+        // anyway.
+        if bytes.starts_with(Self::MAGIC_HEADER_PORTABLE)
+            || Self::is_relocatable_object(bytes)
+            || !Self::is_deserializable(bytes)
+        {
+            return Self::deserialize_from_file_bytes(engine, bytes, path);
+        }
+        let metadata_symbol = engine.inner().metadata_symbol().to_string();
+        let metadata_binary = Self::find_metadata_binary(bytes, &metadata_symbol)?;
+        let metadata_slice = Self::metadata_slice(metadata_binary)?;
+        let (mut metadata, initializers) =
+            match ModuleMetadata::deserialize_borrowed(metadata_slice)? {
+                Some(borrowed) => borrowed,
+                None => {
+                    let metadata = Self::metadata_from_object_bytes(bytes, &metadata_symbol)?;
+                    Self::check_cpu_features(&metadata)?;
+                    let mut artifact = Self::load(&engine, metadata, path)?;
+                    artifact.unwind_sections = Self::has_unwind_sections(bytes);
+                    return Ok(artifact);
+                }
+            };
+        Self::read_code_lengths(bytes, &mut metadata);
+        metadata.build_id = Self::read_build_id(bytes);
+        Self::check_cpu_features(&metadata)?;
+        // Safety: the data initializers are kept alongside the mapping
+        // they borrow from, and dropped before it.
+        let initializers = std::mem::transmute::<_, Vec<DataInitializer<'static>>>(initializers);
+        let mut artifact = Self::load(&engine, metadata, path)?;
+        artifact.unwind_sections = Self::has_unwind_sections(bytes);
+        artifact.mapped_data_initializers = Some(MappedDataInitializers {
+            initializers,
+            owned: OnceCell::new(),
+            mapping,
+        });
+        Ok(artifact)
+    }
+
+    /// Deserialize a `DylibArtifact` from a file descriptor, for
+    /// sandboxed loaders that can't open the file by its path.
+    ///
+    /// The file is read through the descriptor, and loaded through its
+    /// `/proc/self/fd` (or `/dev/fd` outside of Linux) entry. The file
+    /// descriptor is not closed, and must stay open as long as the
+    /// artifact is used.
+    ///
+    /// # Safety
+    ///
+    /// The file's content must represent a serialized WebAssembly module.
+    #[cfg(unix)]
+    pub unsafe fn deserialize_from_fd(
+        engine: &DylibEngine,
+        fd: RawFd,
+    ) -> Result<Self, DeserializeError> {
+        let result = Self::deserialize_from_fd_uncounted(engine, fd);
+        engine
+            .inner()
+            .counters()
+            .record_deserialization(result.is_ok());
+        result
+    }
 
+    /// The implementation of `deserialize_from_fd`.
+    #[cfg(unix)]
+    unsafe fn deserialize_from_fd_uncounted(
+        engine: &DylibEngine,
+        fd: RawFd,
+    ) -> Result<Self, DeserializeError> {
+        // The file is borrowed: it must not be closed when dropped.
+        let file = ManuallyDrop::new(fs::File::from_raw_fd(fd));
+        // The file offset is left untouched.
+        let mut bytes = vec![0; file.metadata()?.len() as usize];
+        file.read_exact_at(&mut bytes, 0)?;
+        let path = if cfg!(any(target_os = "linux", target_os = "android")) {
+            PathBuf::from(format!("/proc/self/fd/{}", fd))
+        } else {
+            PathBuf::from(format!("/dev/fd/{}", fd))
+        };
+        Self::deserialize_from_file_bytes(engine, &bytes, &path)
+    }
+
+    /// Deserialize a `DylibArtifact` from the contents of the file at
+    /// `path`.
+    unsafe fn deserialize_from_file_bytes(
+        engine: &DylibEngine,
+        bytes: &[u8],
+        path: &Path,
+    ) -> Result<Self, DeserializeError> {
+        if bytes.starts_with(Self::MAGIC_HEADER_PORTABLE)
+            || Self::is_relocatable_object(bytes)
+            || Self::checks_integrity(engine, bytes)
+        {
+            // The shared object must be extracted from the container to
+            // be loaded, object files are loaded in process from a copy,
+            // and the artifacts whose integrity is checked are loaded
+            // from a copy of the checked bytes, not from the file, which
+            // could be changed in between.
+            return Self::deserialize_uncounted(engine, bytes);
+        }
+        if !Self::is_deserializable(bytes) {
+            return Err(DeserializeError::Incompatible(
+                "The provided bytes are not in any native format Wasmer can understand".to_string(),
+            ));
+        }
+        let metadata_symbol = engine.inner().metadata_symbol().to_string();
+        let metadata = Self::metadata_from_object_bytes(bytes, &metadata_symbol)?;
+        Self::check_cpu_features(&metadata)?;
+        let mut artifact = Self::load(&engine, metadata, path)?;
+        artifact.unwind_sections = Self::has_unwind_sections(bytes);
         Ok(artifact)
     }
 
-    /// Deserialize a `DylibArtifact` from a file path.
-    ///
-    /// # Safety
-    ///
-    /// The file's content must represent a serialized WebAssembly module.
-    pub unsafe fn deserialize_from_file(
-        engine: &DylibEngine,
+    /// The range of the integrity record in the bytes of an artifact, if
+    /// it has one (see `DylibEngine::set_integrity_check`).
+    fn integrity_record(bytes: &[u8]) -> Option<Range<usize>> {
+        let record = Self::find_metadata_binary(bytes, INTEGRITY_SYMBOL).ok()?;
+        if record.len() < INTEGRITY_RECORD_LEN {
+            return None;
+        }
+        // The contents of the symbol are borrowed from `bytes`.
+        let start = record.as_ptr() as usize - bytes.as_ptr() as usize;
+        Some(start..start + INTEGRITY_RECORD_LEN)
+    }
+
+    /// Fill the integrity record of an artifact with its hash (see
+    /// `DylibEngine::set_integrity_check`).
+    #[cfg(feature = "compiler")]
+    fn seal(engine_inner: &DylibEngineInner, bytes: &mut [u8]) -> Result<(), CompileError> {
+        let record = Self::integrity_record(bytes).ok_or_else(|| {
+            CompileError::Codegen("The integrity record is missing from the artifact".to_string())
+        })?;
+        integrity::seal(bytes, record, engine_inner.artifact_signer());
+        Ok(())
+    }
+
+    /// Whether the integrity of an artifact is checked before it's
+    /// loaded: if it has an integrity record, or the engine requires one
+    /// (see `DylibEngine::set_integrity_check`).
+    fn checks_integrity(engine: &DylibEngine, bytes: &[u8]) -> bool {
+        engine.inner().requires_integrity() || Self::integrity_record(bytes).is_some()
+    }
+
+    /// Check the bytes of an artifact against its integrity record,
+    /// before it's loaded (see `DylibEngine::set_integrity_check`).
+    fn check_integrity(engine: &DylibEngine, bytes: &[u8]) -> Result<(), DeserializeError> {
+        let engine_inner = engine.inner();
+        match Self::integrity_record(bytes) {
+            Some(record) => integrity::verify(bytes, record, engine_inner.artifact_verifier()),
+            None if engine_inner.requires_integrity() => {
+                Err(DeserializeError::IntegrityCheckFailed(
+                    "The artifact has no integrity record".to_string(),
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the host has all the CPU features the code of an
+    /// artifact was compiled for, as it could crash otherwise.
+    fn check_cpu_features(metadata: &ModuleMetadata) -> Result<(), DeserializeError> {
+        let missing = metadata.cpu_features() - CpuFeature::for_host();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(DeserializeError::Incompatible(format!(
+            "The artifact requires CPU features missing on this host: {}",
+            missing
+                .iter()
+                .map(|feature| feature.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )))
+    }
+
+    /// Load the shared object at `path`, whose metadata was already
+    /// extracted.
+    ///
+    /// If `dlopen` fails (e.g. on filesystems that don't support
+    /// executable mappings, like some NFS mounts), the file is copied to
+    /// a local directory and loaded from there, see
+    /// `DylibEngine::set_load_fallback_dir`. The copy is removed when
+    /// the artifact is dropped, which is then marked as temporary.
+    unsafe fn load(
+        engine: &DylibEngine,
+        metadata: ModuleMetadata,
+        path: &Path,
+    ) -> Result<Self, DeserializeError> {
+        let (lib, path, is_copy) = match Library::new(&path) {
+            Ok(lib) => (lib, PathBuf::from(path), false),
+            Err(error) => {
+                let fallback_dir = engine.inner().load_fallback_dir().map(Path::to_path_buf);
+                let (lib, copy_path) =
+                    Self::load_copy(path, fallback_dir.as_deref()).map_err(|fallback_error| {
+                        DeserializeError::CorruptedBinary(format!(
+                            "Library loading failed: {} (and from a local copy: {})",
+                            error, fallback_error
+                        ))
+                    })?;
+                (lib, copy_path, true)
+            }
+        };
+        let mut engine_inner = engine.inner_mut();
+
+        let artifact = Self::from_parts(&mut engine_inner, metadata, path.clone(), lib);
+        match artifact {
+            Ok(mut artifact) => {
+                artifact.is_temporary = is_copy;
+                Ok(artifact)
+            }
+            Err(error) => {
+                if is_copy {
+                    let _ = fs::remove_file(&path);
+                }
+                Err(DeserializeError::Compiler(error))
+            }
+        }
+    }
+
+    /// Copy the shared object at `path` to a temporary file in `dir`
+    /// (or the system temporary directory) and load it from there.
+    unsafe fn load_copy(
         path: &Path,
-    ) -> Result<Self, DeserializeError> {
-        let mut file = File::open(&path)?;
-        let mut buffer = [0; 5];
-        // read up to 5 bytes
-        file.read_exact(&mut buffer)?;
-        if !Self::is_deserializable(&buffer) {
-            return Err(DeserializeError::Incompatible(
-                "The provided bytes are not in any native format Wasmer can understand".to_string(),
-            ));
+        dir: Option<&Path>,
+    ) -> Result<(Library, PathBuf), Box<dyn Error>> {
+        let suffix = path
+            .extension()
+            .map(|extension| format!(".{}", extension.to_string_lossy()))
+            .unwrap_or_default();
+        let mut builder = tempfile::Builder::new();
+        builder.prefix(TEMP_FILE_PREFIX).suffix(&suffix);
+        let file = match dir {
+            Some(dir) => builder.tempfile_in(dir)?,
+            None => builder.tempfile()?,
+        };
+        let copy_path = file.into_temp_path().keep()?;
+        let lib = fs::copy(path, &copy_path)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|_| Library::new(&copy_path).map_err(Box::<dyn Error>::from));
+        match lib {
+            Ok(lib) => Ok((lib, copy_path)),
+            Err(error) => {
+                let _ = fs::remove_file(&copy_path);
+                Err(error)
+            }
         }
-        Self::deserialize_from_file_unchecked(&engine, &path)
     }
 
     /// Deserialize a `DylibArtifact` from a file path (unchecked).
@@ -613,10 +3246,34 @@ impl DylibArtifact {
     /// # Safety
     ///
     /// The file's content must represent a serialized WebAssembly module.
+    /// The file is loaded with `dlopen` right away, without checking that
+    /// it was generated by Wasmer first, nor its integrity: engines that
+    /// require it (see `DylibEngine::set_integrity_check`) refuse to
+    /// deserialize the files this way.
     pub unsafe fn deserialize_from_file_unchecked(
         engine: &DylibEngine,
         path: &Path,
     ) -> Result<Self, DeserializeError> {
+        let result = Self::deserialize_from_file_unchecked_uncounted(engine, path);
+        engine
+            .inner()
+            .counters()
+            .record_deserialization(result.is_ok());
+        result
+    }
+
+    /// The implementation of `deserialize_from_file_unchecked`.
+    unsafe fn deserialize_from_file_unchecked_uncounted(
+        engine: &DylibEngine,
+        path: &Path,
+    ) -> Result<Self, DeserializeError> {
+        if engine.inner().requires_integrity() {
+            return Err(DeserializeError::IntegrityCheckFailed(
+                "The integrity of the artifacts is required, which \
+                 `deserialize_from_file_unchecked` doesn't check"
+                    .to_string(),
+            ));
+        }
         let lib = Library::new(&path).map_err(|e| {
             DeserializeError::CorruptedBinary(format!("Library loading failed: {}", e))
         })?;
@@ -625,17 +3282,26 @@ impl DylibArtifact {
         // (we construct it like that in `metadata_length`) and we also want
         // to take the first element of the data to construct the slice from
         // it.
-        let symbol: LibrarySymbol<*mut [u8; 12 + 1]> =
-            lib.get(WASMER_METADATA_SYMBOL).map_err(|e| {
-                DeserializeError::CorruptedBinary(format!(
-                    "The provided object file doesn't seem to be generated by Wasmer: {}",
-                    e
-                ))
-            })?;
-        use std::ops::Deref;
+        let metadata_symbol = engine.inner().metadata_symbol().to_string();
+        let metadata_address = match lib.get::<*mut u8>(metadata_symbol.as_bytes()) {
+            Ok(symbol) => *symbol as usize,
+            // The metadata symbol may be versioned, which `dlsym` doesn't
+            // find under its plain name: look it up in the symbol table.
+            Err(e) => Self::symbol_addresses(&lib, path, &metadata_symbol)
+                .ok()
+                .and_then(|addresses| addresses.get(&metadata_symbol).copied())
+                .ok_or_else(|| {
+                    DeserializeError::CorruptedBinary(format!(
+                        "The provided object file doesn't seem to be generated by Wasmer: \
+                         tried `dlsym` on `{}` ({}) and the symbol table with symbol \
+                         versions stripped",
+                        metadata_symbol, e
+                    ))
+                })?,
+        };
         use std::slice;
 
-        let size = &mut **symbol.deref();
+        let size = &*(metadata_address as *const [u8; 12 + 1]);
         let mut readable = &size[..];
         let metadata_len = leb128::read::unsigned(&mut readable).map_err(|_e| {
             DeserializeError::CorruptedBinary("Can't read metadata size".to_string())
@@ -644,6 +3310,7 @@ impl DylibArtifact {
             slice::from_raw_parts(&size[12] as *const u8, metadata_len as usize);
 
         let metadata = ModuleMetadata::deserialize(metadata_slice)?;
+        Self::check_cpu_features(&metadata)?;
 
         let mut engine_inner = engine.inner_mut();
 
@@ -651,10 +3318,825 @@ impl DylibArtifact {
             .map_err(DeserializeError::Compiler)
     }
 
+    /// Read the `ModuleMetadata` of a shared object generated by this
+    /// engine, without loading it.
+    ///
+    /// The object file is parsed to locate the metadata, so (unlike
+    /// `dlopen`) no code from the artifact is executed. The metadata is
+    /// looked up under the default `WASMER_METADATA` symbol name.
+    ///
+    /// # Safety
+    ///
+    /// The metadata archive itself is not validated, so the file must
+    /// have been generated by this engine.
+    pub unsafe fn read_metadata(path: &Path) -> Result<ModuleMetadata, DeserializeError> {
+        let bytes = fs::read(path)?;
+        Self::read_metadata_from_bytes(&bytes)
+    }
+
+    /// Read the `ModuleMetadata` of a serialized artifact, like
+    /// `read_metadata`, from its bytes in memory.
+    ///
+    /// Neither a temporary file nor `dlopen` is needed, so this is a
+    /// cheap way to inspect artifacts received over the network.
+    ///
+    /// # Safety
+    ///
+    /// The metadata archive itself is not validated, so the bytes must
+    /// have been generated by this engine.
+    pub unsafe fn read_metadata_from_bytes(
+        bytes: &[u8],
+    ) -> Result<ModuleMetadata, DeserializeError> {
+        Self::metadata_from_object_bytes(bytes, DEFAULT_METADATA_SYMBOL)
+    }
+
+    /// The byte range of the metadata in a serialized artifact (the
+    /// contents of its `WASMER_METADATA` symbol), to decode with
+    /// `read_metadata_from_range`.
+    ///
+    /// This lets clients fetch only the metadata of a large artifact
+    /// stored remotely, e.g. with an HTTP range request. Locating it
+    /// needs the headers and the symbol table of the object file, which
+    /// are spread over it (the ELF section headers are at its end), so
+    /// the range is meant to be computed when the artifact is stored,
+    /// and stored alongside it.
+    pub fn metadata_range(bytes: &[u8]) -> Result<Range<u64>, DeserializeError> {
+        let metadata_binary = Self::find_metadata_binary(bytes, DEFAULT_METADATA_SYMBOL)?;
+        // The metadata binary is borrowed from `bytes`.
+        let start = metadata_binary.as_ptr() as usize - bytes.as_ptr() as usize;
+        let mut readable = metadata_binary;
+        let metadata_len = leb128::read::unsigned(&mut readable).map_err(|_e| {
+            DeserializeError::CorruptedBinary("Can't read metadata size".to_string())
+        })?;
+        let end = (start as u64)
+            .checked_add(12)
+            .and_then(|end| end.checked_add(metadata_len))
+            .filter(|end| *end <= bytes.len() as u64)
+            .ok_or_else(|| {
+                DeserializeError::CorruptedBinary("The metadata is truncated".to_string())
+            })?;
+        Ok(start as u64..end)
+    }
+
+    /// Read the `ModuleMetadata` of a serialized artifact from the bytes
+    /// in its `metadata_range`, without the rest of the artifact.
+    ///
+    /// The code lengths and the build-id are read from the symbol table
+    /// and the notes of the shared object, so they are left empty.
+    ///
+    /// # Safety
+    ///
+    /// The metadata archive itself is not validated, so the bytes must
+    /// have been generated by this engine.
+    pub unsafe fn read_metadata_from_range(
+        bytes: &[u8],
+    ) -> Result<ModuleMetadata, DeserializeError> {
+        Self::decode_metadata_binary(bytes)
+    }
+
+    /// Extract and decode the `ModuleMetadata` from the bytes of a
+    /// shared object generated by this engine.
+    unsafe fn metadata_from_object_bytes(
+        bytes: &[u8],
+        metadata_symbol: &str,
+    ) -> Result<ModuleMetadata, DeserializeError> {
+        let metadata_binary = Self::find_metadata_binary(bytes, metadata_symbol)?;
+        let mut metadata = Self::decode_metadata_binary(metadata_binary)?;
+        Self::read_code_lengths(bytes, &mut metadata);
+        metadata.build_id = Self::read_build_id(bytes);
+        Ok(metadata)
+    }
+
+    /// Fill the code lengths of the metadata from the sizes of the
+    /// symbols of an object file or shared object.
+    ///
+    /// Symbols without a size (e.g. in Mach-O files) are counted as
+    /// empty.
+    fn read_code_lengths(bytes: &[u8], metadata: &mut ModuleMetadata) {
+        let file = match object::File::parse(bytes) {
+            Ok(file) => file,
+            Err(_) => return,
+        };
+        // The same symbol can be both in the symbol table and the
+        // dynamic symbol table.
+        let sizes = file
+            .symbols()
+            .chain(file.dynamic_symbols())
+            .filter_map(|symbol| {
+                // Mach-O symbols are prefixed with an underscore.
+                let name = symbol.name().ok()?;
+                Some((name.strip_prefix('_').unwrap_or(name), symbol.size()))
+            })
+            .collect::<HashMap<_, _>>();
+        let symbol_registry = metadata.get_symbol_registry();
+        let mut function_body_lengths = metadata.function_body_lengths.clone();
+        let mut trampolines_length = 0;
+        let mut custom_sections_length = 0;
+        for (name, size) in sizes {
+            match symbol_registry.name_to_symbol(name) {
+                Some(Symbol::LocalFunction(index)) => {
+                    if let Some(length) = function_body_lengths.get_mut(index) {
+                        *length = size;
+                    }
+                }
+                Some(Symbol::FunctionCallTrampoline(_))
+                | Some(Symbol::DynamicFunctionTrampoline(_)) => trampolines_length += size,
+                Some(Symbol::Section(_)) => custom_sections_length += size,
+                None => {}
+            }
+        }
+        metadata.function_body_lengths = function_body_lengths;
+        metadata.trampolines_length = trampolines_length;
+        metadata.custom_sections_length = custom_sections_length;
+    }
+
+    /// Read the build-id of an ELF shared object, if it has one.
+    fn read_build_id(bytes: &[u8]) -> Option<Vec<u8>> {
+        let file = object::File::parse(bytes).ok()?;
+        file.build_id().ok()?.map(<[u8]>::to_vec)
+    }
+
+    /// The addresses of the symbols of a loaded shared object, read from
+    /// its symbol and dynamic symbol tables at once, including the ones
+    /// that are not exported.
+    ///
+    /// Symbol versions (`name@VERSION` or `name@@VERSION`, as given by a
+    /// version script with version nodes) are stripped from the names,
+    /// so that versioned symbols are found under their plain name even
+    /// when `dlsym` can't look them up.
+    ///
+    /// The addresses are relative to the metadata symbol or, if `dlsym`
+    /// can't find it, to the first exported symbol it finds. This only
+    /// works for shared objects that keep a symbol table, like the ELF
+    /// and Mach-O ones generated by this engine.
+    fn symbol_addresses(
+        lib: &Library,
+        path: &Path,
+        metadata_symbol: &str,
+    ) -> Result<HashMap<String, usize>, CompileError> {
+        let bytes = fs::read(path).map_err(to_compile_error)?;
+        let file = object::File::parse(bytes.as_slice()).map_err(to_compile_error)?;
+        let symbol_name = |symbol: &object::Symbol| -> Option<String> {
+            // Mach-O symbols are prefixed with an underscore.
+            let name = symbol.name().ok()?;
+            let name = name.strip_prefix('_').unwrap_or(name);
+            Some(strip_symbol_version(name).to_string())
+        };
+        let mut addresses = HashMap::new();
+        for symbol in file
+            .symbols()
+            .chain(file.dynamic_symbols())
+            .filter(|symbol| !symbol.is_undefined())
+        {
+            if let Some(name) = symbol_name(&symbol) {
+                addresses.entry(name).or_insert_with(|| symbol.address());
+            }
+        }
+        // Only the exported symbols are tried besides the metadata one:
+        // `dlsym` could find a local symbol under the same name in a
+        // dependency of the shared object.
+        let exported = file
+            .dynamic_symbols()
+            .filter(|symbol| !symbol.is_undefined())
+            .filter_map(|symbol| symbol_name(&symbol));
+        let base = std::iter::once(metadata_symbol.to_string())
+            .chain(exported)
+            .find_map(|name| {
+                let address = *addresses.get(&name)?;
+                let loaded_address = unsafe {
+                    let symbol: LibrarySymbol<*const u8> = lib.get(name.as_bytes()).ok()?;
+                    *symbol as usize
+                };
+                Some(loaded_address.wrapping_sub(address as usize))
+            })
+            .ok_or_else(|| {
+                CompileError::Codegen(format!(
+                    "None of the symbols of `{}` can be found with `dlsym`, \
+                     tried `{}` and the exported symbols",
+                    path.display(),
+                    metadata_symbol
+                ))
+            })?;
+        Ok(addresses
+            .into_iter()
+            .map(|(name, address)| (name, base.wrapping_add(address as usize)))
+            .collect())
+    }
+
+    /// Find the contents of the metadata symbol in the bytes of an
+    /// object file.
+    fn find_metadata_binary<'a>(
+        bytes: &'a [u8],
+        metadata_symbol: &str,
+    ) -> Result<&'a [u8], DeserializeError> {
+        let metadata_symbol = metadata_symbol.as_bytes();
+        let corrupted = |message: &str| DeserializeError::CorruptedBinary(message.to_string());
+        let file = object::File::parse(bytes)
+            .map_err(|e| corrupted(&format!("Can't parse the object file: {}", e)))?;
+        let symbol = file
+            .symbols()
+            .chain(file.dynamic_symbols())
+            .find(|symbol| {
+                // Mach-O symbols are prefixed with an underscore.
+                let name = symbol.name().unwrap_or_default().as_bytes();
+                name == metadata_symbol || name.strip_prefix(b"_") == Some(metadata_symbol)
+            })
+            .ok_or_else(|| {
+                corrupted("The provided object file doesn't seem to be generated by Wasmer")
+            })?;
+        let section = symbol
+            .section_index()
+            .and_then(|index| file.section_by_index(index).ok())
+            .ok_or_else(|| corrupted("The metadata symbol is not defined in a section"))?;
+        let data = section
+            .data()
+            .map_err(|e| corrupted(&format!("Can't read the metadata section: {}", e)))?;
+        symbol
+            .address()
+            .checked_sub(section.address())
+            .and_then(|offset| data.get(offset as usize..))
+            .ok_or_else(|| corrupted("The metadata symbol is out of its section bounds"))
+    }
+
+    /// Read the data initializers stored in the given symbol of an
+    /// artifact (see `DylibEngine::set_data_initializers_symbol`),
+    /// without decoding the module metadata.
+    pub fn read_data_initializers(
+        bytes: &[u8],
+        data_initializers_symbol: &str,
+    ) -> Result<Vec<OwnedDataInitializer>, DeserializeError> {
+        let binary = Self::find_metadata_binary(bytes, data_initializers_symbol)?;
+        decode_data_initializers(binary).ok_or_else(|| {
+            DeserializeError::CorruptedBinary("Can't read the data initializers".to_string())
+        })
+    }
+
+    /// Encode the metadata as the contents of the metadata symbol (a
+    /// 12-byte LEB128 length slot followed by the metadata).
+    fn encode_metadata_binary(metadata: &mut ModuleMetadata) -> Result<Vec<u8>, CompileError> {
+        let serialized_data = metadata.serialize()?;
+
+        let mut metadata_binary = vec![0; 12];
+        let mut writable = &mut metadata_binary[..];
+        leb128::write::unsigned(&mut writable, serialized_data.len() as u64)
+            .expect("Should write number");
+        metadata_binary.extend(serialized_data);
+        Ok(metadata_binary)
+    }
+
+    /// Decode the metadata from the contents of the metadata symbol
+    /// (a 12-byte LEB128 length slot followed by the metadata).
+    unsafe fn decode_metadata_binary(
+        metadata_binary: &[u8],
+    ) -> Result<ModuleMetadata, DeserializeError> {
+        ModuleMetadata::deserialize_unaligned(Self::metadata_slice(metadata_binary)?)
+    }
+
+    /// The serialized metadata in the contents of the metadata symbol,
+    /// after its length slot.
+    fn metadata_slice(metadata_binary: &[u8]) -> Result<&[u8], DeserializeError> {
+        let mut readable = metadata_binary;
+        let metadata_len = leb128::read::unsigned(&mut readable).map_err(|_e| {
+            DeserializeError::CorruptedBinary("Can't read metadata size".to_string())
+        })?;
+        metadata_binary
+            .get(12..)
+            .and_then(|rest| rest.get(..metadata_len as usize))
+            .ok_or_else(|| {
+                DeserializeError::CorruptedBinary("The metadata is truncated".to_string())
+            })
+    }
+
+    /// The compilation information of the module: the module itself,
+    /// its features and its memory and table styles.
+    pub fn compile_info(&self) -> &CompileModuleInfo {
+        &self.metadata.compile_info
+    }
+
+    /// Whether two artifacts were compiled from the same module with the
+    /// same settings, without comparing their shared objects.
+    ///
+    /// The prefixes (usually a hash of the module, see
+    /// `DylibEngine::set_deterministic_prefixer`) are compared first, so
+    /// different modules are usually told apart right away.
+    pub fn same_module(&self, other: &Self) -> bool {
+        let (metadata, other_metadata) = (&self.metadata, &other.metadata);
+        metadata.prefix == other_metadata.prefix
+            && metadata.cpu_features == other_metadata.cpu_features
+            && metadata.compile_info == other_metadata.compile_info
+            && self
+                .data_initializer_views()
+                .iter()
+                .map(|initializer| (&initializer.location, initializer.data))
+                .eq(other
+                    .data_initializer_views()
+                    .iter()
+                    .map(|initializer| (&initializer.location, initializer.data)))
+    }
+
+    /// The data initializers of the module, whether they are in the
+    /// metadata or borrowed from a mapped file.
+    fn data_initializer_views(&self) -> Vec<DataInitializer<'_>> {
+        #[cfg(unix)]
+        {
+            if let Some(mapped) = &self.mapped_data_initializers {
+                return mapped
+                    .initializers
+                    .iter()
+                    .map(|initializer| DataInitializer {
+                        location: initializer.location.clone(),
+                        data: initializer.data,
+                    })
+                    .collect();
+            }
+        }
+        self.metadata
+            .data_initializers
+            .iter()
+            .map(|initializer| DataInitializer {
+                location: initializer.location.clone(),
+                data: &*initializer.data,
+            })
+            .collect()
+    }
+
+    /// The shared signature index of the signature `index` of the
+    /// module, to invoke its functions through their trampolines (see
+    /// `Artifact::finished_function_call_trampolines`).
+    ///
+    /// This registers the signatures first if their registration was
+    /// deferred, and returns `None` if `index` is out of bounds or the
+    /// signature registry is full.
+    pub fn shared_signature(&self, index: SignatureIndex) -> Option<VMSharedSignatureIndex> {
+        self.registered_signatures()?.get(index).copied()
+    }
+
+    /// The component-model adapters of the lowered imports of the
+    /// module (see `DylibArtifact::new_with_component_adapters`).
+    #[cfg(feature = "component-model")]
+    pub fn component_adapters(&self) -> &[ComponentAdapter] {
+        &self.metadata.component_adapters
+    }
+
+    /// The trampoline of the component adapter named `name`, if any.
+    ///
+    /// It's called like the dynamic function trampoline of the adapted
+    /// import (see `Artifact::finished_dynamic_function_trampolines`).
+    /// Cross-compiled artifacts have no trampolines.
+    #[cfg(feature = "component-model")]
+    pub fn component_adapter_trampoline(&self, name: &str) -> Option<FunctionBodyPtr> {
+        let index = self
+            .metadata
+            .component_adapters
+            .iter()
+            .position(|adapter| adapter.name == name)?;
+        self.component_adapter_trampolines.get(index).copied()
+    }
+
+    /// Whether the artifact has usable frame info, so traps in its
+    /// functions can be symbolicated: its code has unwind or debug info
+    /// sections (`.eh_frame`, `__eh_frame`, `.debug_frame` or
+    /// `.debug_info`), and its frame info is registered.
+    ///
+    /// The frame info is registered when the artifact is instantiated
+    /// (see `Artifact::register_frame_info`). It never is for artifacts
+    /// whose shared object isn't loaded (cross-compiled or object-only
+    /// artifacts), nor for artifacts without any local function. The
+    /// sections are not looked for in artifacts loaded with
+    /// `deserialize_from_file_unchecked`, which never have frame info.
+    pub fn has_frame_info(&self) -> bool {
+        self.unwind_sections && self.frame_info_registration.lock().unwrap().is_some()
+    }
+
+    /// Whether an object file or shared object has unwind or debug info
+    /// sections.
+    fn has_unwind_sections(bytes: &[u8]) -> bool {
+        const SECTIONS: [&str; 4] = [".eh_frame", "__eh_frame", ".debug_frame", ".debug_info"];
+        match object::File::parse(bytes) {
+            Ok(file) => file.sections().any(|section| {
+                section
+                    .name()
+                    .map_or(false, |name| SECTIONS.contains(&name))
+            }),
+            Err(_) => false,
+        }
+    }
+
+    /// The total size in bytes of the native code of the artifact: its
+    /// functions, trampolines and custom sections.
+    ///
+    /// This is the executable memory the artifact takes once loaded. It
+    /// is 0 for artifacts loaded with `deserialize_from_file_unchecked`,
+    /// whose file is not parsed.
+    pub fn code_size(&self) -> u64 {
+        self.metadata.function_body_lengths.values().sum::<u64>()
+            + self.metadata.trampolines_length
+            + self.metadata.custom_sections_length
+    }
+
+    /// Resolve a runtime address (e.g. the instruction pointer of a
+    /// trap) to the local function containing it, and its offset in the
+    /// function body.
+    pub fn resolve_address(&self, addr: *const u8) -> Option<(LocalFunctionIndex, usize)> {
+        let addr = addr as usize;
+        self.finished_functions
+            .iter()
+            .zip(self.metadata.function_body_lengths.values())
+            .find_map(|((index, function), length)| {
+                let offset = addr.checked_sub(function.0 as usize)?;
+                if offset < *length as usize {
+                    Some((index, offset))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// The address and length in bytes of the native code of each local
+    /// function, e.g. to build a symbolizer.
+    ///
+    /// This is empty for cross-compiled artifacts, which are not loaded,
+    /// and the lengths are 0 for artifacts loaded with
+    /// `deserialize_from_file_unchecked`, whose file is not parsed.
+    ///
+    /// The pointers are only valid while the artifact (and so its shared
+    /// object) is alive: they must not be dereferenced after it's
+    /// dropped. The code is mapped read-only and executable, so it must
+    /// never be written through them.
+    pub fn function_ranges(&self) -> Vec<(LocalFunctionIndex, *const u8, usize)> {
+        self.finished_functions
+            .iter()
+            .zip(self.metadata.function_body_lengths.values())
+            .map(|((index, function), length)| (index, function.0 as *const u8, *length as usize))
+            .collect()
+    }
+
+    /// The relocations written to the object file, if enabled on the
+    /// engine (see `DylibEngine::set_record_relocations`).
+    ///
+    /// This is `None` for compilers that emit the object file
+    /// themselves.
+    pub fn relocation_records(&self) -> Option<&[RelocationRecord]> {
+        self.relocation_records.as_deref()
+    }
+
+    /// The path of the import library (`.lib`) generated alongside the
+    /// `.dll`, if any (see `DylibEngine::set_windows_import_library`).
+    pub fn import_library_path(&self) -> Option<&Path> {
+        self.import_library.as_deref()
+    }
+
+    /// The path of the debug info split from the `.so`, if any (see
+    /// `DylibEngine::set_split_debug_info`).
+    pub fn debug_info_path(&self) -> Option<&Path> {
+        self.debug_info.as_deref()
+    }
+
+    /// The path of the shared object of the artifact, or of its object
+    /// file if the engine only generates object files (see
+    /// `DylibEngine::set_object_only`).
+    ///
+    /// The files of compiled artifacts are temporary and removed when
+    /// the artifact is dropped, use `serialize_to_file` to keep them.
+    pub fn path(&self) -> &Path {
+        &self.dylib_path
+    }
+
+    /// The relocatable object file of this artifact, before it was linked
+    /// into a shared object, to link it into another binary (e.g. to
+    /// embed the module statically in the host).
+    ///
+    /// The object file defines the symbols named by `symbol_name`, and the
+    /// metadata symbol of the engine. Its undefined symbols are the
+    /// libcalls of the runtime, which the final binary must provide.
+    ///
+    /// The object file of linked artifacts is only available if kept (see
+    /// `DylibEngine::set_keep_object_files`).
+    pub fn emit_object(&self) -> Result<Vec<u8>, SerializeError> {
+        if let Some(object) = &self.object {
+            return Ok(object.clone());
+        }
+        let bytes = fs::read(&self.dylib_path)?;
+        if Self::is_relocatable_object(&bytes) {
+            return Ok(bytes);
+        }
+        Err(SerializeError::Generic(format!(
+            "The object file of `{}` was not kept (see `DylibEngine::set_keep_object_files`)",
+            self.dylib_path.display()
+        )))
+    }
+
+    /// The name of the symbol of `symbol` in the object file and shared
+    /// object of this artifact.
+    pub fn symbol_name(&self, symbol: Symbol) -> String {
+        self.metadata.get_symbol_registry().symbol_to_name(symbol)
+    }
+
+    /// The names of the symbols of the exported functions defined by the
+    /// module, by export name.
+    pub fn exported_function_symbols(&self) -> Vec<(String, String)> {
+        let module = &self.metadata.compile_info.module;
+        let symbol_registry = self.metadata.get_symbol_registry();
+        module
+            .exports
+            .iter()
+            .filter_map(|(name, export)| match export {
+                ExportIndex::Function(index) => module
+                    .local_func_index(*index)
+                    .map(|index| (name.clone(), index)),
+                _ => None,
+            })
+            .map(|(name, index)| {
+                (
+                    name,
+                    symbol_registry.symbol_to_name(Symbol::LocalFunction(index)),
+                )
+            })
+            .collect()
+    }
+
+    /// The target triple the artifact was compiled for.
+    pub fn target_triple(&self) -> &Triple {
+        &self.target_triple
+    }
+
+    /// The C-ABI wrappers of the exported functions of the module (see
+    /// `DylibEngine::set_c_abi_export_prefix`).
+    pub fn c_abi_exports(&self) -> &[CAbiExport] {
+        &self.metadata.c_abi_exports
+    }
+
+    /// The build-id of the shared object, if it was linked with one
+    /// (see `DylibEngine::set_build_id`).
+    pub fn build_id(&self) -> Option<Vec<u8>> {
+        self.metadata.build_id.clone()
+    }
+
     /// Used in test deserialize metadata is correct
+    ///
+    /// The data initializers are missing from the metadata of artifacts
+    /// deserialized from a file on Unix (see `deserialize_from_file`),
+    /// but not from `Artifact::data_initializers`.
     pub fn metadata(&self) -> &ModuleMetadata {
         &self.metadata
     }
+
+    /// Serialize this artifact into a portable container: a magic
+    /// header, the target triple it was compiled for (its length as a
+    /// little-endian `u32`, then its UTF-8 bytes) and the shared object.
+    ///
+    /// Unlike the bare shared object, the container records the exact
+    /// target, so loaders can check the compatibility of the artifact
+    /// before loading it. It's deserialized with `deserialize` or
+    /// `deserialize_portable`, which check the target against the host.
+    pub fn serialize_portable(&self) -> Result<Vec<u8>, SerializeError> {
+        let target_triple = self.metadata.target_triple.as_bytes();
+        let object_bytes = self.serialize()?;
+        let mut bytes = Vec::with_capacity(
+            Self::MAGIC_HEADER_PORTABLE.len() + 4 + target_triple.len() + object_bytes.len(),
+        );
+        bytes.extend_from_slice(Self::MAGIC_HEADER_PORTABLE);
+        bytes.extend_from_slice(&(target_triple.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(target_triple);
+        bytes.extend(object_bytes);
+        Ok(bytes)
+    }
+
+    /// The metadata as embedded in the shared object (the contents of
+    /// the metadata symbol, see `DylibEngine::set_metadata_symbol`): a
+    /// 12-byte LEB128 length slot followed by the serialized metadata.
+    ///
+    /// The metadata is serialized again from this artifact, which lets
+    /// tools check that the embedded metadata matches it. The code
+    /// lengths are not part of the embedded metadata (they are read from
+    /// the symbol sizes instead), so they are left out.
+    pub fn metadata_bytes(&self) -> Result<Vec<u8>, SerializeError> {
+        Self::encode_metadata_binary(&mut self.embedded_metadata())
+            .map_err(|e| SerializeError::Generic(e.to_string()))
+    }
+
+    /// A copy of the metadata as embedded in the shared object, without
+    /// the code lengths.
+    fn embedded_metadata(&self) -> ModuleMetadata {
+        let metadata = &self.metadata;
+        let compile_info = &metadata.compile_info;
+        ModuleMetadata {
+            compile_info: CompileModuleInfo {
+                features: compile_info.features.clone(),
+                module: compile_info.module.clone(),
+                memory_styles: compile_info.memory_styles.clone(),
+                table_styles: compile_info.table_styles.clone(),
+            },
+            prefix: metadata.prefix.clone(),
+            data_initializers: self
+                .data_initializer_views()
+                .iter()
+                .map(OwnedDataInitializer::new)
+                .collect(),
+            function_body_lengths: metadata.function_body_lengths.keys().map(|_| 0).collect(),
+            trampolines_length: 0,
+            custom_sections_length: 0,
+            cpu_features: metadata.cpu_features,
+            target_triple: metadata.target_triple.clone(),
+            component_adapters: metadata.component_adapters.clone(),
+            symbol_naming: metadata.symbol_naming.clone(),
+            c_abi_exports: metadata.c_abi_exports.clone(),
+            build_id: None,
+        }
+    }
+
+    /// Whether the code of this artifact is loaded, as a shared object or
+    /// in process (cross-compiled artifacts are never loaded).
+    fn is_loaded(&self) -> bool {
+        self.library.is_some() || self.loaded_object.is_some()
+    }
+
+    /// Prefault the code of this artifact into memory.
+    ///
+    /// The pages of a freshly loaded shared object are only faulted in
+    /// when first executed, which causes a latency spike on the first
+    /// call of each function. This touches every page of the compiled
+    /// code upfront, so latency-sensitive callers can pay that cost
+    /// ahead of time.
+    ///
+    /// This is a no-op for cross-compiled artifacts.
+    pub fn warm(&self) -> Result<(), InstantiationError> {
+        if !self.is_loaded() {
+            return Ok(());
+        }
+        // The code is touched one function at a time: the functions and
+        // trampolines may be spread over several sections or mappings
+        // (e.g. the stubs of objects loaded in process), with unmapped
+        // gaps between them.
+        let page_size = page_size();
+        let touch = |start: usize, len: usize| {
+            let end = start.saturating_add(len.max(1));
+            let mut address = start;
+            while address < end {
+                // Safety: the address is in the code of the function.
+                unsafe { std::ptr::read_volatile(address as *const u8) };
+                // The next page boundary.
+                address = match (address - address % page_size).checked_add(page_size) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        };
+        for (function, length) in self
+            .finished_functions
+            .values()
+            .zip(self.metadata.function_body_lengths.values())
+        {
+            touch(**function as usize, *length as usize);
+        }
+        // The lengths of the trampolines are not known, but they are
+        // small enough to fit in a page or two.
+        for trampoline in self.finished_dynamic_function_trampolines.values() {
+            touch(**trampoline as usize, 1);
+        }
+        for trampoline in self.finished_function_call_trampolines.values() {
+            touch(*trampoline as usize, 1);
+        }
+        Ok(())
+    }
+
+    /// Check that this artifact can be instantiated, without
+    /// instantiating it.
+    ///
+    /// This checks that the shared object (or object file loaded in
+    /// process) is loaded, that every function and trampoline of the
+    /// module still resolves from it to the address it was resolved to
+    /// on load, inside its mapped code, and that the signatures of the functions and their registration in
+    /// the engine are consistent with the module. Nothing is allocated
+    /// or registered: if the signature registration is deferred (see
+    /// `DylibEngine::set_defer_signature_registration`) and didn't
+    /// happen yet, it's left to the instantiation, which may still fail
+    /// if the signature registry is full. The imports are not checked
+    /// either, as they are only known on instantiation.
+    pub fn validate_instantiable(&self) -> Result<(), InstantiationError> {
+        fn error(message: String) -> InstantiationError {
+            InstantiationError::Link(LinkError::Trap(RuntimeError::new(message)))
+        }
+
+        if !self.is_loaded() {
+            return Err(error(format!(
+                "The artifact at `{}` is not loaded (it was compiled for another target)",
+                self.dylib_path.display()
+            )));
+        }
+        // The functions and trampolines are resolved again: they must be
+        // where they were resolved when the artifact was loaded, in its
+        // code.
+        let symbol_registry = self.metadata.get_symbol_registry();
+        let symbols =
+            self.finished_functions
+                .iter()
+                .map(|(index, function)| (Symbol::LocalFunction(index), **function as usize))
+                .chain(
+                    self.finished_function_call_trampolines
+                        .iter()
+                        .map(|(index, trampoline)| {
+                            (Symbol::FunctionCallTrampoline(index), *trampoline as usize)
+                        }),
+                )
+                .chain(self.finished_dynamic_function_trampolines.iter().map(
+                    |(index, trampoline)| {
+                        (
+                            Symbol::DynamicFunctionTrampoline(index),
+                            **trampoline as usize,
+                        )
+                    },
+                ))
+                .map(|(symbol, address)| (symbol_registry.symbol_to_name(symbol), address))
+                .chain(self.component_adapter_trampolines.iter().enumerate().map(
+                    |(index, trampoline)| {
+                        (
+                            symbol_registry.component_adapter_symbol_name(index),
+                            **trampoline as usize,
+                        )
+                    },
+                ))
+                .collect::<Vec<_>>();
+        let check = |name: &str, address: usize, resolved: Option<usize>, in_code: bool| {
+            if resolved != Some(address) {
+                return Err(error(format!(
+                    "The symbol `{}` resolved to {:#x}, not {:#x} like when the artifact was \
+                     loaded",
+                    name,
+                    resolved.unwrap_or(0),
+                    address
+                )));
+            }
+            if !in_code {
+                return Err(error(format!(
+                    "The symbol `{}` resolved to {:#x}, outside of the code of the artifact",
+                    name, address
+                )));
+            }
+            Ok(())
+        };
+        if let Some(lib) = &self.library {
+            // The metadata symbol may be custom, but the other exported
+            // symbols are tried too when it's not found.
+            let mut resolver = SymbolResolver::new(
+                lib,
+                &self.dylib_path,
+                DEFAULT_METADATA_SYMBOL,
+                symbols.len(),
+            );
+            let library_base = symbols
+                .first()
+                .and_then(|(_, address)| mapped_object_base(*address));
+            for (name, address) in &symbols {
+                let resolved = resolver.resolve(name).ok();
+                let in_code = *address != 0
+                    && library_base.is_some()
+                    && mapped_object_base(*address) == library_base;
+                check(name, *address, resolved, in_code)?;
+            }
+        } else if let Some(loaded_object) = &self.loaded_object {
+            for (name, address) in &symbols {
+                let resolved = loaded_object.symbol(name);
+                check(name, *address, resolved, loaded_object.contains(*address))?;
+            }
+        }
+        let module = &self.metadata.compile_info.module;
+        if let Some((index, signature)) = module
+            .functions
+            .iter()
+            .find(|(_, signature)| module.signatures.get(**signature).is_none())
+        {
+            return Err(error(format!(
+                "Function {} has the signature {}, which is not in the module",
+                index.index(),
+                signature.index()
+            )));
+        }
+        if let Some(signatures) = self.signatures.get() {
+            if signatures.len() != module.signatures.len() {
+                return Err(error(format!(
+                    "{} of the {} signatures are registered in the engine",
+                    signatures.len(),
+                    module.signatures.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the metrics collected while compiling this artifact, if
+    /// enabled on the engine.
+    ///
+    /// Deserialized artifacts have no compilation metrics.
+    pub fn compilation_metrics(&self) -> Option<&CompilationMetrics> {
+        self.compilation_metrics.as_ref()
+    }
+
+    /// Returns an owned copy of the features this artifact was
+    /// compiled with.
+    pub fn features_owned(&self) -> Features {
+        self.metadata.compile_info.features.clone()
+    }
 }
 
 impl Artifact for DylibArtifact {
@@ -801,9 +4283,36 @@ impl Artifact for DylibArtifact {
     }
 
     fn data_initializers(&self) -> &[OwnedDataInitializer] {
+        // The data initializers borrowed from a mapped file are only
+        // copied when they are asked for here, as instantiating the
+        // artifact uses the borrowed ones.
+        #[cfg(unix)]
+        {
+            if let Some(mapped) = &self.mapped_data_initializers {
+                return mapped.owned.get_or_init(|| {
+                    mapped
+                        .initializers
+                        .iter()
+                        .map(OwnedDataInitializer::new)
+                        .collect()
+                });
+            }
+        }
         &*self.metadata.data_initializers
     }
 
+    unsafe fn finish_instantiation(
+        &self,
+        trap_handler: &dyn TrapHandler,
+        handle: &InstanceHandle,
+    ) -> Result<(), InstantiationError> {
+        // The data initializers may be borrowed from a mapped file rather
+        // than be in the metadata (see `deserialize_from_file`).
+        handle
+            .finish_instantiation(trap_handler, &self.data_initializer_views())
+            .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))
+    }
+
     fn memory_styles(&self) -> &PrimaryMap<MemoryIndex, MemoryStyle> {
         &self.metadata.compile_info.memory_styles
     }
@@ -825,7 +4334,7 @@ impl Artifact for DylibArtifact {
     }
 
     fn signatures(&self) -> &BoxedSlice<SignatureIndex, VMSharedSignatureIndex> {
-        &self.signatures
+        self.registered_signatures().expect(SIGNATURE_REGISTRY_FULL)
     }
 
     fn func_data_registry(&self) -> &FuncDataRegistry {
@@ -833,10 +4342,18 @@ impl Artifact for DylibArtifact {
     }
 
     fn preinstantiate(&self) -> Result<(), InstantiationError> {
+        self.registered_signatures().ok_or_else(|| {
+            InstantiationError::Link(LinkError::Resource(SIGNATURE_REGISTRY_FULL.to_string()))
+        })?;
         Ok(())
     }
 
     /// Serialize a `DylibArtifact`.
+    ///
+    /// The shared object is read into memory (or copied, by
+    /// `serialize_to_file`) while the artifact is borrowed, so the
+    /// serialized artifact stays valid once this one is dropped and its
+    /// temporary file removed.
     fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
         Ok(std::fs::read(&self.dylib_path)?)
     }
@@ -845,37 +4362,51 @@ impl Artifact for DylibArtifact {
     #[cfg(feature = "compiler")]
     fn serialize_to_file(&self, path: &Path) -> Result<(), SerializeError> {
         let serialized = self.serialize()?;
-        std::fs::write(&path, serialized)?;
 
-        /*
-        When you write the artifact to a new file it still has the 'Mach-O Identifier'
-        of the original file, and so this can causes linker issues when adding
-        the new file to an XCode project.
-
-        The below code renames the ID of the file so that it references itself through
-        an @executable_path prefix. Basically it tells XCode to find this file
-        inside of the projects' list of 'linked executables'.
-
-        You need to be running MacOS for the following to actually work though.
-        */
-        let has_extension = path.extension().is_some();
-        if has_extension && path.extension().unwrap() == "dylib" {
-            let filename = path.file_name().unwrap().to_str().unwrap();
-            let parent_dir = path.parent().unwrap();
-            let absolute_path = std::fs::canonicalize(&parent_dir)
-                .unwrap()
-                .into_os_string()
-                .into_string()
-                .unwrap();
-
-            Command::new("install_name_tool")
-                .arg("-id")
-                .arg(format!("@executable_path/{}", &filename))
-                .arg(&filename)
-                .current_dir(&absolute_path)
-                .output()?;
+        // Write to a temporary file next to `path` and rename it into
+        // place once complete, so concurrent readers of `path` (e.g.
+        // another process compiling the same module into a cache) never
+        // see a partially written artifact.
+        let temp_path = temp_path_next_to(path)?;
+        let written = write_and_check(&temp_path, &serialized)
+            .and_then(|()| fix_install_name(path, &temp_path))
+            .and_then(|()| fs::rename(&temp_path, path).map_err(SerializeError::from));
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        written?;
+
+        // Keep the import library next to the `.dll`.
+        if let Some(import_library) = &self.import_library {
+            std::fs::copy(import_library, path.with_extension("lib"))?;
+        }
+
+        // Keep the debug info next to the `.so`, under the name its debug
+        // link refers to.
+        if let Some(debug_info) = &self.debug_info {
+            if let Some(file_name) = debug_info.file_name() {
+                std::fs::copy(debug_info, path.with_file_name(file_name))?;
+            }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbol_versions() {
+        assert_eq!(strip_symbol_version("WASMER_METADATA"), "WASMER_METADATA");
+        assert_eq!(
+            strip_symbol_version("WASMER_METADATA@V1"),
+            "WASMER_METADATA"
+        );
+        assert_eq!(
+            strip_symbol_version("WASMER_METADATA@@V2"),
+            "WASMER_METADATA"
+        );
+    }
+}
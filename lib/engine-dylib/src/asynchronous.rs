@@ -0,0 +1,173 @@
+//! Asynchronous compilation and deserialization of `DylibArtifact`s.
+//!
+//! Compiling a module (and linking it) or loading an artifact blocks the
+//! calling thread, which must not happen on the threads of an async
+//! executor. These functions run them on a thread of their own instead,
+//! and can be awaited from any executor.
+
+use crate::{DylibArtifact, DylibEngine};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+#[cfg(feature = "compiler")]
+use wasmer_compiler::CompileError;
+use wasmer_engine::DeserializeError;
+#[cfg(feature = "compiler")]
+use wasmer_engine::Tunables;
+
+/// The state shared by a blocking function and the future awaiting it.
+struct BlockingState<T> {
+    /// The result of the function, once it returned or panicked.
+    result: Option<thread::Result<T>>,
+    /// The waker of the last poll of the future, if any.
+    waker: Option<Waker>,
+}
+
+/// A future resolving to the result of a blocking function running on
+/// its own thread.
+struct Blocking<T>(Arc<Mutex<BlockingState<T>>>);
+
+impl<T> Future for Blocking<T> {
+    type Output = thread::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Run a blocking function on a thread of its own, resuming its panic
+/// if it panicked.
+async fn run_blocking<F, T>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let state = Arc::new(Mutex::new(BlockingState {
+        result: None,
+        waker: None,
+    }));
+    let thread_state = state.clone();
+    thread::Builder::new()
+        .name("wasmer-dylib-blocking".to_string())
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            let waker = {
+                let mut state = thread_state.lock().unwrap();
+                state.result = Some(result);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        })
+        .map_err(|err| format!("Can't spawn the blocking thread: {}", err))?;
+    match Blocking(state).await {
+        Ok(result) => Ok(result),
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+impl DylibArtifact {
+    /// Compile a data buffer into a `DylibArtifact` like `new`, without
+    /// blocking the async executor.
+    #[cfg(feature = "compiler")]
+    pub async fn new_async<T>(
+        engine: &DylibEngine,
+        data: Vec<u8>,
+        tunables: T,
+    ) -> Result<Self, CompileError>
+    where
+        T: Tunables + Send + 'static,
+    {
+        let engine = engine.clone();
+        run_blocking(move || Self::new(&engine, &data, &tunables))
+            .await
+            .map_err(CompileError::Codegen)?
+    }
+
+    /// Deserialize a `DylibArtifact` from bytes like `deserialize`,
+    /// without blocking the async executor.
+    ///
+    /// # Safety
+    ///
+    /// See `DylibArtifact::deserialize`.
+    pub async unsafe fn deserialize_async(
+        engine: &DylibEngine,
+        bytes: Vec<u8>,
+    ) -> Result<Self, DeserializeError> {
+        let engine = engine.clone();
+        run_blocking(move || Self::deserialize(&engine, &bytes))
+            .await
+            .map_err(DeserializeError::Generic)?
+    }
+
+    /// Deserialize a `DylibArtifact` from a file path like
+    /// `deserialize_from_file`, without blocking the async executor.
+    ///
+    /// # Safety
+    ///
+    /// See `DylibArtifact::deserialize_from_file`.
+    pub async unsafe fn deserialize_from_file_async(
+        engine: &DylibEngine,
+        path: PathBuf,
+    ) -> Result<Self, DeserializeError> {
+        let engine = engine.clone();
+        run_blocking(move || Self::deserialize_from_file(&engine, &path))
+            .await
+            .map_err(DeserializeError::Generic)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::task::Wake;
+
+    /// Run a future to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct ThreadWaker(thread::Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn deserialize_async_runs_on_a_thread() {
+        let engine = DylibEngine::headless();
+        let result = block_on(async {
+            unsafe { DylibArtifact::deserialize_async(&engine, b"not an artifact".to_vec()).await }
+        });
+        assert!(matches!(result, Err(DeserializeError::Incompatible(_))));
+    }
+
+    #[test]
+    #[should_panic(expected = "in the blocking thread")]
+    fn run_blocking_resumes_panics() {
+        let _ = block_on(run_blocking(|| panic!("in the blocking thread")));
+    }
+}
@@ -1,11 +1,66 @@
-use crate::DylibEngine;
-use wasmer_compiler::{CompilerConfig, Features, Target};
+#[cfg(feature = "signing")]
+use crate::integrity::{self, INTEGRITY_KEY_LEN};
+use crate::integrity::{ArtifactSigner, ArtifactVerifier};
+use crate::{
+    DylibEngine, LinkProfile, RelocationModel, SymbolNaming, INTEGRITY_HASH_LEN,
+    INTEGRITY_SIGNATURE_LEN,
+};
+use enumset::EnumSet;
+use std::path::PathBuf;
+use wasmer_compiler::{CompileError, CompilerConfig, CpuFeature, Features, Target};
+use wasmer_types::ModuleInfo;
+
+type ModuleValidator = Box<dyn Fn(&ModuleInfo, &[u8]) -> Result<(), CompileError> + Send>;
 
 /// The Dylib builder
 pub struct Dylib {
     compiler_config: Option<Box<dyn CompilerConfig>>,
     target: Option<Target>,
     features: Option<Features>,
+    max_module_bytes: Option<usize>,
+    max_object_bytes: Option<usize>,
+    max_relocations: Option<usize>,
+    macos_install_name: Option<String>,
+    macos_rpaths: Vec<String>,
+    sanitizers: Vec<String>,
+    linker: Option<String>,
+    extra_link_args: Vec<String>,
+    elf_soname: Option<String>,
+    symbol_naming: Option<SymbolNaming>,
+    runtime_object: Option<PathBuf>,
+    compilation_metrics: Option<usize>,
+    compilation_metrics_batches: Option<usize>,
+    link_profile: LinkProfile,
+    metadata_symbol: Option<String>,
+    windows_import_library: bool,
+    split_debug_info: bool,
+    artifact_cache_capacity: usize,
+    export_required_symbols_only: bool,
+    export_module_functions_only: bool,
+    defer_signature_registration: bool,
+    record_relocations: bool,
+    data_initializers_symbol: Option<String>,
+    cpu_features: Option<EnumSet<CpuFeature>>,
+    object_only: bool,
+    large_custom_section_threshold: Option<usize>,
+    relocation_model: RelocationModel,
+    incremental_linking: bool,
+    module_validator: Option<ModuleValidator>,
+    load_fallback_dir: Option<PathBuf>,
+    android_ndk: Option<PathBuf>,
+    build_id: bool,
+    text_alignment: Option<u64>,
+    data_alignment: Option<u64>,
+    verify_relocations: bool,
+    dynamic_lookup: bool,
+    c_abi_export_prefix: Option<String>,
+    in_process_linking: bool,
+    keep_object_files: bool,
+    cache_dir: Option<PathBuf>,
+    cache_dir_max_bytes: Option<u64>,
+    integrity_check: bool,
+    artifact_signer: Option<ArtifactSigner>,
+    artifact_verifier: Option<ArtifactVerifier>,
 }
 
 impl Dylib {
@@ -18,19 +73,63 @@ impl Dylib {
         let mut compiler_config = compiler_config.into();
         compiler_config.enable_pic();
 
-        Self {
-            compiler_config: Some(compiler_config),
-            target: None,
-            features: None,
-        }
+        Self::with_compiler_config(Some(compiler_config))
     }
 
     /// Create a new headless Dylib builder.
     pub fn headless() -> Self {
+        Self::with_compiler_config(None)
+    }
+
+    fn with_compiler_config(compiler_config: Option<Box<dyn CompilerConfig>>) -> Self {
         Self {
-            compiler_config: None,
+            compiler_config,
             target: None,
             features: None,
+            max_module_bytes: None,
+            max_object_bytes: None,
+            max_relocations: None,
+            macos_install_name: None,
+            macos_rpaths: vec![],
+            sanitizers: vec![],
+            linker: None,
+            extra_link_args: vec![],
+            elf_soname: None,
+            symbol_naming: None,
+            runtime_object: None,
+            compilation_metrics: None,
+            compilation_metrics_batches: None,
+            link_profile: LinkProfile::Default,
+            metadata_symbol: None,
+            windows_import_library: false,
+            split_debug_info: false,
+            artifact_cache_capacity: 0,
+            export_required_symbols_only: false,
+            export_module_functions_only: false,
+            defer_signature_registration: false,
+            record_relocations: false,
+            data_initializers_symbol: None,
+            cpu_features: None,
+            object_only: false,
+            large_custom_section_threshold: None,
+            relocation_model: RelocationModel::Pic,
+            incremental_linking: false,
+            module_validator: None,
+            load_fallback_dir: None,
+            android_ndk: None,
+            build_id: false,
+            text_alignment: None,
+            data_alignment: None,
+            verify_relocations: false,
+            dynamic_lookup: true,
+            c_abi_export_prefix: None,
+            in_process_linking: false,
+            keep_object_files: false,
+            cache_dir: None,
+            cache_dir_max_bytes: None,
+            integrity_check: false,
+            artifact_signer: None,
+            artifact_verifier: None,
         }
     }
 
@@ -46,15 +145,452 @@ impl Dylib {
         self
     }
 
+    /// Set the maximum size of the modules accepted for compilation, see
+    /// `DylibEngine::set_max_module_bytes`.
+    pub fn max_module_bytes(mut self, max_module_bytes: usize) -> Self {
+        self.max_module_bytes = Some(max_module_bytes);
+        self
+    }
+
+    /// Set the maximum size of the generated object files, see
+    /// `DylibEngine::set_max_object_bytes`.
+    pub fn max_object_bytes(mut self, max_object_bytes: usize) -> Self {
+        self.max_object_bytes = Some(max_object_bytes);
+        self
+    }
+
+    /// Set the maximum number of relocations of the compiled code of a
+    /// module, see `DylibEngine::set_max_relocations`.
+    pub fn max_relocations(mut self, max_relocations: usize) -> Self {
+        self.max_relocations = Some(max_relocations);
+        self
+    }
+
+    /// Set the install name of the generated `.dylib`s, see
+    /// `DylibEngine::set_macos_install_name`.
+    pub fn macos_install_name(mut self, install_name: impl Into<String>) -> Self {
+        self.macos_install_name = Some(install_name.into());
+        self
+    }
+
+    /// Add a runtime search path to the generated `.dylib`s, see
+    /// `DylibEngine::add_macos_rpath`.
+    pub fn macos_rpath(mut self, rpath: impl Into<String>) -> Self {
+        self.macos_rpaths.push(rpath.into());
+        self
+    }
+
+    /// Add a sanitizer to link the generated shared objects with, see
+    /// `DylibEngine::add_sanitizer`.
+    pub fn sanitizer(mut self, sanitizer: impl Into<String>) -> Self {
+        self.sanitizers.push(sanitizer.into());
+        self
+    }
+
+    /// Set the command to link the generated shared objects with (e.g.
+    /// `zig cc`), see `DylibEngine::set_linker`.
+    pub fn linker(mut self, linker: impl Into<String>) -> Self {
+        self.linker = Some(linker.into());
+        self
+    }
+
+    /// Add arguments to pass to the linker after the ones of the engine,
+    /// see `DylibEngine::add_extra_link_arg`.
+    pub fn extra_link_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_link_args
+            .extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the soname of the generated `.so`s on ELF targets, see
+    /// `DylibEngine::set_elf_soname`.
+    pub fn elf_soname(mut self, soname: impl Into<String>) -> Self {
+        self.elf_soname = Some(soname.into());
+        self
+    }
+
+    /// Set the naming scheme of the symbols of the generated shared
+    /// objects, see `DylibEngine::set_symbol_naming`, failing if it's
+    /// invalid.
+    pub fn symbol_naming(mut self, symbol_naming: SymbolNaming) -> Result<Self, CompileError> {
+        symbol_naming.validate()?;
+        self.symbol_naming = Some(symbol_naming);
+        Ok(self)
+    }
+
+    /// Set the runtime object linked into the generated shared objects,
+    /// see `DylibEngine::set_runtime_object`.
+    pub fn runtime_object(mut self, runtime_object: impl Into<PathBuf>) -> Self {
+        self.runtime_object = Some(runtime_object.into());
+        self
+    }
+
+    /// Enable the collection of compilation metrics, see
+    /// `DylibEngine::set_compilation_metrics`.
+    pub fn compilation_metrics(mut self, slowest_functions: usize) -> Self {
+        self.compilation_metrics = Some(slowest_functions);
+        self
+    }
+
+    /// Set in how many batches the functions are timed, see
+    /// `DylibEngine::set_compilation_metrics_batches`.
+    pub fn compilation_metrics_batches(mut self, batches: usize) -> Self {
+        self.compilation_metrics_batches = Some(batches);
+        self
+    }
+
+    /// Set the link profile, see `DylibEngine::set_link_profile`.
+    pub fn link_profile(mut self, link_profile: LinkProfile) -> Self {
+        self.link_profile = link_profile;
+        self
+    }
+
+    /// Set the name of the metadata symbol, see
+    /// `DylibEngine::set_metadata_symbol`.
+    pub fn metadata_symbol(mut self, metadata_symbol: impl Into<String>) -> Self {
+        self.metadata_symbol = Some(metadata_symbol.into());
+        self
+    }
+
+    /// Generate the import library of Windows `.dll`s, see
+    /// `DylibEngine::set_windows_import_library`.
+    pub fn windows_import_library(mut self, windows_import_library: bool) -> Self {
+        self.windows_import_library = windows_import_library;
+        self
+    }
+
+    /// Split the debug info of the generated `.so`s into a separate
+    /// file, see `DylibEngine::set_split_debug_info`.
+    pub fn split_debug_info(mut self, split_debug_info: bool) -> Self {
+        self.split_debug_info = split_debug_info;
+        self
+    }
+
+    /// Keep up to `capacity` compiled artifacts in memory, see
+    /// `DylibEngine::set_artifact_cache_capacity`.
+    pub fn artifact_cache_capacity(mut self, capacity: usize) -> Self {
+        self.artifact_cache_capacity = capacity;
+        self
+    }
+
+    /// Only export the symbols needed to load the shared objects, see
+    /// `DylibEngine::set_export_required_symbols_only`.
+    pub fn export_required_symbols_only(mut self, export_required_symbols_only: bool) -> Self {
+        self.export_required_symbols_only = export_required_symbols_only;
+        self
+    }
+
+    /// Only export the functions exported by the Wasm module, see
+    /// `DylibEngine::set_export_module_functions_only`.
+    pub fn export_module_functions_only(mut self, export_module_functions_only: bool) -> Self {
+        self.export_module_functions_only = export_module_functions_only;
+        self
+    }
+
+    /// Defer the registration of the artifacts signatures to their first
+    /// instantiation, see `DylibEngine::set_defer_signature_registration`.
+    pub fn defer_signature_registration(mut self, defer_signature_registration: bool) -> Self {
+        self.defer_signature_registration = defer_signature_registration;
+        self
+    }
+
+    /// Record the relocations written to the object files, see
+    /// `DylibEngine::set_record_relocations`.
+    pub fn record_relocations(mut self, record_relocations: bool) -> Self {
+        self.record_relocations = record_relocations;
+        self
+    }
+
+    /// Additionally store the data initializers in the given symbol, see
+    /// `DylibEngine::set_data_initializers_symbol`.
+    pub fn data_initializers_symbol(mut self, data_initializers_symbol: impl Into<String>) -> Self {
+        self.data_initializers_symbol = Some(data_initializers_symbol.into());
+        self
+    }
+
+    /// Stop the compilation after writing the object file, see
+    /// `DylibEngine::set_object_only`.
+    pub fn object_only(mut self, object_only: bool) -> Self {
+        self.object_only = object_only;
+        self
+    }
+
+    /// Emit the custom sections bigger than the given size in their own
+    /// object section, see `DylibEngine::set_large_custom_section_threshold`.
+    pub fn large_custom_section_threshold(mut self, large_custom_section_threshold: usize) -> Self {
+        self.large_custom_section_threshold = Some(large_custom_section_threshold);
+        self
+    }
+
+    /// Set the relocation model of the generated objects, see
+    /// `DylibEngine::set_relocation_model`.
+    pub fn relocation_model(mut self, relocation_model: RelocationModel) -> Self {
+        self.relocation_model = relocation_model;
+        self
+    }
+
+    /// Keep the compiled code of the artifacts to replace their
+    /// functions, see `DylibEngine::set_incremental_linking`.
+    pub fn incremental_linking(mut self, incremental_linking: bool) -> Self {
+        self.incremental_linking = incremental_linking;
+        self
+    }
+
+    /// Enforce a custom policy on the modules before they are compiled,
+    /// see `DylibEngine::set_module_validator`.
+    pub fn module_validator<F>(mut self, module_validator: F) -> Self
+    where
+        F: Fn(&ModuleInfo, &[u8]) -> Result<(), CompileError> + Send + 'static,
+    {
+        self.module_validator = Some(Box::new(module_validator));
+        self
+    }
+
+    /// Copy the shared objects that fail to load in place to the given
+    /// directory, see `DylibEngine::set_load_fallback_dir`.
+    pub fn load_fallback_dir(mut self, load_fallback_dir: impl Into<PathBuf>) -> Self {
+        self.load_fallback_dir = Some(load_fallback_dir.into());
+        self
+    }
+
+    /// Set the root directory of the Android NDK, see
+    /// `DylibEngine::set_android_ndk`.
+    pub fn android_ndk(mut self, android_ndk: impl Into<PathBuf>) -> Self {
+        self.android_ndk = Some(android_ndk.into());
+        self
+    }
+
+    /// Set whether to embed a build-id into the shared objects, see
+    /// `DylibEngine::set_build_id`.
+    pub fn build_id(mut self, build_id: bool) -> Self {
+        self.build_id = build_id;
+        self
+    }
+
+    /// Set the alignment of the code in the object files, see
+    /// `DylibEngine::set_text_alignment`.
+    pub fn text_alignment(mut self, text_alignment: u64) -> Self {
+        self.text_alignment = Some(text_alignment);
+        self
+    }
+
+    /// Set the alignment of the read-only data in the object files, see
+    /// `DylibEngine::set_data_alignment`.
+    pub fn data_alignment(mut self, data_alignment: u64) -> Self {
+        self.data_alignment = Some(data_alignment);
+        self
+    }
+
+    /// Set whether to check the bounds of the relocations before
+    /// linking, see `DylibEngine::set_verify_relocations`.
+    pub fn verify_relocations(mut self, verify_relocations: bool) -> Self {
+        self.verify_relocations = verify_relocations;
+        self
+    }
+
+    /// Set whether undefined symbols are looked up from the host process
+    /// at load time, see `DylibEngine::set_dynamic_lookup`.
+    pub fn dynamic_lookup(mut self, dynamic_lookup: bool) -> Self {
+        self.dynamic_lookup = dynamic_lookup;
+        self
+    }
+
+    /// Set the prefix of the C-ABI wrappers of the exported functions,
+    /// see `DylibEngine::set_c_abi_export_prefix`.
+    pub fn c_abi_export_prefix(mut self, c_abi_export_prefix: impl Into<String>) -> Self {
+        self.c_abi_export_prefix = Some(c_abi_export_prefix.into());
+        self
+    }
+
+    /// Set whether the object files are loaded in process instead of
+    /// being linked, see `DylibEngine::set_in_process_linking`.
+    pub fn in_process_linking(mut self, in_process_linking: bool) -> Self {
+        self.in_process_linking = in_process_linking;
+        self
+    }
+
+    /// Set whether the artifacts keep the object file they were linked
+    /// from, see `DylibEngine::set_keep_object_files`.
+    pub fn keep_object_files(mut self, keep_object_files: bool) -> Self {
+        self.keep_object_files = keep_object_files;
+        self
+    }
+
+    /// Set the directory to keep the shared objects of the compiled
+    /// modules in, see `DylibEngine::set_cache_dir`.
+    pub fn cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    /// Set the maximum total size of the shared objects in the cache
+    /// directory, see `DylibEngine::set_cache_dir_max_bytes`.
+    pub fn cache_dir_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.cache_dir_max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Seal the artifacts with their hash and require it when
+    /// deserializing them, see `DylibEngine::set_integrity_check`.
+    pub fn integrity_check(mut self, integrity_check: bool) -> Self {
+        self.integrity_check = integrity_check;
+        self
+    }
+
+    /// Sign the hash of the artifacts, see
+    /// `DylibEngine::set_artifact_signer`.
+    pub fn artifact_signer<F>(mut self, signer: F) -> Self
+    where
+        F: Fn(&[u8; INTEGRITY_HASH_LEN]) -> [u8; INTEGRITY_SIGNATURE_LEN] + Send + 'static,
+    {
+        self.artifact_signer = Some(Box::new(signer));
+        self
+    }
+
+    /// Sign the hash of the artifacts with an ed25519 secret key, see
+    /// `DylibEngine::set_artifact_signing_key`.
+    #[cfg(feature = "signing")]
+    pub fn artifact_signing_key(mut self, signing_key: &[u8; INTEGRITY_KEY_LEN]) -> Self {
+        self.artifact_signer = Some(integrity::ed25519_signer(signing_key));
+        self
+    }
+
+    /// Check the signature of the deserialized artifacts, see
+    /// `DylibEngine::set_artifact_verifier`.
+    pub fn artifact_verifier<F>(mut self, verifier: F) -> Self
+    where
+        F: Fn(&[u8; INTEGRITY_HASH_LEN], &[u8; INTEGRITY_SIGNATURE_LEN]) -> bool + Send + 'static,
+    {
+        self.artifact_verifier = Some(Box::new(verifier));
+        self
+    }
+
+    /// Check the signature of the deserialized artifacts with an ed25519
+    /// public key, see `DylibEngine::set_artifact_verifying_key`.
+    #[cfg(feature = "signing")]
+    pub fn artifact_verifying_key(mut self, verifying_key: &[u8; INTEGRITY_KEY_LEN]) -> Self {
+        self.artifact_verifier = Some(integrity::ed25519_verifier(verifying_key));
+        self
+    }
+
+    /// Set the CPU features the compiled code can use (e.g. AVX2), in
+    /// place of the ones of the target.
+    ///
+    /// The features are recorded in the artifacts, which then refuse to
+    /// load on a CPU lacking any of them. They are part of the target of
+    /// the engine, so they can't be changed once it's built.
+    pub fn cpu_features(mut self, cpu_features: EnumSet<CpuFeature>) -> Self {
+        self.cpu_features = Some(cpu_features);
+        self
+    }
+
     /// Build the `DylibEngine` for this configuration
-    pub fn engine(self) -> DylibEngine {
-        if let Some(_compiler_config) = self.compiler_config {
+    pub fn engine(mut self) -> DylibEngine {
+        let mut engine = self.base_engine();
+        if let Some(max_module_bytes) = self.max_module_bytes {
+            engine.set_max_module_bytes(max_module_bytes);
+        }
+        if let Some(max_object_bytes) = self.max_object_bytes {
+            engine.set_max_object_bytes(max_object_bytes);
+        }
+        if let Some(max_relocations) = self.max_relocations {
+            engine.set_max_relocations(max_relocations);
+        }
+        if let Some(install_name) = self.macos_install_name {
+            engine.set_macos_install_name(install_name);
+        }
+        for rpath in self.macos_rpaths {
+            engine.add_macos_rpath(rpath);
+        }
+        for sanitizer in self.sanitizers {
+            engine.add_sanitizer(sanitizer);
+        }
+        engine.set_linker(self.linker);
+        for arg in self.extra_link_args {
+            engine.add_extra_link_arg(arg);
+        }
+        if let Some(soname) = self.elf_soname {
+            engine.set_elf_soname(soname);
+        }
+        if let Some(symbol_naming) = self.symbol_naming {
+            engine
+                .set_symbol_naming(symbol_naming)
+                .expect("the symbol naming is validated by the builder");
+        }
+        if let Some(runtime_object) = self.runtime_object {
+            engine.set_runtime_object(runtime_object);
+        }
+        if let Some(slowest_functions) = self.compilation_metrics {
+            engine.set_compilation_metrics(slowest_functions);
+        }
+        if let Some(batches) = self.compilation_metrics_batches {
+            engine.set_compilation_metrics_batches(batches);
+        }
+        engine.set_link_profile(self.link_profile);
+        if let Some(metadata_symbol) = self.metadata_symbol {
+            engine.set_metadata_symbol(metadata_symbol);
+        }
+        engine.set_windows_import_library(self.windows_import_library);
+        engine.set_split_debug_info(self.split_debug_info);
+        engine.set_artifact_cache_capacity(self.artifact_cache_capacity);
+        engine.set_export_required_symbols_only(self.export_required_symbols_only);
+        engine.set_export_module_functions_only(self.export_module_functions_only);
+        engine.set_defer_signature_registration(self.defer_signature_registration);
+        engine.set_record_relocations(self.record_relocations);
+        engine.set_data_initializers_symbol(self.data_initializers_symbol);
+        engine.set_object_only(self.object_only);
+        engine.set_large_custom_section_threshold(self.large_custom_section_threshold);
+        engine.set_relocation_model(self.relocation_model);
+        engine.set_incremental_linking(self.incremental_linking);
+        engine.set_load_fallback_dir(self.load_fallback_dir);
+        if let Some(android_ndk) = self.android_ndk {
+            engine.set_android_ndk(android_ndk);
+        }
+        engine.set_build_id(self.build_id);
+        if let Some(text_alignment) = self.text_alignment {
+            engine.set_text_alignment(text_alignment);
+        }
+        if let Some(data_alignment) = self.data_alignment {
+            engine.set_data_alignment(data_alignment);
+        }
+        engine.set_verify_relocations(self.verify_relocations);
+        engine.set_dynamic_lookup(self.dynamic_lookup);
+        engine.set_c_abi_export_prefix(self.c_abi_export_prefix);
+        engine.set_in_process_linking(self.in_process_linking);
+        engine.set_keep_object_files(self.keep_object_files);
+        engine.set_cache_dir(self.cache_dir);
+        engine.set_cache_dir_max_bytes(self.cache_dir_max_bytes);
+        engine.set_integrity_check(self.integrity_check);
+        if let Some(signer) = self.artifact_signer {
+            engine.set_artifact_signer(signer);
+        }
+        if let Some(verifier) = self.artifact_verifier {
+            engine.set_artifact_verifier(verifier);
+        }
+        if let Some(module_validator) = self.module_validator {
+            engine.set_module_validator(module_validator);
+        }
+        engine
+    }
+
+    /// Build the `DylibEngine` for the compiler configuration, target
+    /// and features.
+    fn base_engine(&mut self) -> DylibEngine {
+        if let Some(_compiler_config) = self.compiler_config.take() {
             #[cfg(feature = "compiler")]
             {
                 let compiler_config = _compiler_config;
-                let target = self.target.unwrap_or_default();
+                let mut target = self.target.take().unwrap_or_default();
+                if let Some(cpu_features) = self.cpu_features.take() {
+                    target = Target::new(target.triple().clone(), cpu_features);
+                }
                 let features = self
                     .features
+                    .take()
                     .unwrap_or_else(|| compiler_config.default_features_for_target(&target));
                 let compiler = compiler_config.compiler();
                 DylibEngine::new(compiler, target, features)
@@ -114,4 +650,53 @@ mod tests {
         let dylib = Dylib::headless();
         let _engine = dylib.engine();
     }
+
+    #[test]
+    fn build_configured_headless_engine() {
+        let dylib = Dylib::headless()
+            .metadata_symbol("CUSTOM_METADATA")
+            .link_profile(LinkProfile::Hardened)
+            .export_required_symbols_only(true)
+            .export_module_functions_only(true)
+            .data_initializers_symbol("DATA_INITIALIZERS")
+            .object_only(true)
+            .large_custom_section_threshold(1 << 20)
+            .relocation_model(RelocationModel::Static)
+            .incremental_linking(true)
+            .load_fallback_dir("/tmp")
+            .android_ndk("/opt/android-ndk")
+            .build_id(true)
+            .text_alignment(4096)
+            .data_alignment(64)
+            .verify_relocations(true)
+            .dynamic_lookup(false)
+            .c_abi_export_prefix("module_")
+            .in_process_linking(true)
+            .keep_object_files(true)
+            .cache_dir("/tmp/wasmer-cache")
+            .cache_dir_max_bytes(1 << 30)
+            .integrity_check(true)
+            .artifact_signer(|_| [0; INTEGRITY_SIGNATURE_LEN])
+            .artifact_verifier(|_, signature| signature == &[0; INTEGRITY_SIGNATURE_LEN])
+            .elf_soname("libmodule.so")
+            .split_debug_info(true)
+            .artifact_cache_capacity(16)
+            .symbol_naming(SymbolNaming {
+                prefix: "module".to_string(),
+                separator: ".".to_string(),
+            })
+            .unwrap()
+            .sanitizer("address")
+            .linker("zig cc")
+            .extra_link_args(vec!["-Wl,--threads=1"])
+            .max_relocations(1 << 16)
+            .compilation_metrics(3)
+            .compilation_metrics_batches(4);
+        #[cfg(feature = "signing")]
+        let dylib = dylib
+            .artifact_signing_key(&[1; INTEGRITY_KEY_LEN])
+            .artifact_verifying_key(&[2; INTEGRITY_KEY_LEN]);
+        let engine = dylib.engine();
+        assert_eq!(engine.inner().metadata_symbol(), "CUSTOM_METADATA");
+    }
 }
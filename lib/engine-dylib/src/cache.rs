@@ -0,0 +1,252 @@
+//! Caches of compiled `DylibArtifact`s, in memory and on disk.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// The prefix of the names of the shared objects in a cache directory.
+const CACHE_FILE_PREFIX: &str = "wasmer_cached_";
+
+/// The BLAKE3 hash of the version of the engine and of the `inputs`,
+/// in hexadecimal.
+#[cfg(feature = "compiler")]
+pub(crate) fn digest<'a>(inputs: impl IntoIterator<Item = &'a [u8]>) -> String {
+    let mut hasher = blake3::Hasher::new();
+    // Every input is prefixed by its length, so that they can't run into
+    // each other.
+    for input in std::iter::once(crate::VERSION.as_bytes()).chain(inputs) {
+        hasher.update(&(input.len() as u64).to_le_bytes());
+        hasher.update(input);
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// A least-recently-used cache of the serialized shared objects of
+/// compiled artifacts, keyed by their module prefix and the digest of
+/// the settings they were compiled with (see
+/// `DylibEngine::set_artifact_cache_capacity`).
+///
+/// The cache is meant to hold a handful of hot modules, so the entries
+/// are kept in a list ordered from the least to the most recently used,
+/// and looked up linearly.
+#[derive(Debug, Default)]
+pub(crate) struct ArtifactCache {
+    capacity: usize,
+    entries: VecDeque<(String, Arc<[u8]>)>,
+}
+
+impl ArtifactCache {
+    /// The maximum number of artifacts in the cache.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Set the maximum number of artifacts in the cache, evicting the
+    /// least recently used ones if there are more.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Get the artifact cached with `key`, marking it as the most
+    /// recently used.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn get(&mut self, key: &str) -> Option<Arc<[u8]>> {
+        let position = self.entries.iter().position(|(entry, _)| entry == key)?;
+        let entry = self.entries.remove(position)?;
+        let bytes = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(bytes)
+    }
+
+    /// Insert the artifact cached with `key`, as the most recently
+    /// used.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn insert(&mut self, key: String, bytes: Arc<[u8]>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(&key);
+        self.entries.push_back((key, bytes));
+        self.evict();
+    }
+
+    /// Remove the artifact cached with `key`, if any.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn remove(&mut self, key: &str) {
+        self.entries.retain(|(entry, _)| entry != key);
+    }
+
+    /// Remove all the artifacts.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// A directory of the shared objects of compiled artifacts, keyed by
+/// the hash of their module (see `DylibEngine::set_cache_dir`).
+#[derive(Debug, Clone)]
+pub(crate) struct CacheDir {
+    dir: PathBuf,
+    max_bytes: Option<u64>,
+}
+
+impl CacheDir {
+    pub(crate) fn new(dir: PathBuf, max_bytes: Option<u64>) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// The key of a module in the cache: the hash of the module and of
+    /// everything else the compiled code depends on.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn key(data: &[u8], parts: &[&str]) -> String {
+        digest(std::iter::once(data).chain(parts.iter().map(|part| part.as_bytes())))
+    }
+
+    /// The path of the shared object of the module with the key `key`.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn path(&self, key: &str, extension: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}{}.{}", CACHE_FILE_PREFIX, key, extension))
+    }
+
+    /// Create the directory if it doesn't exist.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn create(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)
+    }
+
+    /// The shared objects in the directory, with their size and the
+    /// time they were last written.
+    fn entries(&self) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let is_cached = entry
+                .file_name()
+                .to_str()
+                .map_or(false, |name| name.starts_with(CACHE_FILE_PREFIX));
+            let metadata = match entry.metadata() {
+                Ok(metadata) if is_cached && metadata.is_file() => metadata,
+                _ => continue,
+            };
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((entry.path(), metadata.len(), modified));
+        }
+        Ok(entries)
+    }
+
+    /// Remove the least recently written shared objects until the
+    /// directory fits in its maximum size, returning how many were
+    /// removed.
+    pub(crate) fn evict(&self) -> io::Result<usize> {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return Ok(0),
+        };
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        let mut removed = 0;
+        for (path, len, _) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            // The shared objects loaded by running processes can't be
+            // removed on Windows, they are left for a later eviction.
+            if fs::remove_file(&path).is_ok() {
+                total -= len;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove all the shared objects, returning how many were removed.
+    pub(crate) fn clear(&self) -> io::Result<usize> {
+        let mut removed = 0;
+        for (path, _, _) in self.entries()? {
+            if fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+
+    fn bytes(byte: u8) -> Arc<[u8]> {
+        Arc::from(vec![byte])
+    }
+
+    #[test]
+    fn least_recently_used_eviction() {
+        let mut cache = ArtifactCache::default();
+        cache.insert("a".to_string(), bytes(1));
+        assert!(cache.get("a").is_none());
+
+        cache.set_capacity(2);
+        cache.insert("a".to_string(), bytes(1));
+        cache.insert("b".to_string(), bytes(2));
+        // `a` becomes the most recently used, so `b` is evicted.
+        assert_eq!(cache.get("a").as_deref(), Some(&[1][..]));
+        cache.insert("c".to_string(), bytes(3));
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("c").as_deref(), Some(&[3][..]));
+
+        cache.set_capacity(1);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.get("c").as_deref(), Some(&[3][..]));
+
+        cache.clear();
+        assert!(cache.get("c").is_none());
+    }
+
+    #[test]
+    fn cache_dir_eviction() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = CacheDir::new(dir.path().to_path_buf(), Some(10));
+        let key = CacheDir::key(b"module", &["x86_64-unknown-linux-gnu"]);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, CacheDir::key(b"module", &["x86_64-unknown-linux-gnu"]));
+        assert_ne!(
+            key,
+            CacheDir::key(b"module", &["aarch64-unknown-linux-gnu"])
+        );
+        assert_ne!(CacheDir::key(b"ab", &["c"]), CacheDir::key(b"a", &["bc"]));
+
+        let old = cache_dir.path("old", "so");
+        let new = cache_dir.path("new", "so");
+        fs::write(&old, vec![0; 6]).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(&new, vec![0; 6]).unwrap();
+        // Other files are left alone.
+        let other = dir.path().join("other.so");
+        fs::write(&other, vec![0; 6]).unwrap();
+
+        assert_eq!(cache_dir.evict().unwrap(), 1);
+        assert!(!old.exists());
+        assert!(new.exists());
+        assert_eq!(cache_dir.evict().unwrap(), 0);
+
+        assert_eq!(cache_dir.clear().unwrap(), 1);
+        assert!(!new.exists());
+        assert!(other.exists());
+    }
+}
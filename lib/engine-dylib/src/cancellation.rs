@@ -0,0 +1,44 @@
+//! Cancellation of `DylibArtifact` compilations.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use wasmer_compiler::CompileError;
+
+/// A token to cancel a compilation started with
+/// `DylibArtifact::new_cancellable`.
+///
+/// Clones of a token share its state, so the compilation can be
+/// cancelled from another thread (e.g. when the client that requested
+/// it disconnected). The compilation is aborted between its phases
+/// (translation, compilation, object generation and linking), and the
+/// linker is killed if it's running.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new token, not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the compilations using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether this token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail with `CompileError::Cancelled` if this token was cancelled.
+    pub(crate) fn check(&self) -> Result<(), CompileError> {
+        if self.is_cancelled() {
+            Err(CompileError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -1,22 +1,79 @@
 //! Dylib Engine.
 
+use crate::artifact::{LoadedCode, DEFAULT_METADATA_SYMBOL, TEMP_FILE_PREFIX};
+use crate::cache::{ArtifactCache, CacheDir};
+#[cfg(feature = "signing")]
+use crate::integrity::{self, INTEGRITY_KEY_LEN};
+use crate::integrity::{
+    ArtifactSigner, ArtifactVerifier, INTEGRITY_HASH_LEN, INTEGRITY_SIGNATURE_LEN,
+};
+use crate::loader::LoadedObject;
+use crate::metrics::{AtomicEngineCounters, EngineCounters};
+use crate::serialize::SymbolNaming;
 use crate::DylibArtifact;
 use libloading::Library;
 use loupe::MemoryUsage;
-use std::path::Path;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
 use wasmer_compiler::{CompileError, Target};
 #[cfg(feature = "compiler")]
-use wasmer_compiler::{Compiler, Triple};
+use wasmer_compiler::{Compiler, Environment, Triple};
 use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
-#[cfg(feature = "compiler")]
+use wasmer_object::RelocationModel;
 use wasmer_types::Features;
-use wasmer_types::FunctionType;
+use wasmer_types::{FunctionType, ModuleInfo};
 use wasmer_vm::{
     FuncDataRegistry, SignatureRegistry, VMCallerCheckedAnyfunc, VMFuncRef, VMSharedSignatureIndex,
 };
 
+/// The default alignment of the code in the object files: the usual
+/// function alignment of compilers.
+const DEFAULT_TEXT_ALIGNMENT: u64 = 16;
+
+/// The default number of batches in which the functions are timed when
+/// collecting compilation metrics.
+const DEFAULT_METRICS_BATCHES: usize = 16;
+
+/// The default alignment of the read-only data in the object files
+/// (raised to the minimum alignment of the target architecture).
+const DEFAULT_DATA_ALIGNMENT: u64 = 1;
+
+/// The files mapped by the running processes (at least, by those whose
+/// mappings are readable).
+#[cfg(target_os = "linux")]
+fn mapped_files() -> HashSet<PathBuf> {
+    let processes = match fs::read_dir("/proc") {
+        Ok(processes) => processes,
+        Err(_) => return HashSet::new(),
+    };
+    processes
+        .filter_map(|process| {
+            let process = process.ok()?;
+            process.file_name().to_str()?.parse::<u32>().ok()?;
+            fs::read_to_string(process.path().join("maps")).ok()
+        })
+        .flat_map(|maps| {
+            // The path is the last field, and may contain spaces.
+            maps.lines()
+                .filter_map(|line| line.splitn(6, char::is_whitespace).nth(5))
+                .map(|path| PathBuf::from(path.trim_start()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The files mapped by the running processes, which are unknown on this
+/// platform.
+#[cfg(not(target_os = "linux"))]
+fn mapped_files() -> HashSet<PathBuf> {
+    HashSet::new()
+}
+
 /// A WebAssembly `Dylib` Engine.
 #[derive(Clone, MemoryUsage)]
 pub struct DylibEngine {
@@ -30,19 +87,30 @@ impl DylibEngine {
     /// Create a new `DylibEngine` with the given config
     #[cfg(feature = "compiler")]
     pub fn new(compiler: Box<dyn Compiler>, target: Target, features: Features) -> Self {
-        let is_cross_compiling = *target.triple() != Triple::host();
-        let linker = Linker::find_linker(is_cross_compiling);
+        let is_cross_compiling = is_cross_compiling(&target);
+        let is_musl = target.triple().environment == Environment::Musl;
+        let android_ndk = if is_android(target.triple()) {
+            android_ndk_from_env()
+        } else {
+            None
+        };
+        // Android targets are linked with the `clang` of the NDK when set
+        // (see `set_android_ndk`), which may only happen later.
+        let linker = if is_android(target.triple()) {
+            Linker::find_clang().unwrap_or(Linker::None)
+        } else {
+            Linker::find_linker(is_cross_compiling, is_musl).unwrap_or(Linker::None)
+        };
 
         Self {
             inner: Arc::new(Mutex::new(DylibEngineInner {
-                compiler: Some(compiler),
-                signatures: SignatureRegistry::new(),
-                func_data: Arc::new(FuncDataRegistry::new()),
-                prefixer: None,
+                compiler_fingerprint: compiler.config_fingerprint(),
+                compiler: Some(Arc::new(Mutex::new(compiler))),
                 features,
                 is_cross_compiling,
                 linker,
-                libraries: vec![],
+                android_ndk,
+                ..DylibEngineInner::default()
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -64,18 +132,7 @@ impl DylibEngine {
     /// they just take already processed Modules (via `Module::serialize`).
     pub fn headless() -> Self {
         Self {
-            inner: Arc::new(Mutex::new(DylibEngineInner {
-                #[cfg(feature = "compiler")]
-                compiler: None,
-                #[cfg(feature = "compiler")]
-                features: Features::default(),
-                signatures: SignatureRegistry::new(),
-                func_data: Arc::new(FuncDataRegistry::new()),
-                prefixer: None,
-                is_cross_compiling: false,
-                linker: Linker::None,
-                libraries: vec![],
-            })),
+            inner: Arc::new(Mutex::new(DylibEngineInner::default())),
             target: Arc::new(Target::default()),
             engine_id: EngineId::default(),
         }
@@ -90,7 +147,9 @@ impl DylibEngine {
     /// # Important
     ///
     /// This prefixer function should be deterministic, so the compilation
-    /// remains deterministic.
+    /// remains deterministic. With the artifact cache (see
+    /// `set_artifact_cache_capacity`), it must also be injective, giving
+    /// different prefixes to different modules (e.g. a hash of the module).
     pub fn set_deterministic_prefixer<F>(&mut self, prefixer: F)
     where
         F: Fn(&[u8]) -> String + Send + 'static,
@@ -99,6 +158,650 @@ impl DylibEngine {
         inner.prefixer = Some(Box::new(prefixer));
     }
 
+    /// Sets a validator to enforce a custom policy on the modules (e.g.
+    /// limits on the number of functions or memory pages) before they
+    /// are compiled.
+    ///
+    /// The validator receives the translated module (after the module
+    /// middlewares are applied) and its raw bytes, and rejects the
+    /// module by returning an error, which is returned by the
+    /// compilation.
+    pub fn set_module_validator<F>(&mut self, module_validator: F)
+    where
+        F: Fn(&ModuleInfo, &[u8]) -> Result<(), CompileError> + Send + 'static,
+    {
+        let mut inner = self.inner_mut();
+        inner.module_validator = Some(Box::new(module_validator));
+    }
+
+    /// Sets the maximum size (in bytes) of a WebAssembly module that
+    /// this engine accepts for compilation.
+    ///
+    /// Bigger modules are rejected before any translation work is done.
+    pub fn set_max_module_bytes(&mut self, max_module_bytes: usize) {
+        let mut inner = self.inner_mut();
+        inner.max_module_bytes = Some(max_module_bytes);
+    }
+
+    /// Sets the maximum size (in bytes) of the object file generated
+    /// for a module, checked before invoking the linker.
+    pub fn set_max_object_bytes(&mut self, max_object_bytes: usize) {
+        let mut inner = self.inner_mut();
+        inner.max_object_bytes = Some(max_object_bytes);
+    }
+
+    /// Sets the maximum number of relocations of the compiled code of a
+    /// module, checked before generating the object file.
+    ///
+    /// Linking time grows with the number of relocations, so this guards
+    /// against modules crafted to slow the linker down. Compilers can't
+    /// emit the object file themselves when this is set.
+    pub fn set_max_relocations(&mut self, max_relocations: usize) {
+        let mut inner = self.inner_mut();
+        inner.max_relocations = Some(max_relocations);
+    }
+
+    /// Sets the install name of the generated `.dylib` on macOS/iOS
+    /// targets (passed to the linker as `-install_name`).
+    ///
+    /// By default the install name is the temporary path the dylib was
+    /// linked at, so this should be set to the final deployed path
+    /// (e.g. `@rpath/module.dylib`) when distributing artifacts.
+    pub fn set_macos_install_name(&mut self, install_name: impl Into<String>) {
+        let mut inner = self.inner_mut();
+        inner.macos_install_name = Some(install_name.into());
+    }
+
+    /// Adds a runtime search path (`-rpath`) to the generated `.dylib`
+    /// on macOS/iOS targets.
+    pub fn add_macos_rpath(&mut self, rpath: impl Into<String>) {
+        let mut inner = self.inner_mut();
+        inner.macos_rpaths.push(rpath.into());
+    }
+
+    /// Adds a sanitizer (e.g. `address` or `undefined`) to link the
+    /// generated shared objects with, passed to the linker as
+    /// `-fsanitize=`.
+    ///
+    /// The compiled code itself isn't instrumented: this makes the
+    /// shared objects link against the sanitizer runtime, so their
+    /// libcalls resolve into an instrumented host. The linker must be
+    /// `clang` with the sanitizer runtime installed, and the host process
+    /// must be built with the same sanitizers (e.g. with
+    /// `RUSTFLAGS=-Zsanitizer=address`), or loading the shared objects
+    /// will fail on the missing runtime symbols.
+    pub fn add_sanitizer(&mut self, sanitizer: impl Into<String>) {
+        let mut inner = self.inner_mut();
+        inner.sanitizers.push(sanitizer.into());
+    }
+
+    /// Sets the command to link the generated shared objects with, or
+    /// `None` (the default) to use the `gcc` or `clang` found when the
+    /// engine was created.
+    ///
+    /// The command is split on whitespace into the program and its first
+    /// arguments, so `zig cc` runs `zig` with `cc` before the arguments of
+    /// the engine. It must accept the flags of `gcc` and `clang`. It is
+    /// also used for Android targets, instead of the `clang` of the NDK.
+    pub fn set_linker(&mut self, linker: Option<String>) {
+        let mut inner = self.inner_mut();
+        inner.linker_command = linker
+            .map(|linker| linker.split_whitespace().map(str::to_string).collect())
+            .filter(|command: &Vec<String>| !command.is_empty());
+    }
+
+    /// Adds an argument to pass to the linker after the ones of the
+    /// engine, e.g. `-Wl,--threads=1` or `-L/opt/lib`.
+    pub fn add_extra_link_arg(&mut self, arg: impl Into<String>) {
+        let mut inner = self.inner_mut();
+        inner.extra_link_args.push(arg.into());
+    }
+
+    /// Sets the `SONAME` to give to generated `.so`s on ELF targets, so
+    /// other components can reference them at runtime (e.g. through
+    /// `ldconfig`).
+    ///
+    /// By default the soname is derived from the module prefix (see
+    /// `set_deterministic_prefixer`): `libwasmer_{prefix}.so`, or
+    /// `libwasmer_module.so` without a prefix.
+    pub fn set_elf_soname(&mut self, soname: impl Into<String>) {
+        let mut inner = self.inner_mut();
+        inner.elf_soname = Some(soname.into());
+    }
+
+    /// Sets the naming scheme of the symbols of the generated shared
+    /// objects (see `SymbolNaming`), e.g. to match the names expected
+    /// by profilers or symbolizers.
+    ///
+    /// The scheme is recorded in the metadata of the shared objects, so
+    /// artifacts compiled with any scheme can be deserialized by any
+    /// engine. An invalid scheme (see `SymbolNaming::validate`) is
+    /// refused, and the current one kept.
+    pub fn set_symbol_naming(&mut self, symbol_naming: SymbolNaming) -> Result<(), CompileError> {
+        symbol_naming.validate()?;
+        let mut inner = self.inner_mut();
+        inner.symbol_naming = symbol_naming;
+        Ok(())
+    }
+
+    /// Sets a runtime object file or static archive to link into every
+    /// generated shared object.
+    ///
+    /// The runtime must provide the implementation of all the libcalls
+    /// used by the compiled code. When set, the shared objects are linked
+    /// without `-undefined dynamic_lookup`, so they no longer depend on
+    /// the host process exporting these symbols.
+    pub fn set_runtime_object(&mut self, runtime_object: impl Into<PathBuf>) {
+        let mut inner = self.inner_mut();
+        inner.runtime_object = Some(runtime_object.into());
+    }
+
+    /// Enables the collection of `CompilationMetrics` for every module
+    /// compiled by this engine, reporting the `slowest_functions` batches
+    /// of functions taking the longest to compile (see
+    /// `set_compilation_metrics_batches`).
+    ///
+    /// Timing the functions compiles each module once more per batch, so
+    /// this is meant for diagnosing slow compilations, not for production.
+    /// The metrics are available via `DylibArtifact::compilation_metrics`.
+    pub fn set_compilation_metrics(&mut self, slowest_functions: usize) {
+        let mut inner = self.inner_mut();
+        inner.metrics_slowest_functions = Some(slowest_functions);
+    }
+
+    /// Sets in how many batches of consecutive functions the functions
+    /// are timed when collecting compilation metrics (16 by default).
+    ///
+    /// More batches give finer timings (down to one function per batch),
+    /// but each batch compiles the module once more.
+    pub fn set_compilation_metrics_batches(&mut self, batches: usize) {
+        let mut inner = self.inner_mut();
+        inner.metrics_batches = batches.max(1);
+    }
+
+    /// Sets the profile used to link the generated shared objects.
+    pub fn set_link_profile(&mut self, link_profile: LinkProfile) {
+        let mut inner = self.inner_mut();
+        inner.link_profile = link_profile;
+    }
+
+    /// Sets the name of the symbol holding the module metadata in the
+    /// generated shared objects (`WASMER_METADATA` by default).
+    ///
+    /// This avoids symbol collisions when the compiled module is embedded
+    /// into a bigger binary that already defines `WASMER_METADATA`. The
+    /// same name must be used to deserialize the artifact.
+    pub fn set_metadata_symbol(&mut self, metadata_symbol: impl Into<String>) {
+        let mut inner = self.inner_mut();
+        inner.metadata_symbol = metadata_symbol.into();
+    }
+
+    /// Sets whether to generate the import library (`.lib`) of the
+    /// `.dll`s generated for Windows targets.
+    ///
+    /// The import library is written next to the `.dll`, its path is
+    /// available via `DylibArtifact::import_library_path`, and it is
+    /// copied along when serializing the artifact to a file.
+    pub fn set_windows_import_library(&mut self, windows_import_library: bool) {
+        let mut inner = self.inner_mut();
+        inner.windows_import_library = windows_import_library;
+    }
+
+    /// Sets whether to split the debug info of the `.so`s generated for
+    /// ELF targets into a separate file.
+    ///
+    /// After linking, `objcopy` (from GNU binutils, which must be
+    /// installed and support the target) copies the debug info to a
+    /// `.debug` file next to the `.so`, strips it from the `.so`, and
+    /// adds a `.gnu_debuglink` section to the `.so` referencing the
+    /// `.debug` file. The path of the `.debug` file is available via
+    /// `DylibArtifact::debug_info_path`, and it is copied along when
+    /// serializing the artifact to a file. The symbol table is kept, so
+    /// the stripped `.so` is deserialized like any other.
+    pub fn set_split_debug_info(&mut self, split_debug_info: bool) {
+        let mut inner = self.inner_mut();
+        inner.split_debug_info = split_debug_info;
+    }
+
+    /// Sets whether the generated shared objects should only export the
+    /// symbols needed to load them (the metadata, the functions and the
+    /// trampolines), hiding everything else.
+    ///
+    /// On ELF targets this is done with a linker version script. On
+    /// other targets, only the symbols of the custom sections (which are
+    /// never looked up by name) are hidden, when the compiler doesn't
+    /// emit the object file itself.
+    pub fn set_export_required_symbols_only(&mut self, export_required_symbols_only: bool) {
+        let mut inner = self.inner_mut();
+        inner.export_required_symbols_only = export_required_symbols_only;
+    }
+
+    /// Sets whether the generated shared objects should only export the
+    /// functions exported by the Wasm module, hiding the internal ones.
+    ///
+    /// This shrinks the dynamic symbol table, and leaves the exports of
+    /// the module as the interface of the shared object for host code
+    /// calling them directly. The hidden functions are still found when
+    /// loading the shared object, from its symbol table, which must then
+    /// not be stripped. This only applies to ELF and Mach-O targets,
+    /// when the compiler doesn't emit the object file itself.
+    pub fn set_export_module_functions_only(&mut self, export_module_functions_only: bool) {
+        let mut inner = self.inner_mut();
+        inner.export_module_functions_only = export_module_functions_only;
+    }
+
+    /// Sets whether the registration of the signatures of the loaded
+    /// artifacts is deferred to their first instantiation.
+    ///
+    /// This saves registry space and lock time for artifacts that are
+    /// loaded but may never be instantiated (e.g. when validating them in
+    /// bulk).
+    pub fn set_defer_signature_registration(&mut self, defer_signature_registration: bool) {
+        let mut inner = self.inner_mut();
+        inner.defer_signature_registration = defer_signature_registration;
+    }
+
+    /// Sets whether to record the relocations written to the object
+    /// files, available via `DylibArtifact::relocation_records`.
+    ///
+    /// This is useful to debug the relocations of new targets.
+    pub fn set_record_relocations(&mut self, record_relocations: bool) {
+        let mut inner = self.inner_mut();
+        inner.record_relocations = record_relocations;
+    }
+
+    /// Sets the size in bytes above which custom sections (e.g. big
+    /// constant tables) are emitted in their own object section, or
+    /// `None` (the default) to always append them to the shared section.
+    ///
+    /// See `wasmer_object::EmitOptions::large_custom_section_threshold`.
+    pub fn set_large_custom_section_threshold(
+        &mut self,
+        large_custom_section_threshold: Option<usize>,
+    ) {
+        let mut inner = self.inner_mut();
+        inner.large_custom_section_threshold = large_custom_section_threshold;
+    }
+
+    /// Sets the relocation model of the generated objects
+    /// (`RelocationModel::Pic` by default).
+    ///
+    /// Objects of the `RelocationModel::Static` model are meant to be
+    /// linked into a static executable (with
+    /// `RelocationModel::linker_args`), and can't be loaded as a shared
+    /// object: the compilation stops after writing the object file, like
+    /// with `set_object_only`. Compilers emitting the object file
+    /// themselves keep their own (position-independent) relocations,
+    /// which can be linked statically too.
+    pub fn set_relocation_model(&mut self, relocation_model: RelocationModel) {
+        let mut inner = self.inner_mut();
+        inner.relocation_model = relocation_model;
+    }
+
+    /// Sets whether the artifacts keep their compiled code in memory,
+    /// so their functions can be replaced with
+    /// `DylibArtifact::replace_function` without recompiling the module.
+    ///
+    /// This is meant for development (e.g. hot-patching): the code is
+    /// kept for as long as the artifacts live, and compilers can't emit
+    /// the object file themselves.
+    pub fn set_incremental_linking(&mut self, incremental_linking: bool) {
+        let mut inner = self.inner_mut();
+        inner.incremental_linking = incremental_linking;
+    }
+
+    /// Sets the directory to copy the shared objects to when loading
+    /// them in place fails, or `None` (the default) to use the system
+    /// temporary directory.
+    ///
+    /// Loading can fail on filesystems that don't support executable
+    /// mappings (e.g. some NFS mounts), in which case the shared object
+    /// is loaded from a local copy instead.
+    pub fn set_load_fallback_dir(&mut self, load_fallback_dir: Option<PathBuf>) {
+        let mut inner = self.inner_mut();
+        inner.load_fallback_dir = load_fallback_dir;
+    }
+
+    /// Sets the root directory of the Android NDK, whose `clang` links
+    /// the shared objects for Android targets.
+    ///
+    /// By default, it's read from the `ANDROID_NDK_HOME` (or
+    /// `ANDROID_NDK_ROOT`) environment variable when the engine targets
+    /// Android.
+    pub fn set_android_ndk(&mut self, android_ndk: PathBuf) {
+        let mut inner = self.inner_mut();
+        inner.android_ndk = Some(android_ndk);
+    }
+
+    /// Sets whether to embed a build-id (`-Wl,--build-id`) into the
+    /// generated shared objects, to tie crash reports to the exact
+    /// artifacts they come from.
+    ///
+    /// Only ELF shared objects get one; it's read back from them (see
+    /// `DylibArtifact::build_id`).
+    pub fn set_build_id(&mut self, build_id: bool) {
+        let mut inner = self.inner_mut();
+        inner.build_id = build_id;
+    }
+
+    /// Sets the alignment of the functions, trampolines and executable
+    /// custom sections in the generated object files (16 bytes by
+    /// default), e.g. the page size for loaders or hardening
+    /// configurations that require page-aligned code.
+    ///
+    /// It must be a power of two, otherwise the compilation fails. It's
+    /// raised to the minimum alignment of the target architecture if
+    /// lower.
+    pub fn set_text_alignment(&mut self, text_alignment: u64) {
+        let mut inner = self.inner_mut();
+        inner.text_alignment = text_alignment;
+    }
+
+    /// Sets the alignment of the read-only custom sections in the
+    /// generated object files (no alignment by default), like
+    /// `set_text_alignment`.
+    pub fn set_data_alignment(&mut self, data_alignment: u64) {
+        let mut inner = self.inner_mut();
+        inner.data_alignment = data_alignment;
+    }
+
+    /// Sets whether to check that every relocation is within the data of
+    /// the function or custom section it applies to when writing the
+    /// object file, before linking it.
+    ///
+    /// Such a relocation is a code generation bug, reported then as a
+    /// `CompileError` naming the symbol instead of a linker error (or a
+    /// silently corrupted shared object). The compilers emitting the
+    /// object file themselves are not used when enabled.
+    pub fn set_verify_relocations(&mut self, verify_relocations: bool) {
+        let mut inner = self.inner_mut();
+        inner.verify_relocations = verify_relocations;
+    }
+
+    /// Sets whether the symbols left undefined by the generated shared
+    /// objects (the libcalls) are looked up from the host process at load
+    /// time (`-undefined dynamic_lookup`, the default).
+    ///
+    /// When disabled, every symbol must be resolved at link time, e.g. by
+    /// a runtime linked in (see `DylibEngine::set_runtime_object`), and
+    /// the unresolved ones fail the compilation with a `CompileError`
+    /// listing the linker errors.
+    pub fn set_dynamic_lookup(&mut self, dynamic_lookup: bool) {
+        let mut inner = self.inner_mut();
+        inner.dynamic_lookup = dynamic_lookup;
+    }
+
+    /// Sets the prefix of the C-ABI wrappers to emit for the exported
+    /// functions of the modules, or `None` (the default) to emit none.
+    ///
+    /// Each exported function defined by a module gets a wrapper named
+    /// after the prefix and the export name (with the characters invalid
+    /// in C identifiers replaced by `_`), calling the function through its
+    /// trampoline, so that host C code can link against the shared object
+    /// (see `CAbiExport`). The wrappers are recorded in the metadata,
+    /// available via `DylibArtifact::c_abi_exports`. Exported functions
+    /// whose names collide once sanitized fail the compilation.
+    ///
+    /// Only x86_64 and AArch64 targets are supported. The compilers
+    /// emitting the object file themselves are not used when set.
+    pub fn set_c_abi_export_prefix(&mut self, c_abi_export_prefix: Option<String>) {
+        let mut inner = self.inner_mut();
+        inner.c_abi_export_prefix = c_abi_export_prefix;
+    }
+
+    /// Sets whether to load the object files of the modules in process
+    /// instead of linking them into shared objects (off by default).
+    ///
+    /// The relocations of the object file are applied by the engine
+    /// itself and its code is mapped into executable memory, so no
+    /// external linker is needed. The serialized artifacts are the object
+    /// files, which are loaded the same way when deserialized (engines
+    /// without it refuse to deserialize object files).
+    ///
+    /// Only x86_64 and AArch64 ELF objects for the host are supported, on
+    /// Unix. The compilers emitting the object file themselves are not
+    /// used when set, and it has no effect when cross-compiling.
+    pub fn set_in_process_linking(&mut self, in_process_linking: bool) {
+        let mut inner = self.inner_mut();
+        inner.in_process_linking = in_process_linking;
+    }
+
+    /// Sets whether the artifacts keep the relocatable object file they
+    /// were linked from, returned by `DylibArtifact::emit_object` (off by
+    /// default, as it's kept in memory).
+    ///
+    /// The artifacts that are not linked (see `set_object_only` and
+    /// `set_in_process_linking`) always have their object file.
+    pub fn set_keep_object_files(&mut self, keep_object_files: bool) {
+        let mut inner = self.inner_mut();
+        inner.keep_object_files = keep_object_files;
+    }
+
+    /// Sets whether to stop the compilation after writing the object
+    /// file, without linking it into a shared object.
+    ///
+    /// The resulting artifacts can't be instantiated, like cross-compiled
+    /// ones: their path (see `DylibArtifact::path`) is the object file,
+    /// which is useful to link many modules at once later.
+    pub fn set_object_only(&mut self, object_only: bool) {
+        let mut inner = self.inner_mut();
+        inner.object_only = object_only;
+    }
+
+    /// Sets the name of a symbol to additionally store the data
+    /// initializers of the generated shared objects in, or `None` (the
+    /// default) to only store them in the module metadata.
+    ///
+    /// This lets tools read the data segments of an artifact (e.g. with
+    /// the `object` crate) without decoding the whole module metadata,
+    /// see `encode_data_initializers` for the format of the symbol.
+    pub fn set_data_initializers_symbol(&mut self, data_initializers_symbol: Option<String>) {
+        let mut inner = self.inner_mut();
+        inner.data_initializers_symbol = data_initializers_symbol;
+    }
+
+    /// Sets how many compiled artifacts to keep in memory, so compiling
+    /// the same module again skips both the compiler and the linker
+    /// (0, the default, disables the cache).
+    ///
+    /// The artifacts are cached by their module prefix and the settings
+    /// they were compiled with (the styles of the memories and tables,
+    /// and the settings of the engine, like for `set_cache_dir`), so only
+    /// the modules compiled with a deterministic prefixer (see
+    /// `set_deterministic_prefixer`) giving them a non-empty prefix are
+    /// cached. The prefixer must be injective: two different modules
+    /// getting the same prefix are served the same artifact. The least
+    /// recently used artifact is evicted when the cache is full.
+    /// Compilations writing the object file or generating component
+    /// adapters are never served from the cache, and artifacts served
+    /// from it have no compilation metrics.
+    pub fn set_artifact_cache_capacity(&mut self, capacity: usize) {
+        let mut inner = self.inner_mut();
+        inner.artifact_cache.set_capacity(capacity);
+    }
+
+    /// Removes all the artifacts from the artifact cache (see
+    /// `set_artifact_cache_capacity`).
+    pub fn clear_artifact_cache(&self) {
+        let mut inner = self.inner_mut();
+        inner.artifact_cache.clear();
+    }
+
+    /// Sets a directory to keep the shared objects of the compiled
+    /// modules in, or `None` (the default) to only keep them in temporary
+    /// files removed with the artifacts.
+    ///
+    /// The shared objects are named after the BLAKE3 hash of the module,
+    /// its prefix (see `set_deterministic_prefixer`), the styles of its
+    /// memories and tables, the target, the features, the configuration
+    /// of the compiler (told apart by its `Debug` representation, with
+    /// the middlewares) and the settings of the engine changing the
+    /// generated shared objects, so compiling the same module again with
+    /// the same settings loads its shared object from the directory
+    /// instead of compiling it. Like with the artifact cache
+    /// (see `set_artifact_cache_capacity`), compilations writing the
+    /// object file or generating component adapters don't use the
+    /// directory, nor do artifacts that are not loaded.
+    ///
+    /// The directory is created when needed, and can be shared by
+    /// several processes. Its size is bounded with
+    /// `set_cache_dir_max_bytes`.
+    pub fn set_cache_dir(&mut self, cache_dir: Option<PathBuf>) {
+        let mut inner = self.inner_mut();
+        inner.cache_dir = cache_dir;
+    }
+
+    /// Sets the maximum total size of the shared objects in the cache
+    /// directory (see `set_cache_dir`), or `None` (the default) for no
+    /// limit.
+    ///
+    /// When a shared object added to the directory makes it exceed that
+    /// size, the least recently written ones are removed. The shared
+    /// objects loaded in running processes stay usable when removed,
+    /// except on Windows where they can't be removed until unloaded.
+    pub fn set_cache_dir_max_bytes(&mut self, max_bytes: Option<u64>) {
+        let mut inner = self.inner_mut();
+        inner.cache_dir_max_bytes = max_bytes;
+    }
+
+    /// Sets whether to seal the generated artifacts with a hash of their
+    /// contents, and to refuse deserializing the artifacts without a
+    /// valid one (with `DeserializeError::IntegrityCheckFailed`).
+    ///
+    /// The hash is stored in the `WASMER_INTEGRITY` symbol, and checked
+    /// before the shared object is loaded, so a corrupted or tampered
+    /// artifact is caught before `dlopen` runs any of its code. The
+    /// hash of artifacts that have one is checked even when this is
+    /// disabled, and the checked artifacts are loaded from a private copy
+    /// of the checked bytes, so the file can't be changed in between. It
+    /// doesn't protect against an attacker rewriting the hash, which
+    /// takes a signature (see `set_artifact_signing_key`).
+    pub fn set_integrity_check(&mut self, integrity_check: bool) {
+        let mut inner = self.inner_mut();
+        inner.integrity_check = integrity_check;
+    }
+
+    /// Sets a signer for the hash of the generated artifacts (see
+    /// `set_integrity_check`), which they are then sealed with even if
+    /// the integrity check is disabled.
+    ///
+    /// The signer is meant to return an ed25519 signature of the hash
+    /// (e.g. made by a key management service), checked by the verifier
+    /// of the engines deserializing the artifacts (see
+    /// `set_artifact_verifier`). To sign with a secret key held by the
+    /// engine, see `set_artifact_signing_key` (with the `signing`
+    /// feature).
+    pub fn set_artifact_signer<F>(&mut self, signer: F)
+    where
+        F: Fn(&[u8; INTEGRITY_HASH_LEN]) -> [u8; INTEGRITY_SIGNATURE_LEN] + Send + 'static,
+    {
+        let mut inner = self.inner_mut();
+        inner.artifact_signer = Some(Box::new(signer));
+    }
+
+    /// Sets the ed25519 secret key to sign the hash of the generated
+    /// artifacts with (see `set_artifact_signer`).
+    ///
+    /// The engines deserializing the artifacts check the signatures with
+    /// the matching public key (see `artifact_verifying_key` and
+    /// `set_artifact_verifying_key`).
+    #[cfg(feature = "signing")]
+    pub fn set_artifact_signing_key(&mut self, signing_key: &[u8; INTEGRITY_KEY_LEN]) {
+        let mut inner = self.inner_mut();
+        inner.artifact_signer = Some(integrity::ed25519_signer(signing_key));
+    }
+
+    /// Sets a verifier for the signature of the hash of the deserialized
+    /// artifacts (see `set_artifact_signer`), returning whether it is
+    /// valid.
+    ///
+    /// With a verifier, the artifacts without a valid signature are
+    /// refused (with `DeserializeError::IntegrityCheckFailed`) before
+    /// they are loaded, even if the integrity check is disabled.
+    pub fn set_artifact_verifier<F>(&mut self, verifier: F)
+    where
+        F: Fn(&[u8; INTEGRITY_HASH_LEN], &[u8; INTEGRITY_SIGNATURE_LEN]) -> bool + Send + 'static,
+    {
+        let mut inner = self.inner_mut();
+        inner.artifact_verifier = Some(Box::new(verifier));
+    }
+
+    /// Sets the ed25519 public key to check the signature of the hash of
+    /// the deserialized artifacts with (see `set_artifact_verifier` and
+    /// `set_artifact_signing_key`).
+    ///
+    /// All the artifacts are refused if the key is not a valid ed25519
+    /// public key.
+    #[cfg(feature = "signing")]
+    pub fn set_artifact_verifying_key(&mut self, verifying_key: &[u8; INTEGRITY_KEY_LEN]) {
+        let mut inner = self.inner_mut();
+        inner.artifact_verifier = Some(integrity::ed25519_verifier(verifying_key));
+    }
+
+    /// Removes all the shared objects from the cache directory (see
+    /// `set_cache_dir`), returning how many were removed.
+    pub fn clear_cache_dir(&self) -> io::Result<usize> {
+        match self.inner().cache_dir() {
+            Some(cache_dir) => cache_dir.clear(),
+            None => Ok(0),
+        }
+    }
+
+    /// Returns a snapshot of the counters of this engine: the modules
+    /// compiled, the artifacts deserialized (or that failed to), the
+    /// linker failures and the lookups in the artifact cache.
+    pub fn counters(&self) -> EngineCounters {
+        self.inner().counters().snapshot()
+    }
+
+    /// Removes the temporary files left over by crashed processes from
+    /// the system temporary directory (and the directory set with
+    /// `set_load_fallback_dir`), returning how many were removed.
+    ///
+    /// The temporary files of the artifacts (their object files and
+    /// shared objects) are removed once their code is unloaded, when the
+    /// engine is dropped, but they leak if the process crashes. Operators can call this at startup to
+    /// reclaim their space. Only the files last modified more than
+    /// `older_than` ago are removed, so the files of running compilations
+    /// are kept; on Linux, the shared objects loaded by running processes
+    /// are kept too.
+    pub fn cleanup_stale_temp_files(&self, older_than: Duration) -> io::Result<usize> {
+        let load_fallback_dir = self.inner().load_fallback_dir().map(Path::to_path_buf);
+        let mapped_files = mapped_files();
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for dir in std::iter::once(std::env::temp_dir()).chain(load_fallback_dir) {
+            for entry in fs::read_dir(&dir)? {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(_) => continue,
+                };
+                let is_temp_file = entry
+                    .file_name()
+                    .to_str()
+                    .map_or(false, |name| name.starts_with(TEMP_FILE_PREFIX));
+                let is_stale = entry.metadata().ok().map_or(false, |metadata| {
+                    metadata.is_file()
+                        && metadata
+                            .modified()
+                            .ok()
+                            .and_then(|modified| now.duration_since(modified).ok())
+                            .map_or(false, |age| age >= older_than)
+                });
+                let path = entry.path();
+                if !is_temp_file || !is_stale {
+                    continue;
+                }
+                // The mapped files are listed by their canonical path.
+                let canonical_path = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                if !mapped_files.contains(&canonical_path) && fs::remove_file(&path).is_ok() {
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+
     pub(crate) fn inner(&self) -> std::sync::MutexGuard<'_, DylibEngineInner> {
         self.inner.lock().unwrap()
     }
@@ -184,6 +887,71 @@ impl Engine for DylibEngine {
     }
 }
 
+/// The profile used to link the shared objects generated by the
+/// `DylibEngine`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, MemoryUsage)]
+pub enum LinkProfile {
+    /// Link with the default flags of the linker.
+    Default,
+    /// Link a hardened shared object: with a non-executable stack and
+    /// full RELRO (`-z noexecstack -z relro -z now`).
+    ///
+    /// These flags only exist for ELF targets (e.g. Linux); on other
+    /// targets this profile is the same as `Default`.
+    Hardened,
+    /// Link a shared object for debugging the compiled code with a
+    /// debugger like gdb or lldb: with debug information (`-g`), with
+    /// the `.eh_frame` lookup table on ELF targets (`--eh-frame-hdr`),
+    /// and without hiding any symbol (even if
+    /// `DylibEngine::set_export_required_symbols_only` is enabled).
+    ///
+    /// Whether the compiled code keeps frame pointers is up to the
+    /// compiler configuration.
+    Debug,
+}
+
+impl Default for LinkProfile {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+/// Whether compiling for `target` from the current host requires
+/// cross-compiling.
+///
+/// Only the architecture, the operating system, the binary format and
+/// whether it's an Android target are taken into account: a target that
+/// only differs from the host by its vendor or its CPU features can
+/// still be linked natively.
+#[cfg(feature = "compiler")]
+pub(crate) fn is_cross_compiling(target: &Target) -> bool {
+    let triple = target.triple();
+    let host = Triple::host();
+    triple.architecture != host.architecture
+        || triple.operating_system != host.operating_system
+        || triple.binary_format != host.binary_format
+        || is_android(triple) != is_android(&host)
+}
+
+/// Whether `triple` is an Android (bionic) target.
+#[cfg(feature = "compiler")]
+pub(crate) fn is_android(triple: &Triple) -> bool {
+    matches!(
+        triple.environment,
+        Environment::Android | Environment::Androideabi
+    )
+}
+
+/// The root directory of the Android NDK set in the environment, if any.
+#[cfg(feature = "compiler")]
+fn android_ndk_from_env() -> Option<PathBuf> {
+    ["ANDROID_NDK_HOME", "ANDROID_NDK_ROOT"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from)
+        .find(|path| path.is_dir())
+}
+
 #[derive(Clone, Copy, MemoryUsage)]
 pub(crate) enum Linker {
     None,
@@ -191,30 +959,47 @@ pub(crate) enum Linker {
     Clang10,
     Clang,
     Gcc,
+    MuslGcc,
 }
 
 impl Linker {
+    /// The first linker found for the target, if any.
     #[cfg(feature = "compiler")]
-    fn find_linker(is_cross_compiling: bool) -> Self {
-        let (possibilities, requirements): (&[_], _) = if is_cross_compiling {
-            (
-                &[Linker::Clang11, Linker::Clang10, Linker::Clang],
-                "at least one of `clang-11`, `clang-10`, or `clang`",
-            )
+    fn find_linker(is_cross_compiling: bool, is_musl: bool) -> Option<Self> {
+        let possibilities: &[_] = if is_cross_compiling {
+            &[Linker::Clang11, Linker::Clang10, Linker::Clang]
+        } else if is_musl {
+            // No libc is linked for musl targets, so `gcc` works too.
+            &[Linker::MuslGcc, Linker::Gcc]
         } else {
-            (&[Linker::Gcc], "`gcc`")
+            &[Linker::Gcc]
         };
-        *possibilities
+        possibilities
             .iter()
-            .filter(|linker| which::which(linker.executable()).is_ok())
-            .next()
-            .unwrap_or_else(|| {
-                panic!(
-                    "Need {} installed in order to use `DylibEngine` when {}cross-compiling",
-                    requirements,
-                    if is_cross_compiling { "" } else { "not " }
-                )
-            })
+            .copied()
+            .find(|linker| which::which(linker.executable()).is_ok())
+    }
+
+    /// The linkers `find_linker` looks for, to report them when none is
+    /// found.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn requirements(is_cross_compiling: bool, is_musl: bool) -> &'static str {
+        if is_cross_compiling {
+            "at least one of `clang-11`, `clang-10`, or `clang`"
+        } else if is_musl {
+            "`musl-gcc` or `gcc`"
+        } else {
+            "`gcc`"
+        }
+    }
+
+    /// The first `clang` found, if any.
+    #[cfg(feature = "compiler")]
+    fn find_clang() -> Option<Self> {
+        [Linker::Clang11, Linker::Clang10, Linker::Clang]
+            .iter()
+            .copied()
+            .find(|linker| which::which(linker.executable()).is_ok())
     }
 
     pub(crate) fn executable(self) -> &'static str {
@@ -224,6 +1009,7 @@ impl Linker {
             Self::Clang10 => "clang-10",
             Self::Clang => "clang",
             Self::Gcc => "gcc",
+            Self::MuslGcc => "musl-gcc",
         }
     }
 }
@@ -231,9 +1017,15 @@ impl Linker {
 /// The inner contents of `DylibEngine`
 #[derive(MemoryUsage)]
 pub struct DylibEngineInner {
-    /// The compiler
+    /// The compiler, locked on its own so that the engine isn't locked
+    /// while modules are compiled.
     #[cfg(feature = "compiler")]
-    compiler: Option<Box<dyn Compiler>>,
+    compiler: Option<Arc<Mutex<Box<dyn Compiler>>>>,
+
+    /// The fingerprint of the configuration of the compiler, taken when
+    /// the engine is created (see `Compiler::config_fingerprint`).
+    #[cfg(feature = "compiler")]
+    compiler_fingerprint: String,
 
     /// The WebAssembly features to use
     #[cfg(feature = "compiler")]
@@ -241,7 +1033,7 @@ pub struct DylibEngineInner {
 
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
-    signatures: SignatureRegistry,
+    signatures: Arc<SignatureRegistry>,
 
     /// The backing storage of `VMFuncRef`s. This centralized store ensures that 2
     /// functions with the same `VMCallerCheckedAnyfunc` will have the same `VMFuncRef`.
@@ -254,28 +1046,297 @@ pub struct DylibEngineInner {
     #[loupe(skip)]
     prefixer: Option<Box<dyn Fn(&[u8]) -> String + Send>>,
 
+    /// The validator enforcing a custom policy on the modules before
+    /// they are compiled, if any.
+    #[loupe(skip)]
+    module_validator: Option<Box<dyn Fn(&ModuleInfo, &[u8]) -> Result<(), CompileError> + Send>>,
+
     /// Whether the Dylib engine will cross-compile.
     is_cross_compiling: bool,
 
     /// The linker to use.
     linker: Linker,
 
-    /// List of libraries loaded by this engine.
+    /// The maximum size of a Wasm module accepted for compilation.
+    max_module_bytes: Option<usize>,
+
+    /// The maximum size of the object file generated for a module.
+    max_object_bytes: Option<usize>,
+
+    /// The maximum number of relocations of the compiled code of a module.
+    max_relocations: Option<usize>,
+
+    /// The install name to give to generated `.dylib`s on Apple targets.
+    macos_install_name: Option<String>,
+
+    /// The runtime search paths to add to generated `.dylib`s on Apple targets.
+    macos_rpaths: Vec<String>,
+
+    /// The sanitizers to link the generated shared objects with.
+    sanitizers: Vec<String>,
+
+    /// The command to link with (the program and its first arguments),
+    /// if not the detected linker.
+    linker_command: Option<Vec<String>>,
+
+    /// The arguments to pass to the linker after the ones of the engine.
+    extra_link_args: Vec<String>,
+
+    /// The soname to give to generated `.so`s on ELF targets, if not
+    /// derived from the module prefix.
+    elf_soname: Option<String>,
+
+    /// The naming scheme of the symbols of the generated shared objects.
+    symbol_naming: SymbolNaming,
+
+    /// A runtime object file or archive to link into generated shared
+    /// objects, providing the libcalls.
+    runtime_object: Option<PathBuf>,
+
+    /// Whether to collect compilation metrics, and for how many of the
+    /// slowest batches of functions.
+    metrics_slowest_functions: Option<usize>,
+
+    /// In how many batches the functions are timed.
+    metrics_batches: usize,
+
+    /// The profile used to link the generated shared objects.
+    link_profile: LinkProfile,
+
+    /// The name of the symbol holding the module metadata.
+    metadata_symbol: String,
+
+    /// Counters of the compilation and deserialization outcomes.
+    #[loupe(skip)]
+    counters: AtomicEngineCounters,
+
+    /// The recently compiled artifacts.
+    #[loupe(skip)]
+    artifact_cache: ArtifactCache,
+
+    /// Whether to generate the import library of `.dll`s.
+    windows_import_library: bool,
+
+    /// Whether to split the debug info of `.so`s into a separate file.
+    split_debug_info: bool,
+
+    /// Whether to only export the symbols needed to load the shared objects.
+    export_required_symbols_only: bool,
+
+    /// Whether to only export the functions exported by the Wasm module.
+    export_module_functions_only: bool,
+
+    /// Whether to defer the registration of the artifacts signatures to
+    /// their first instantiation.
+    defer_signature_registration: bool,
+
+    /// Whether to record the relocations written to the object files.
+    record_relocations: bool,
+
+    /// The name of the symbol to store the data initializers in, if any.
+    data_initializers_symbol: Option<String>,
+
+    /// Whether to stop the compilation after writing the object file.
+    object_only: bool,
+
+    /// The size above which custom sections get their own object section.
+    large_custom_section_threshold: Option<usize>,
+
+    /// The relocation model of the generated objects.
     #[loupe(skip)]
-    libraries: Vec<Library>,
+    relocation_model: RelocationModel,
+
+    /// Whether the artifacts keep their compiled code.
+    incremental_linking: bool,
+
+    /// The directory to copy the shared objects to when loading them in
+    /// place fails, if not the system temporary directory.
+    load_fallback_dir: Option<PathBuf>,
+
+    /// The root directory of the Android NDK, if any.
+    android_ndk: Option<PathBuf>,
+
+    /// Whether to embed a build-id into the shared objects.
+    build_id: bool,
+
+    /// The alignment of the code in the object files.
+    text_alignment: u64,
+
+    /// The alignment of the read-only data in the object files.
+    data_alignment: u64,
+
+    /// Whether to check the bounds of the relocations before linking.
+    verify_relocations: bool,
+
+    /// Whether undefined symbols are looked up at load time.
+    dynamic_lookup: bool,
+
+    /// The prefix of the C-ABI wrappers of the exported functions, if
+    /// any.
+    c_abi_export_prefix: Option<String>,
+
+    /// Whether the object files are loaded in process instead of being
+    /// linked into shared objects.
+    in_process_linking: bool,
+
+    /// Whether the artifacts keep the object file they were linked from.
+    keep_object_files: bool,
+
+    /// The directory to keep the shared objects in, if any.
+    cache_dir: Option<PathBuf>,
+
+    /// The maximum total size of the shared objects in the cache
+    /// directory, if any.
+    cache_dir_max_bytes: Option<u64>,
+
+    /// Whether to seal the artifacts with their hash, and require it when
+    /// deserializing them.
+    integrity_check: bool,
+
+    /// The signer of the hash of the artifacts, if any.
+    #[loupe(skip)]
+    artifact_signer: Option<ArtifactSigner>,
+
+    /// The verifier of the signature of the artifacts, if any.
+    #[loupe(skip)]
+    artifact_verifier: Option<ArtifactVerifier>,
+
+    /// The shared objects loaded by the artifacts of this engine (see
+    /// `LoadedCode`).
+    #[loupe(skip)]
+    libraries: Vec<Arc<LoadedCode<Library>>>,
+
+    /// The object files loaded in process by the artifacts of this
+    /// engine (see `LoadedCode`).
+    #[loupe(skip)]
+    loaded_objects: Vec<Arc<LoadedCode<LoadedObject>>>,
+}
+
+impl Default for DylibEngineInner {
+    /// The inner contents of a headless engine with the default settings.
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "compiler")]
+            compiler: None,
+            #[cfg(feature = "compiler")]
+            compiler_fingerprint: String::new(),
+            #[cfg(feature = "compiler")]
+            features: Features::default(),
+            signatures: Arc::new(SignatureRegistry::new()),
+            func_data: Arc::new(FuncDataRegistry::new()),
+            prefixer: None,
+            module_validator: None,
+            is_cross_compiling: false,
+            linker: Linker::None,
+            max_module_bytes: None,
+            max_object_bytes: None,
+            max_relocations: None,
+            macos_install_name: None,
+            macos_rpaths: vec![],
+            sanitizers: vec![],
+            linker_command: None,
+            extra_link_args: vec![],
+            elf_soname: None,
+            symbol_naming: SymbolNaming::default(),
+            runtime_object: None,
+            metrics_slowest_functions: None,
+            metrics_batches: DEFAULT_METRICS_BATCHES,
+            link_profile: LinkProfile::Default,
+            metadata_symbol: DEFAULT_METADATA_SYMBOL.to_string(),
+            counters: AtomicEngineCounters::default(),
+            artifact_cache: ArtifactCache::default(),
+            windows_import_library: false,
+            split_debug_info: false,
+            export_required_symbols_only: false,
+            export_module_functions_only: false,
+            defer_signature_registration: false,
+            record_relocations: false,
+            data_initializers_symbol: None,
+            object_only: false,
+            large_custom_section_threshold: None,
+            relocation_model: RelocationModel::Pic,
+            incremental_linking: false,
+            load_fallback_dir: None,
+            android_ndk: None,
+            build_id: false,
+            text_alignment: DEFAULT_TEXT_ALIGNMENT,
+            data_alignment: DEFAULT_DATA_ALIGNMENT,
+            verify_relocations: false,
+            dynamic_lookup: true,
+            c_abi_export_prefix: None,
+            in_process_linking: false,
+            keep_object_files: false,
+            cache_dir: None,
+            cache_dir_max_bytes: None,
+            integrity_check: false,
+            artifact_signer: None,
+            artifact_verifier: None,
+            libraries: vec![],
+            loaded_objects: vec![],
+        }
+    }
 }
 
 impl DylibEngineInner {
     /// Gets the compiler associated to this engine.
+    ///
+    /// The compiler is shared with the compilations in progress, which
+    /// lock it instead of the engine.
     #[cfg(feature = "compiler")]
-    pub fn compiler(&self) -> Result<&dyn Compiler, CompileError> {
+    pub fn compiler(&self) -> Result<Arc<Mutex<Box<dyn Compiler>>>, CompileError> {
         if self.compiler.is_none() {
             return Err(CompileError::Codegen("The `DylibEngine` is operating in headless mode, so it can only execute already compiled Modules.".to_string()));
         }
-        Ok(&**self
-            .compiler
-            .as_ref()
-            .expect("Can't get compiler reference"))
+        Ok(self.compiler.clone().expect("Can't get compiler reference"))
+    }
+
+    /// A fingerprint of everything the artifacts compiled by this engine
+    /// depend on besides the module, its tunables and the target: the
+    /// configuration of the compiler, the features, and the settings of
+    /// the engine changing the generated shared objects.
+    ///
+    /// The middlewares of the compiler are only told apart by their
+    /// `Debug` representation, and the signers of the artifacts not at
+    /// all (the cached artifacts are checked by the verifier anyway).
+    #[cfg(feature = "compiler")]
+    pub(crate) fn settings_fingerprint(&self) -> String {
+        format!(
+            "{:?}",
+            (
+                (&self.compiler_fingerprint, &self.features),
+                (
+                    self.relocation_model,
+                    self.text_alignment,
+                    self.data_alignment,
+                    self.large_custom_section_threshold,
+                    self.in_process_linking,
+                ),
+                (
+                    &self.symbol_naming,
+                    &self.metadata_symbol,
+                    &self.data_initializers_symbol,
+                    &self.c_abi_export_prefix,
+                    self.export_required_symbols_only,
+                    self.export_module_functions_only,
+                    self.record_relocations,
+                ),
+                (
+                    self.link_profile,
+                    &self.linker_command,
+                    &self.extra_link_args,
+                    &self.sanitizers,
+                    &self.runtime_object,
+                    self.dynamic_lookup,
+                    &self.elf_soname,
+                    &self.macos_install_name,
+                    &self.macos_rpaths,
+                    self.windows_import_library,
+                    self.split_debug_info,
+                    self.build_id,
+                ),
+                (self.integrity_check, self.artifact_signer.is_some()),
+            )
+        )
     }
 
     #[cfg(feature = "compiler")]
@@ -287,6 +1348,18 @@ impl DylibEngineInner {
         }
     }
 
+    #[cfg(feature = "compiler")]
+    pub(crate) fn validate_module(
+        &self,
+        module: &ModuleInfo,
+        data: &[u8],
+    ) -> Result<(), CompileError> {
+        match &self.module_validator {
+            Some(module_validator) => module_validator(module, data),
+            None => Ok(()),
+        }
+    }
+
     #[cfg(feature = "compiler")]
     pub(crate) fn features(&self) -> &Features {
         &self.features
@@ -295,7 +1368,9 @@ impl DylibEngineInner {
     /// Validate the module
     #[cfg(feature = "compiler")]
     pub fn validate<'data>(&self, data: &'data [u8]) -> Result<(), CompileError> {
-        self.compiler()?.validate_module(self.features(), data)
+        let compiler = self.compiler()?;
+        let compiler = compiler.lock().unwrap();
+        compiler.validate_module(self.features(), data)
     }
 
     /// Validate the module
@@ -311,6 +1386,14 @@ impl DylibEngineInner {
         &self.signatures
     }
 
+    pub(crate) fn signature_registry(&self) -> &Arc<SignatureRegistry> {
+        &self.signatures
+    }
+
+    pub(crate) fn defer_signature_registration(&self) -> bool {
+        self.defer_signature_registration
+    }
+
     /// Shared func metadata registry.
     pub(crate) fn func_data(&self) -> &Arc<FuncDataRegistry> {
         &self.func_data
@@ -324,7 +1407,299 @@ impl DylibEngineInner {
         self.linker
     }
 
-    pub(crate) fn add_library(&mut self, library: Library) {
+    #[cfg(feature = "compiler")]
+    pub(crate) fn macos_install_name(&self) -> Option<&str> {
+        self.macos_install_name.as_deref()
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn macos_rpaths(&self) -> &[String] {
+        &self.macos_rpaths
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn sanitizers(&self) -> &[String] {
+        &self.sanitizers
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn linker_command(&self) -> Option<&[String]> {
+        self.linker_command.as_deref()
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn extra_link_args(&self) -> &[String] {
+        &self.extra_link_args
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn elf_soname(&self) -> Option<&str> {
+        self.elf_soname.as_deref()
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn symbol_naming(&self) -> &SymbolNaming {
+        &self.symbol_naming
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn runtime_object(&self) -> Option<&Path> {
+        self.runtime_object.as_deref()
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn metrics_slowest_functions(&self) -> Option<usize> {
+        self.metrics_slowest_functions
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn metrics_batches(&self) -> usize {
+        self.metrics_batches
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn link_profile(&self) -> LinkProfile {
+        self.link_profile
+    }
+
+    pub(crate) fn metadata_symbol(&self) -> &str {
+        &self.metadata_symbol
+    }
+
+    pub(crate) fn counters(&self) -> &AtomicEngineCounters {
+        &self.counters
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn artifact_cache(&mut self) -> &mut ArtifactCache {
+        &mut self.artifact_cache
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn windows_import_library(&self) -> bool {
+        self.windows_import_library
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn split_debug_info(&self) -> bool {
+        self.split_debug_info
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn export_required_symbols_only(&self) -> bool {
+        self.export_required_symbols_only
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn export_module_functions_only(&self) -> bool {
+        self.export_module_functions_only
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn record_relocations(&self) -> bool {
+        self.record_relocations
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn data_initializers_symbol(&self) -> Option<&str> {
+        self.data_initializers_symbol.as_deref()
+    }
+
+    pub(crate) fn load_fallback_dir(&self) -> Option<&Path> {
+        self.load_fallback_dir.as_deref()
+    }
+
+    /// The `clang` of the Android NDK, if set.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn android_ndk_clang(&self) -> Option<PathBuf> {
+        let host_tag = if cfg!(target_os = "windows") {
+            "windows-x86_64"
+        } else if cfg!(target_os = "macos") {
+            // The NDK only ships x86_64 binaries for macOS, run with
+            // Rosetta on Apple Silicon.
+            "darwin-x86_64"
+        } else {
+            "linux-x86_64"
+        };
+        let clang = if cfg!(target_os = "windows") {
+            "clang.exe"
+        } else {
+            "clang"
+        };
+        self.android_ndk.as_ref().map(|android_ndk| {
+            android_ndk
+                .join("toolchains/llvm/prebuilt")
+                .join(host_tag)
+                .join("bin")
+                .join(clang)
+        })
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn object_only(&self) -> bool {
+        self.object_only
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn large_custom_section_threshold(&self) -> Option<usize> {
+        self.large_custom_section_threshold
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn relocation_model(&self) -> RelocationModel {
+        self.relocation_model
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn incremental_linking(&self) -> bool {
+        self.incremental_linking
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn build_id(&self) -> bool {
+        self.build_id
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn text_alignment(&self) -> u64 {
+        self.text_alignment
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn data_alignment(&self) -> u64 {
+        self.data_alignment
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn verify_relocations(&self) -> bool {
+        self.verify_relocations
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn dynamic_lookup(&self) -> bool {
+        self.dynamic_lookup
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn c_abi_export_prefix(&self) -> Option<&str> {
+        self.c_abi_export_prefix.as_deref()
+    }
+
+    pub(crate) fn in_process_linking(&self) -> bool {
+        self.in_process_linking
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn keep_object_files(&self) -> bool {
+        self.keep_object_files
+    }
+
+    /// Whether the generated artifacts are sealed with their hash.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn seals_artifacts(&self) -> bool {
+        self.integrity_check || self.artifact_signer.is_some()
+    }
+
+    /// Whether the deserialized artifacts must have a valid hash.
+    pub(crate) fn requires_integrity(&self) -> bool {
+        self.integrity_check || self.artifact_verifier.is_some()
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn artifact_signer(&self) -> Option<&ArtifactSigner> {
+        self.artifact_signer.as_ref()
+    }
+
+    pub(crate) fn artifact_verifier(&self) -> Option<&ArtifactVerifier> {
+        self.artifact_verifier.as_ref()
+    }
+
+    /// Keeps a shared object loaded as long as the engine lives.
+    pub(crate) fn add_library(&mut self, library: Arc<LoadedCode<Library>>) {
         self.libraries.push(library);
     }
+
+    /// Keeps an object file loaded in process as long as the engine
+    /// lives.
+    pub(crate) fn add_loaded_object(&mut self, loaded_object: Arc<LoadedCode<LoadedObject>>) {
+        self.loaded_objects.push(loaded_object);
+    }
+
+    pub(crate) fn cache_dir(&self) -> Option<CacheDir> {
+        self.cache_dir
+            .clone()
+            .map(|dir| CacheDir::new(dir, self.cache_dir_max_bytes))
+    }
+
+    /// Whether the alignments of the object files are the default ones,
+    /// which compilers emitting the object file themselves honor.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn has_default_alignments(&self) -> bool {
+        self.text_alignment == DEFAULT_TEXT_ALIGNMENT
+            && self.data_alignment == DEFAULT_DATA_ALIGNMENT
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn check_module_size(&self, size: usize) -> Result<(), CompileError> {
+        match self.max_module_bytes {
+            Some(max) if size > max => Err(CompileError::Resource(format!(
+                "the module is {} bytes, which exceeds the limit of {} bytes",
+                size, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn check_object_size(&self, size: usize) -> Result<(), CompileError> {
+        match self.max_object_bytes {
+            Some(max) if size > max => Err(CompileError::Resource(format!(
+                "the generated object file is {} bytes, which exceeds the limit of {} bytes",
+                size, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn max_relocations(&self) -> Option<usize> {
+        self.max_relocations
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn check_relocation_count(&self, count: usize) -> Result<(), CompileError> {
+        match self.max_relocations {
+            Some(max) if count > max => Err(CompileError::Resource(format!(
+                "the compiled code has {} relocations, which exceeds the limit of {}",
+                count, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+    use wasmer_compiler::{Architecture, CpuFeature};
+
+    #[test]
+    fn cpu_features_dont_trigger_cross_compiling() {
+        let host = Triple::host();
+        assert!(!is_cross_compiling(&Target::new(
+            host.clone(),
+            CpuFeature::for_host()
+        )));
+        assert!(!is_cross_compiling(&Target::new(
+            host.clone(),
+            CpuFeature::set()
+        )));
+
+        let mut other_architecture = host;
+        other_architecture.architecture = Architecture::Wasm32;
+        assert!(is_cross_compiling(&Target::new(
+            other_architecture,
+            CpuFeature::for_host()
+        )));
+    }
 }
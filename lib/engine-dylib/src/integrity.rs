@@ -0,0 +1,214 @@
+//! Integrity records of the artifacts, checked before they are loaded
+//! (see `DylibEngine::set_integrity_check`).
+//!
+//! The record is the contents of the `WASMER_INTEGRITY` symbol: a magic
+//! number, flags, the BLAKE3 hash of the artifact (computed with the
+//! record zeroed past its magic number) and an optional signature of
+//! that hash (an ed25519 one with the `signing` feature). The symbol is
+//! emitted with a zeroed record, which is filled once the artifact is
+//! linked, in place.
+
+#[cfg(feature = "signing")]
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer};
+#[cfg(feature = "signing")]
+use std::convert::TryFrom;
+use std::ops::Range;
+use wasmer_engine::DeserializeError;
+
+/// The name of the symbol holding the integrity record.
+pub const INTEGRITY_SYMBOL: &str = "WASMER_INTEGRITY";
+
+/// The length of the content hash of an artifact.
+pub const INTEGRITY_HASH_LEN: usize = 32;
+
+/// The length of the signature of an artifact (an ed25519 signature).
+pub const INTEGRITY_SIGNATURE_LEN: usize = 64;
+
+/// The length of the ed25519 keys signing and verifying the artifacts.
+#[cfg(feature = "signing")]
+pub const INTEGRITY_KEY_LEN: usize = 32;
+
+/// Signs the content hash of an artifact.
+pub(crate) type ArtifactSigner =
+    Box<dyn Fn(&[u8; INTEGRITY_HASH_LEN]) -> [u8; INTEGRITY_SIGNATURE_LEN] + Send>;
+
+/// Checks the signature of the content hash of an artifact.
+pub(crate) type ArtifactVerifier =
+    Box<dyn Fn(&[u8; INTEGRITY_HASH_LEN], &[u8; INTEGRITY_SIGNATURE_LEN]) -> bool + Send>;
+
+const INTEGRITY_MAGIC: &[u8; 8] = b"WASMERIN";
+
+/// The ed25519 key pair of the secret key `signing_key` (any 32 bytes,
+/// which should be random).
+#[cfg(feature = "signing")]
+fn keypair(signing_key: &[u8; INTEGRITY_KEY_LEN]) -> Keypair {
+    let secret = SecretKey::from_bytes(signing_key).expect("the secret key has the right length");
+    let public = PublicKey::from(&secret);
+    Keypair { secret, public }
+}
+
+/// The ed25519 public key verifying the signatures made with the secret
+/// key `signing_key` (see `DylibEngine::set_artifact_signing_key`).
+#[cfg(feature = "signing")]
+pub fn artifact_verifying_key(signing_key: &[u8; INTEGRITY_KEY_LEN]) -> [u8; INTEGRITY_KEY_LEN] {
+    keypair(signing_key).public.to_bytes()
+}
+
+/// A signer making ed25519 signatures with the secret key `signing_key`.
+#[cfg(feature = "signing")]
+pub(crate) fn ed25519_signer(signing_key: &[u8; INTEGRITY_KEY_LEN]) -> ArtifactSigner {
+    let keypair = keypair(signing_key);
+    Box::new(move |hash| keypair.sign(hash).to_bytes())
+}
+
+/// A verifier checking ed25519 signatures with the public key
+/// `verifying_key`, which rejects all of them if the key is invalid.
+#[cfg(feature = "signing")]
+pub(crate) fn ed25519_verifier(verifying_key: &[u8; INTEGRITY_KEY_LEN]) -> ArtifactVerifier {
+    let public = PublicKey::from_bytes(verifying_key).ok();
+    Box::new(
+        move |hash, signature| match (&public, Signature::try_from(&signature[..])) {
+            (Some(public), Ok(signature)) => public.verify_strict(hash, &signature).is_ok(),
+            _ => false,
+        },
+    )
+}
+
+/// The flag set once the record holds the hash.
+const FLAG_SEALED: u32 = 1;
+
+/// The flag set when the record holds a signature.
+const FLAG_SIGNED: u32 = 2;
+
+/// The offsets of the fields of the record: the magic number, the flags
+/// (in little-endian), 4 reserved bytes, the hash and the signature.
+const FLAGS_OFFSET: usize = 8;
+const HASH_OFFSET: usize = 16;
+const SIGNATURE_OFFSET: usize = HASH_OFFSET + INTEGRITY_HASH_LEN;
+
+/// The length of the record.
+pub(crate) const INTEGRITY_RECORD_LEN: usize = SIGNATURE_OFFSET + INTEGRITY_SIGNATURE_LEN;
+
+/// The record emitted in the object files, before the artifact is
+/// sealed.
+#[cfg(feature = "compiler")]
+pub(crate) fn empty_record() -> Vec<u8> {
+    let mut record = vec![0; INTEGRITY_RECORD_LEN];
+    record[..INTEGRITY_MAGIC.len()].copy_from_slice(INTEGRITY_MAGIC);
+    record
+}
+
+/// The content hash of `bytes`, with the record at `record` zeroed.
+fn content_hash(bytes: &[u8], record: Range<usize>) -> [u8; INTEGRITY_HASH_LEN] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&bytes[..record.start]);
+    hasher.update(INTEGRITY_MAGIC);
+    hasher.update(&[0; INTEGRITY_RECORD_LEN - INTEGRITY_MAGIC.len()]);
+    hasher.update(&bytes[record.end..]);
+    *hasher.finalize().as_bytes()
+}
+
+/// Fill the record at `record` in the bytes of an artifact with their
+/// hash, signed if a signer is given.
+#[cfg(feature = "compiler")]
+pub(crate) fn seal(bytes: &mut [u8], record: Range<usize>, signer: Option<&ArtifactSigner>) {
+    let hash = content_hash(bytes, record.clone());
+    let record = &mut bytes[record];
+    let mut flags = FLAG_SEALED;
+    if let Some(signer) = signer {
+        record[SIGNATURE_OFFSET..].copy_from_slice(&signer(&hash));
+        flags |= FLAG_SIGNED;
+    }
+    record[FLAGS_OFFSET..FLAGS_OFFSET + 4].copy_from_slice(&flags.to_le_bytes());
+    record[HASH_OFFSET..SIGNATURE_OFFSET].copy_from_slice(&hash);
+}
+
+/// Check the bytes of an artifact against the record at `record`, and
+/// its signature if a verifier is given.
+pub(crate) fn verify(
+    bytes: &[u8],
+    record: Range<usize>,
+    verifier: Option<&ArtifactVerifier>,
+) -> Result<(), DeserializeError> {
+    let failed = |message: &str| Err(DeserializeError::IntegrityCheckFailed(message.to_string()));
+    let contents = &bytes[record.clone()];
+    if !contents.starts_with(INTEGRITY_MAGIC) {
+        return failed("The integrity record is malformed");
+    }
+    let mut flags = [0; 4];
+    flags.copy_from_slice(&contents[FLAGS_OFFSET..FLAGS_OFFSET + 4]);
+    let flags = u32::from_le_bytes(flags);
+    if flags & FLAG_SEALED == 0 {
+        return failed("The artifact was not sealed after it was linked");
+    }
+    let hash = content_hash(bytes, record);
+    if contents[HASH_OFFSET..SIGNATURE_OFFSET] != hash {
+        return failed("The contents of the artifact don't match its hash");
+    }
+    if let Some(verifier) = verifier {
+        if flags & FLAG_SIGNED == 0 {
+            return failed("The artifact is not signed");
+        }
+        let mut signature = [0; INTEGRITY_SIGNATURE_LEN];
+        signature.copy_from_slice(&contents[SIGNATURE_OFFSET..]);
+        if !verifier(&hash, &signature) {
+            return failed("The signature of the artifact is invalid");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "compiler"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_verify() {
+        let mut bytes = b"code".to_vec();
+        bytes.extend(empty_record());
+        bytes.extend(b"data");
+        let record = 4..4 + INTEGRITY_RECORD_LEN;
+        assert!(verify(&bytes, record.clone(), None).is_err());
+
+        let signer: ArtifactSigner = Box::new(|hash| {
+            let mut signature = [0; INTEGRITY_SIGNATURE_LEN];
+            signature[..INTEGRITY_HASH_LEN].copy_from_slice(hash);
+            signature
+        });
+        let verifier: ArtifactVerifier =
+            Box::new(|hash, signature| &signature[..INTEGRITY_HASH_LEN] == hash);
+        seal(&mut bytes, record.clone(), None);
+        verify(&bytes, record.clone(), None).unwrap();
+        // A signature is required by a verifier.
+        assert!(verify(&bytes, record.clone(), Some(&verifier)).is_err());
+
+        seal(&mut bytes, record.clone(), Some(&signer));
+        verify(&bytes, record.clone(), Some(&verifier)).unwrap();
+        let rejecting: ArtifactVerifier = Box::new(|_, _| false);
+        assert!(verify(&bytes, record.clone(), Some(&rejecting)).is_err());
+
+        bytes[0] ^= 1;
+        match verify(&bytes, record, None) {
+            Err(DeserializeError::IntegrityCheckFailed(message)) => {
+                assert!(message.contains("hash"), "{}", message)
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn seal_and_verify_ed25519() {
+        let mut bytes = b"code".to_vec();
+        bytes.extend(empty_record());
+        let record = 4..4 + INTEGRITY_RECORD_LEN;
+
+        let signing_key = [7; INTEGRITY_KEY_LEN];
+        let signer = ed25519_signer(&signing_key);
+        seal(&mut bytes, record.clone(), Some(&signer));
+        let verifier = ed25519_verifier(&artifact_verifying_key(&signing_key));
+        verify(&bytes, record.clone(), Some(&verifier)).unwrap();
+        let other_verifier = ed25519_verifier(&artifact_verifying_key(&[8; INTEGRITY_KEY_LEN]));
+        assert!(verify(&bytes, record, Some(&other_verifier)).is_err());
+    }
+}
@@ -24,13 +24,38 @@
 )]
 
 mod artifact;
+#[cfg(feature = "async")]
+mod asynchronous;
 mod builder;
+mod cache;
+#[cfg(feature = "compiler")]
+mod cancellation;
 mod engine;
+mod integrity;
+mod loader;
+mod metrics;
 mod serialize;
+#[cfg(feature = "testing")]
+pub mod testing;
 
+#[cfg(feature = "compiler")]
+pub use crate::artifact::BatchArtifacts;
 pub use crate::artifact::DylibArtifact;
 pub use crate::builder::Dylib;
-pub use crate::engine::DylibEngine;
+#[cfg(feature = "compiler")]
+pub use crate::cancellation::CancellationToken;
+pub use crate::engine::{DylibEngine, LinkProfile};
+#[cfg(feature = "signing")]
+pub use crate::integrity::{artifact_verifying_key, INTEGRITY_KEY_LEN};
+pub use crate::integrity::{INTEGRITY_HASH_LEN, INTEGRITY_SIGNATURE_LEN, INTEGRITY_SYMBOL};
+pub use crate::metrics::{CompilationMetrics, EngineCounters, LinkerUsage};
+pub use crate::serialize::{
+    component_adapter_symbol_name, decode_data_initializers, encode_data_initializers,
+    function_symbol_name, parse_function_symbol, CAbiExport, ComponentAdapter, ModuleMetadata,
+    ModuleMetadataSymbolRegistry, SymbolNaming, COMPONENT_ADAPTER_SYMBOL_PREFIX,
+    FUNCTION_SYMBOL_PREFIX, METADATA_FORMAT_VERSION,
+};
+pub use wasmer_object::{RelocationModel, RelocationRecord};
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
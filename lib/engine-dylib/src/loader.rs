@@ -0,0 +1,579 @@
+//! Loading of the object files of `DylibArtifact`s in process, without
+//! linking them into a shared object with an external linker (see
+//! `DylibEngine::set_in_process_linking`).
+//!
+//! The sections of the object file are copied into memory, its
+//! relocations are applied like a static linker would, and its code is
+//! then made executable. Only the x86_64 and AArch64 ELF object files
+//! generated by this engine are supported, on Unix hosts.
+
+use object::elf;
+use object::read::{Relocation, SectionIndex};
+use object::{
+    Architecture, BinaryFormat, Object, ObjectSection, ObjectSymbol, RelocationKind,
+    RelocationTarget, SectionKind, SymbolKind,
+};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use wasmer_compiler::CompileError;
+use wasmer_vm::libcalls::LibCall;
+use wasmer_vm::Mmap;
+
+/// All the libcalls, which are resolved to the functions of the runtime
+/// instead of being looked up in the host process.
+const LIBCALLS: &[LibCall] = &[
+    LibCall::CeilF32,
+    LibCall::CeilF64,
+    LibCall::FloorF32,
+    LibCall::FloorF64,
+    LibCall::NearestF32,
+    LibCall::NearestF64,
+    LibCall::TruncF32,
+    LibCall::TruncF64,
+    LibCall::Memory32Size,
+    LibCall::ImportedMemory32Size,
+    LibCall::TableCopy,
+    LibCall::TableInit,
+    LibCall::TableFill,
+    LibCall::TableSize,
+    LibCall::ImportedTableSize,
+    LibCall::TableGet,
+    LibCall::ImportedTableGet,
+    LibCall::TableSet,
+    LibCall::ImportedTableSet,
+    LibCall::TableGrow,
+    LibCall::ImportedTableGrow,
+    LibCall::FuncRef,
+    LibCall::ElemDrop,
+    LibCall::Memory32Copy,
+    LibCall::ImportedMemory32Copy,
+    LibCall::Memory32Fill,
+    LibCall::ImportedMemory32Fill,
+    LibCall::Memory32Init,
+    LibCall::DataDrop,
+    LibCall::RaiseTrap,
+    LibCall::Probestack,
+];
+
+/// The size of a stub jumping to an absolute address, used for calls to
+/// external functions out of the range of the call instruction.
+const STUB_SIZE: usize = 16;
+
+/// The size of an entry of the global offset table.
+const GOT_ENTRY_SIZE: usize = 8;
+
+/// `jmp [rip + 0]`, followed by the address.
+const X86_64_STUB: &[u8] = &[0xff, 0x25, 0, 0, 0, 0];
+
+/// `ldr x16, #8; br x16`, followed by the address.
+const AARCH64_STUB: &[u8] = &[0x50, 0x00, 0x00, 0x58, 0x00, 0x02, 0x1f, 0xd6];
+
+fn to_compile_error(err: impl std::error::Error) -> CompileError {
+    CompileError::Codegen(err.to_string())
+}
+
+/// The error of an object file whose layout is inconsistent, e.g. with
+/// a relocation out of the bounds of its section.
+fn malformed(message: &str) -> CompileError {
+    CompileError::Codegen(format!("Malformed object file: {}", message))
+}
+
+/// `offset` rounded up to `alignment`, unless it overflows.
+fn align(offset: usize, alignment: u64) -> Option<usize> {
+    let alignment = usize::try_from(alignment.max(1)).ok()?;
+    Some(offset.checked_add(alignment - 1)? / alignment * alignment)
+}
+
+/// The address of an external symbol: a libcall, or else a symbol of the
+/// host process.
+fn resolve_external(name: &str) -> Option<usize> {
+    if let Some(libcall) = LIBCALLS
+        .iter()
+        .find(|libcall| libcall.to_function_name() == name)
+    {
+        return Some(libcall.function_pointer());
+    }
+    resolve_host_symbol(name)
+}
+
+#[cfg(unix)]
+fn resolve_host_symbol(name: &str) -> Option<usize> {
+    let host = libloading::os::unix::Library::this();
+    let symbol = unsafe { host.get::<*const u8>(name.as_bytes()).ok()? };
+    Some(*symbol as usize)
+}
+
+#[cfg(not(unix))]
+fn resolve_host_symbol(_name: &str) -> Option<usize> {
+    None
+}
+
+/// The size of the memory pages of the host.
+#[cfg(unix)]
+pub(crate) fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn page_size() -> usize {
+    4096
+}
+
+#[cfg(unix)]
+fn make_executable(memory: &Mmap, len: usize) -> Result<(), CompileError> {
+    let result = unsafe {
+        libc::mprotect(
+            memory.as_ptr() as *mut libc::c_void,
+            len,
+            libc::PROT_READ | libc::PROT_EXEC,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(to_compile_error(std::io::Error::last_os_error()))
+    }
+}
+
+#[cfg(not(unix))]
+fn make_executable(_memory: &Mmap, _len: usize) -> Result<(), CompileError> {
+    Err(CompileError::Codegen(
+        "In-process linking is only supported on Unix".to_string(),
+    ))
+}
+
+/// Where a section of the object file is loaded.
+#[derive(Clone, Copy)]
+struct Placement {
+    /// The offset of the section in the memory.
+    offset: usize,
+    /// The size of the section.
+    size: usize,
+    /// The address of the section in the object file, which the
+    /// addresses of its symbols are relative to.
+    address: u64,
+}
+
+/// The object file of an artifact, loaded in memory with its relocations
+/// applied.
+///
+/// The code is read-only and executable, the data writable. The memory
+/// is unmapped when this is dropped, so the code must not be running
+/// anymore by then.
+pub(crate) struct LoadedObject {
+    /// The code (followed by the stubs), then the data (followed by the
+    /// global offset table), each starting on a new page.
+    memory: Mmap,
+    /// The addresses of the symbols defined by the object file.
+    symbols: HashMap<String, usize>,
+}
+
+/// The state of the loading of an object file into memory.
+struct Loader {
+    memory: Mmap,
+    architecture: Architecture,
+    placements: HashMap<SectionIndex, Placement>,
+    /// The offset of the next stub, and the stubs of the external
+    /// symbols by name.
+    next_stub: usize,
+    stubs: HashMap<String, usize>,
+    /// The offset of the next entry of the global offset table, and the
+    /// entries of the external symbols by name.
+    next_got_entry: usize,
+    got_entries: HashMap<String, usize>,
+}
+
+impl LoadedObject {
+    /// Load a relocatable object file generated by this engine for the
+    /// host.
+    ///
+    /// The sections, symbols and relocations are checked to be within
+    /// the bounds of the file, so a malformed file fails to load instead
+    /// of corrupting the memory.
+    pub(crate) fn load(bytes: &[u8]) -> Result<Self, CompileError> {
+        let file = object::File::parse(bytes).map_err(to_compile_error)?;
+        let architecture = file.architecture();
+        let host_architecture = if cfg!(target_arch = "x86_64") {
+            Architecture::X86_64
+        } else if cfg!(target_arch = "aarch64") {
+            Architecture::Aarch64
+        } else {
+            Architecture::Unknown
+        };
+        if file.format() != BinaryFormat::Elf
+            || architecture != host_architecture
+            || architecture == Architecture::Unknown
+        {
+            return Err(CompileError::Codegen(format!(
+                "In-process linking only supports x86_64 and AArch64 ELF object files \
+                 for the host, not {:?} {:?} ones",
+                architecture,
+                file.format()
+            )));
+        }
+
+        // Lay out the sections: the code first, then the data.
+        let too_large = || malformed("the sections are too large");
+        let mut code_size = 0;
+        let mut data_size = 0;
+        let mut placements = Vec::new();
+        for section in file.sections() {
+            let size = match section.kind() {
+                SectionKind::Text => &mut code_size,
+                SectionKind::Data
+                | SectionKind::ReadOnlyData
+                | SectionKind::ReadOnlyString
+                | SectionKind::UninitializedData => &mut data_size,
+                _ => continue,
+            };
+            let offset = align(*size, section.align()).ok_or_else(too_large)?;
+            let section_size = usize::try_from(section.size()).map_err(|_| too_large())?;
+            *size = offset.checked_add(section_size).ok_or_else(too_large)?;
+            placements.push((
+                section.kind() == SectionKind::Text,
+                section,
+                offset,
+                section_size,
+            ));
+        }
+        let external_symbols = file
+            .symbols()
+            .filter(|symbol| symbol.is_undefined() && !symbol.name().unwrap_or("").is_empty())
+            .count();
+        let stubs_offset = align(code_size, STUB_SIZE as u64).ok_or_else(too_large)?;
+        let code_len = external_symbols
+            .checked_mul(STUB_SIZE)
+            .and_then(|stubs_size| stubs_offset.checked_add(stubs_size))
+            .and_then(|code_len| align(code_len, page_size() as u64))
+            .ok_or_else(too_large)?;
+        let got_offset = align(data_size, GOT_ENTRY_SIZE as u64)
+            .and_then(|data_len| code_len.checked_add(data_len))
+            .ok_or_else(too_large)?;
+        let total_len = external_symbols
+            .checked_mul(GOT_ENTRY_SIZE)
+            .and_then(|got_size| got_offset.checked_add(got_size))
+            .ok_or_else(too_large)?;
+
+        let mut loader = Loader {
+            memory: Mmap::with_at_least(total_len.max(1)).map_err(CompileError::Resource)?,
+            architecture,
+            placements: HashMap::new(),
+            next_stub: stubs_offset,
+            stubs: HashMap::new(),
+            next_got_entry: got_offset,
+            got_entries: HashMap::new(),
+        };
+        for (is_code, section, offset, size) in placements {
+            let offset = if is_code { offset } else { code_len + offset };
+            if section.kind() != SectionKind::UninitializedData {
+                let data = section.data().map_err(to_compile_error)?;
+                if data.len() > size {
+                    return Err(malformed("the data of a section is larger than its size"));
+                }
+                loader.memory.as_mut_slice()[offset..offset + data.len()].copy_from_slice(data);
+            }
+            loader.placements.insert(
+                section.index(),
+                Placement {
+                    offset,
+                    size,
+                    address: section.address(),
+                },
+            );
+        }
+
+        let mut symbols = HashMap::new();
+        for symbol in file.symbols() {
+            let name = symbol.name().unwrap_or("");
+            if name.is_empty() || symbol.kind() == SymbolKind::Section {
+                continue;
+            }
+            if let Some(address) = loader.symbol_address(&symbol) {
+                symbols.insert(name.to_string(), address);
+            }
+        }
+
+        for section in file.sections() {
+            let placement = match loader.placements.get(&section.index()) {
+                Some(placement) => *placement,
+                None => continue,
+            };
+            for (offset, relocation) in section.relocations() {
+                let target = match relocation.target() {
+                    RelocationTarget::Symbol(index) => {
+                        let symbol = file.symbol_by_index(index).map_err(to_compile_error)?;
+                        if symbol.is_undefined() {
+                            let name = symbol.name().map_err(to_compile_error)?;
+                            let address = resolve_external(name).ok_or_else(|| {
+                                CompileError::Codegen(format!(
+                                    "Can't resolve the symbol `{}` for in-process linking",
+                                    name
+                                ))
+                            })?;
+                            Target::External(name.to_string(), address)
+                        } else {
+                            Target::Defined(loader.symbol_address(&symbol).ok_or_else(|| {
+                                CompileError::Codegen(format!(
+                                    "The symbol `{}` is not in a loaded section",
+                                    symbol.name().unwrap_or("")
+                                ))
+                            })?)
+                        }
+                    }
+                    RelocationTarget::Section(index) => {
+                        let placement = loader.placements.get(&index).ok_or_else(|| {
+                            CompileError::Codegen(
+                                "A relocation targets a section that is not loaded".to_string(),
+                            )
+                        })?;
+                        Target::Defined(loader.address(placement.offset))
+                    }
+                    _ => {
+                        return Err(CompileError::Codegen(format!(
+                            "Unsupported relocation target {:?} for in-process linking",
+                            relocation.target()
+                        )))
+                    }
+                };
+                loader.relocate(placement, offset, &relocation, target)?;
+            }
+        }
+
+        if code_len > 0 {
+            make_executable(&loader.memory, code_len)?;
+        }
+        Ok(Self {
+            memory: loader.memory,
+            symbols,
+        })
+    }
+
+    /// The address of the symbol `name` defined by the object file.
+    pub(crate) fn symbol(&self, name: &str) -> Option<usize> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Whether `address` is in the memory of the loaded object.
+    pub(crate) fn contains(&self, address: usize) -> bool {
+        let start = self.memory.as_ptr() as usize;
+        address >= start && address - start < self.memory.len()
+    }
+}
+
+/// The target of a relocation.
+enum Target {
+    /// A symbol or section of the object file, at the given address.
+    Defined(usize),
+    /// An external symbol, at the given address.
+    External(String, usize),
+}
+
+impl Loader {
+    fn address(&self, offset: usize) -> usize {
+        self.memory.as_ptr() as usize + offset
+    }
+
+    /// The address of a symbol defined in a loaded section, unless it's
+    /// out of the bounds of the section.
+    fn symbol_address(&self, symbol: &object::Symbol) -> Option<usize> {
+        let placement = self.placements.get(&symbol.section_index()?)?;
+        let offset = symbol.address().checked_sub(placement.address)?;
+        let offset = usize::try_from(offset)
+            .ok()
+            .filter(|offset| *offset <= placement.size)?;
+        Some(self.address(placement.offset + offset))
+    }
+
+    fn write(&mut self, offset: usize, bytes: &[u8]) {
+        self.memory.as_mut_slice()[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// The address of a stub jumping to the external symbol `name`.
+    fn stub(&mut self, name: &str, address: usize) -> usize {
+        if let Some(offset) = self.stubs.get(name) {
+            return self.address(*offset);
+        }
+        let offset = self.next_stub;
+        self.next_stub += STUB_SIZE;
+        let code = match self.architecture {
+            Architecture::Aarch64 => AARCH64_STUB,
+            _ => X86_64_STUB,
+        };
+        self.write(offset, code);
+        self.write(offset + code.len(), &(address as u64).to_le_bytes());
+        self.stubs.insert(name.to_string(), offset);
+        self.address(offset)
+    }
+
+    /// The address of the global offset table entry of the external
+    /// symbol `name`.
+    fn got_entry(&mut self, name: &str, address: usize) -> usize {
+        if let Some(offset) = self.got_entries.get(name) {
+            return self.address(*offset);
+        }
+        let offset = self.next_got_entry;
+        self.next_got_entry += GOT_ENTRY_SIZE;
+        self.write(offset, &(address as u64).to_le_bytes());
+        self.got_entries.insert(name.to_string(), offset);
+        self.address(offset)
+    }
+
+    /// Apply the relocation at `offset` in the section loaded at
+    /// `placement`.
+    fn relocate(
+        &mut self,
+        placement: Placement,
+        offset: u64,
+        relocation: &Relocation,
+        target: Target,
+    ) -> Result<(), CompileError> {
+        let is_branch26 = match relocation.kind() {
+            RelocationKind::Elf(elf::R_AARCH64_CALL26) => true,
+            RelocationKind::Relative | RelocationKind::PltRelative => relocation.size() == 26,
+            _ => false,
+        };
+        // The bytes patched by the relocation must be in its section.
+        let width = if is_branch26 {
+            4
+        } else {
+            usize::from(relocation.size()) / 8
+        };
+        let offset = usize::try_from(offset)
+            .ok()
+            .filter(|offset| {
+                offset
+                    .checked_add(width)
+                    .map_or(false, |end| end <= placement.size)
+            })
+            .map(|offset| placement.offset + offset)
+            .ok_or_else(|| malformed("a relocation is out of the bounds of its section"))?;
+        let place = self.address(offset) as i64;
+        let addend = relocation.addend();
+        let unsupported = || {
+            CompileError::Codegen(format!(
+                "Unsupported {:?} relocation of {} bits for in-process linking",
+                relocation.kind(),
+                relocation.size()
+            ))
+        };
+        let out_of_range = |value: i64| {
+            CompileError::Codegen(format!(
+                "The {:?} relocation value {:#x} is out of range",
+                relocation.kind(),
+                value
+            ))
+        };
+        let (address, external) = match &target {
+            Target::Defined(address) => (*address, None),
+            Target::External(name, address) => (*address, Some(name.as_str())),
+        };
+        match (self.architecture, relocation.kind(), relocation.size()) {
+            (_, RelocationKind::Absolute, 64) => {
+                let value = (address as i64).wrapping_add(addend);
+                self.write(offset, &value.to_le_bytes());
+            }
+            (Architecture::X86_64, RelocationKind::Absolute, 32) => {
+                let value = (address as i64).wrapping_add(addend);
+                let value = u32::try_from(value).map_err(|_| out_of_range(value))?;
+                self.write(offset, &value.to_le_bytes());
+            }
+            (Architecture::X86_64, RelocationKind::Relative, 32)
+            | (Architecture::X86_64, RelocationKind::PltRelative, 32) => {
+                let mut value = (address as i64).wrapping_add(addend) - place;
+                if let (Err(_), Some(name)) = (i32::try_from(value), external) {
+                    // Calls to external functions go through a stub.
+                    value = (self.stub(name, address) as i64).wrapping_add(addend) - place;
+                }
+                let value = i32::try_from(value).map_err(|_| out_of_range(value))?;
+                self.write(offset, &value.to_le_bytes());
+            }
+            (Architecture::X86_64, RelocationKind::GotRelative, 32) if external.is_some() => {
+                let entry = self.got_entry(external.unwrap_or(""), address);
+                let value = (entry as i64).wrapping_add(addend) - place;
+                let value = i32::try_from(value).map_err(|_| out_of_range(value))?;
+                self.write(offset, &value.to_le_bytes());
+            }
+            (Architecture::Aarch64, _, _) if is_branch26 => {
+                let in_range =
+                    |value: i64| value % 4 == 0 && (-(1 << 27)..(1 << 27)).contains(&value);
+                let mut value = (address as i64).wrapping_add(addend) - place;
+                if let (false, Some(name)) = (in_range(value), external) {
+                    // Calls to external functions go through a stub.
+                    value = (self.stub(name, address) as i64).wrapping_add(addend) - place;
+                }
+                if !in_range(value) {
+                    return Err(out_of_range(value));
+                }
+                let mut instruction = [0; 4];
+                instruction.copy_from_slice(&self.memory.as_slice()[offset..offset + 4]);
+                let instruction = (u32::from_le_bytes(instruction) & 0xfc00_0000)
+                    | ((value >> 2) as u32 & 0x03ff_ffff);
+                self.write(offset, &instruction.to_le_bytes());
+            }
+            (Architecture::Aarch64, RelocationKind::Relative, 32) => {
+                let value = (address as i64).wrapping_add(addend) - place;
+                let value = i32::try_from(value).map_err(|_| out_of_range(value))?;
+                self.write(offset, &value.to_le_bytes());
+            }
+            _ => return Err(unsupported()),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix, any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod tests {
+    use super::*;
+    use object::write::{self, StandardSection, SymbolSection};
+    use object::{RelocationEncoding, SymbolFlags, SymbolScope};
+    use wasmer_compiler::Triple;
+    use wasmer_object::get_object_for_target;
+
+    /// An object file with a 16-byte function and an absolute relocation
+    /// at `offset` in it.
+    fn object_with_relocation(offset: u64) -> Vec<u8> {
+        let mut obj = get_object_for_target(&Triple::host()).unwrap();
+        let text = obj.section_id(StandardSection::Text);
+        obj.append_section_data(text, &[0; 16], 16);
+        let symbol = obj.add_symbol(write::Symbol {
+            name: b"function".to_vec(),
+            value: 0,
+            size: 16,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+        obj.add_relocation(
+            text,
+            write::Relocation {
+                offset,
+                size: 64,
+                kind: RelocationKind::Absolute,
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend: 0,
+            },
+        )
+        .unwrap();
+        obj.write().unwrap()
+    }
+
+    #[test]
+    fn relocations_out_of_bounds() {
+        let loaded = LoadedObject::load(&object_with_relocation(8)).unwrap();
+        let function = loaded.symbol("function").unwrap();
+        assert!(loaded.contains(function));
+
+        for offset in &[12, 16, u64::MAX - 4] {
+            match LoadedObject::load(&object_with_relocation(*offset)) {
+                Err(CompileError::Codegen(message)) => {
+                    assert!(message.contains("out of the bounds"), "{}", message)
+                }
+                Err(err) => panic!("unexpected error: {}", err),
+                Ok(_) => panic!("the relocation at {} was applied", offset),
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Metrics collected by the Dylib engine.
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use wasmer_types::LocalFunctionIndex;
+
+/// Metrics collected while compiling a module into a `DylibArtifact`.
+///
+/// They are only collected if enabled on the engine, see
+/// `DylibEngine::set_compilation_metrics`.
+#[derive(Debug, Clone, Default)]
+pub struct CompilationMetrics {
+    /// Time spent translating (and validating) the Wasm module.
+    pub translation: Duration,
+    /// Time spent compiling the functions (and, for compilers that emit
+    /// the object file themselves, generating the object file).
+    pub compilation: Duration,
+    /// Time spent generating the object file.
+    pub emission: Duration,
+    /// Time spent linking the object file into a shared object.
+    pub linking: Duration,
+    /// The resources used by the linker process (only available on
+    /// Unix).
+    pub linker_usage: Option<LinkerUsage>,
+    /// The batches of consecutive functions taking the longest to
+    /// compile, with their compilation time, slowest first.
+    ///
+    /// The compiler compiles all the functions at once, so each batch
+    /// (see `DylibEngine::set_compilation_metrics_batches`) is timed by
+    /// compiling the module with the bodies of the other functions
+    /// replaced by `unreachable`, minus the time to compile the module
+    /// with only such bodies.
+    pub slowest_functions: Vec<(Range<LocalFunctionIndex>, Duration)>,
+}
+
+/// The resources used by the linker process, see
+/// `CompilationMetrics::linker_usage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkerUsage {
+    /// CPU time spent in user mode.
+    pub user_time: Duration,
+    /// CPU time spent in kernel mode.
+    pub system_time: Duration,
+    /// The peak resident set size, in bytes.
+    pub max_rss_bytes: u64,
+}
+
+impl LinkerUsage {
+    #[cfg(all(feature = "compiler", unix))]
+    pub(crate) fn from_rusage(rusage: &libc::rusage) -> Self {
+        let duration = |time: libc::timeval| {
+            Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+        };
+        // `ru_maxrss` is in bytes on Apple targets, and kilobytes elsewhere.
+        let max_rss_unit = if cfg!(any(target_os = "macos", target_os = "ios")) {
+            1
+        } else {
+            1024
+        };
+        Self {
+            user_time: duration(rusage.ru_utime),
+            system_time: duration(rusage.ru_stime),
+            max_rss_bytes: rusage.ru_maxrss as u64 * max_rss_unit,
+        }
+    }
+}
+
+/// A snapshot of the counters kept by a `DylibEngine`, see
+/// `DylibEngine::counters`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EngineCounters {
+    /// The number of modules compiled successfully.
+    pub compiles: u64,
+    /// The number of artifacts deserialized successfully.
+    pub deserializations_succeeded: u64,
+    /// The number of artifacts that failed to deserialize.
+    pub deserializations_failed: u64,
+    /// The number of times the linker failed.
+    pub link_failures: u64,
+    /// The number of compilations served from the artifact cache (see
+    /// `DylibEngine::set_artifact_cache_capacity`) or the cache directory
+    /// (see `DylibEngine::set_cache_dir`).
+    pub cache_hits: u64,
+    /// The number of cacheable compilations missing from the artifact
+    /// cache or the cache directory.
+    pub cache_misses: u64,
+}
+
+/// The counters kept by a `DylibEngine`.
+///
+/// They are atomic so they can be incremented and read without any
+/// synchronization beyond the engine's own.
+#[derive(Debug, Default)]
+pub(crate) struct AtomicEngineCounters {
+    compiles: AtomicU64,
+    deserializations_succeeded: AtomicU64,
+    deserializations_failed: AtomicU64,
+    link_failures: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl AtomicEngineCounters {
+    pub(crate) fn record_compile(&self) {
+        self.compiles.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_deserialization(&self, succeeded: bool) {
+        let counter = if succeeded {
+            &self.deserializations_succeeded
+        } else {
+            &self.deserializations_failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_link_failure(&self) {
+        self.link_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> EngineCounters {
+        EngineCounters {
+            compiles: self.compiles.load(Ordering::Relaxed),
+            deserializations_succeeded: self.deserializations_succeeded.load(Ordering::Relaxed),
+            deserializations_failed: self.deserializations_failed.load(Ordering::Relaxed),
+            link_failures: self.link_failures.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use enumset::EnumSet;
 use loupe::MemoryUsage;
 use rkyv::{
     archived_value,
@@ -8,15 +9,45 @@ use rkyv::{
 };
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use wasmer_compiler::{CompileError, CompileModuleInfo, SectionIndex, Symbol, SymbolRegistry};
+use wasmer_compiler::{
+    CompileError, CompileModuleInfo, CpuFeature, SectionIndex, Symbol, SymbolRegistry,
+};
 use wasmer_engine::DeserializeError;
 use wasmer_types::entity::{EntityRef, PrimaryMap};
-use wasmer_types::{FunctionIndex, LocalFunctionIndex, OwnedDataInitializer, SignatureIndex};
+use wasmer_types::{
+    DataInitializer, DataInitializerLocation, FunctionIndex, GlobalIndex, LocalFunctionIndex,
+    MemoryIndex, OwnedDataInitializer, SignatureIndex,
+};
 
 fn to_compile_error(err: impl Error) -> CompileError {
     CompileError::Codegen(format!("{}", err))
 }
 
+/// The magic bytes starting the serialized metadata (see
+/// `ModuleMetadata::serialize`).
+const METADATA_MAGIC: &[u8; 8] = b"WASMERMD";
+
+/// The version of the format of the serialized metadata, to bump
+/// whenever the layout of `ModuleMetadata` changes.
+pub const METADATA_FORMAT_VERSION: u32 = 1;
+
+/// The engine producing the serialized metadata, whose archive can only
+/// be read by the same version.
+const METADATA_PRODUCER: &str = concat!("wasmer-engine-dylib/", env!("CARGO_PKG_VERSION"));
+
+/// The length of the header of the serialized metadata: the magic bytes,
+/// the format version, 4 reserved bytes and the producer padded with
+/// zeros. It's a multiple of the alignment of the archive following it.
+const METADATA_HEADER_LEN: usize = 48;
+
+/// The offset of the producer in the header.
+const METADATA_PRODUCER_OFFSET: usize = 16;
+
+// The producer must fit in the header, or it would be truncated and the
+// header of every artifact rejected (this fails to compile otherwise).
+const _: [(); 0] =
+    [(); (METADATA_PRODUCER.len() > METADATA_HEADER_LEN - METADATA_PRODUCER_OFFSET) as usize];
+
 /// Serializable struct that represents the compiled metadata.
 #[derive(
     Serialize,
@@ -30,59 +61,543 @@ fn to_compile_error(err: impl Error) -> CompileError {
     Eq,
 )]
 pub struct ModuleMetadata {
+    /// The module information and the compilation settings.
     pub compile_info: CompileModuleInfo,
+    /// The prefix of the symbols of this module.
     pub prefix: String,
+    /// The data initializers of the module.
     pub data_initializers: Box<[OwnedDataInitializer]>,
-    // The function body lengths (used to find function by address)
+    /// The function body lengths (used to find function by address)
+    ///
+    /// They are unknown when the metadata is embedded in the object
+    /// file, so they are filled from the sizes of the function symbols
+    /// when the artifact is created or deserialized.
     pub function_body_lengths: PrimaryMap<LocalFunctionIndex, u64>,
+    /// The total length of the trampolines, filled like the function
+    /// body lengths.
+    pub trampolines_length: u64,
+    /// The total length of the custom sections, filled like the
+    /// function body lengths.
+    pub custom_sections_length: u64,
+    /// The CPU features the code was compiled for, as a bit set (see
+    /// `ModuleMetadata::cpu_features`).
+    pub cpu_features: u64,
+    /// The target triple the code was compiled for (e.g.
+    /// `x86_64-unknown-linux-gnu`).
+    pub target_triple: String,
+    /// The component-model adapters of the lowered imports of the
+    /// module, in the order of their symbols (see
+    /// [`component_adapter_symbol_name`]). Empty for core modules.
+    pub component_adapters: Vec<ComponentAdapter>,
+    /// The naming scheme of the symbols of the shared object.
+    pub symbol_naming: SymbolNaming,
+    /// The C-ABI wrappers of the exported functions of the module, if
+    /// any (see `DylibEngine::set_c_abi_export_prefix`).
+    pub c_abi_exports: Vec<CAbiExport>,
+    /// The build-id of the shared object, if it has one (see
+    /// `DylibEngine::set_build_id`).
+    ///
+    /// It's only known once the shared object is linked, so it's filled
+    /// from its `.note.gnu.build-id` section when the artifact is created
+    /// or deserialized, like the function body lengths.
+    pub build_id: Option<Vec<u8>>,
 }
 
+/// The naming scheme of the symbols of the generated shared objects.
+///
+/// Every function, custom section and trampoline symbol is named
+/// `{prefix}{separator}{kind}{separator}{module}{separator}{index}`,
+/// where `module` is the (possibly empty) output of the engine's
+/// deterministic prefixer, and `kind` is one of `function`, `section`,
+/// `trampoline{separator}function{separator}call`,
+/// `trampoline{separator}dynamic{separator}function` and
+/// `component{separator}adapter`. The default scheme, with the `wasmer`
+/// prefix and the `_` separator, names the first local function of a
+/// module `wasmer_function_{module}_0`.
+///
+/// Both the prefix and the separator must only contain characters
+/// valid in symbol names, and the separator must not be empty. The
+/// scheme is recorded in the metadata of the shared object, so it's
+/// used again when the artifact is deserialized.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    MemoryUsage,
+    RkyvSerialize,
+    RkyvDeserialize,
+    Archive,
+    PartialEq,
+    Eq,
+)]
+pub struct SymbolNaming {
+    /// The prefix of every symbol.
+    pub prefix: String,
+    /// The separator of the components of the names.
+    pub separator: String,
+}
+
+impl Default for SymbolNaming {
+    fn default() -> Self {
+        Self {
+            prefix: "wasmer".to_string(),
+            separator: "_".to_string(),
+        }
+    }
+}
+
+const FUNCTION_KIND: &[&str] = &["function"];
+const SECTION_KIND: &[&str] = &["section"];
+const FUNCTION_CALL_TRAMPOLINE_KIND: &[&str] = &["trampoline", "function", "call"];
+const DYNAMIC_FUNCTION_TRAMPOLINE_KIND: &[&str] = &["trampoline", "dynamic", "function"];
+const COMPONENT_ADAPTER_KIND: &[&str] = &["component", "adapter"];
+
+impl SymbolNaming {
+    /// Checks that the prefix and the separator only contain characters
+    /// valid in symbol names (ASCII alphanumerics, `_`, `.` and `$`),
+    /// and that the separator isn't empty, so the symbols can be parsed
+    /// back.
+    pub fn validate(&self) -> Result<(), CompileError> {
+        fn is_symbol_char(c: char) -> bool {
+            c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '$'
+        }
+        if self.separator.is_empty() {
+            return Err(CompileError::Codegen(
+                "the separator of the symbol naming is empty".to_string(),
+            ));
+        }
+        for (name, part) in &[("prefix", &self.prefix), ("separator", &self.separator)] {
+            if !part.chars().all(is_symbol_char) {
+                return Err(CompileError::Codegen(format!(
+                    "the {} of the symbol naming `{}` isn't a valid symbol name",
+                    name, part
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The common start of the names of the symbols of `kind`, up to
+    /// their index.
+    fn symbol_prefix(&self, kind: &[&str], module_prefix: &str) -> String {
+        let mut name = self.prefix.clone();
+        for component in kind.iter().chain(std::iter::once(&module_prefix)) {
+            name.push_str(&self.separator);
+            name.push_str(component);
+        }
+        name.push_str(&self.separator);
+        name
+    }
+
+    fn symbol_name(&self, kind: &[&str], module_prefix: &str, index: usize) -> String {
+        format!("{}{}", self.symbol_prefix(kind, module_prefix), index)
+    }
+
+    fn parse_symbol(&self, name: &str, kind: &[&str], module_prefix: &str) -> Option<u32> {
+        name.strip_prefix(&self.symbol_prefix(kind, module_prefix))?
+            .parse::<u32>()
+            .ok()
+    }
+}
+
+/// A component-model adapter for a lowered import of a core module.
+///
+/// The adapter gets its own trampoline symbol in the shared object,
+/// adapting calls of the core function type of the import to a host
+/// dynamic function (like the dynamic function trampoline of the
+/// import), so that the host can implement the lowering with the
+/// canonical ABI and resolve the trampoline by the adapter name.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    MemoryUsage,
+    RkyvSerialize,
+    RkyvDeserialize,
+    Archive,
+    PartialEq,
+    Eq,
+)]
+pub struct ComponentAdapter {
+    /// The name of the adapter, unique in the module (e.g. the name of
+    /// the lowered component function).
+    pub name: String,
+    /// The imported function adapted.
+    pub import: FunctionIndex,
+}
+
+/// A C-ABI wrapper of an exported function of a module, emitted into the
+/// generated shared object so that host C code can link against it (see
+/// `DylibEngine::set_c_abi_export_prefix`).
+///
+/// The wrapper is declared in C as:
+///
+/// ```c
+/// void symbol(void *vmctx, uint128_t *values);
+/// ```
+///
+/// where `vmctx` is the `VMContext` of an instance of the module, and
+/// `values` holds the arguments of the function when called, and its
+/// results on return (one 16-byte slot each).
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    MemoryUsage,
+    RkyvSerialize,
+    RkyvDeserialize,
+    Archive,
+    PartialEq,
+    Eq,
+)]
+pub struct CAbiExport {
+    /// The name of the export.
+    pub name: String,
+    /// The exported function.
+    pub function: FunctionIndex,
+    /// The name of the symbol of the wrapper.
+    pub symbol: String,
+}
+
+/// The prefix of the symbol of every component adapter trampoline in
+/// the generated shared object.
+pub const COMPONENT_ADAPTER_SYMBOL_PREFIX: &str = "wasmer_component_adapter_";
+
+/// Get the symbol name of the trampoline of the `index`-th component
+/// adapter of a module, in a shared object generated with the given
+/// `prefix` and the default [`SymbolNaming`].
+///
+/// Adapter names are not restricted to symbol characters, so the
+/// symbol is named after the position of the adapter in
+/// `ModuleMetadata::component_adapters` instead.
+pub fn component_adapter_symbol_name(prefix: &str, index: usize) -> String {
+    SymbolNaming::default().symbol_name(COMPONENT_ADAPTER_KIND, prefix, index)
+}
+
+/// The prefix of the symbol of every local function in the generated
+/// shared object.
+///
+/// Local functions are exported as `wasmer_function_{prefix}_{index}`,
+/// where `prefix` is the (possibly empty) output of the engine's
+/// deterministic prefixer and `index` is the decimal
+/// `LocalFunctionIndex` of the function, unless the engine uses
+/// another [`SymbolNaming`].
+pub const FUNCTION_SYMBOL_PREFIX: &str = "wasmer_function_";
+
+/// Get the symbol name of the local function `index` in a shared
+/// object generated with the given `prefix`.
+pub fn function_symbol_name(prefix: &str, index: LocalFunctionIndex) -> String {
+    SymbolNaming::default().symbol_name(FUNCTION_KIND, prefix, index.index())
+}
+
+/// Parse a local function symbol name (as generated by
+/// [`function_symbol_name`]) back into its `LocalFunctionIndex`.
+///
+/// The module prefix is not needed: the index is always the last
+/// `_`-separated component of the name. Returns `None` if the name
+/// is not a local function symbol.
+pub fn parse_function_symbol(name: &str) -> Option<LocalFunctionIndex> {
+    let (_prefix, index) = name
+        .strip_prefix(FUNCTION_SYMBOL_PREFIX)?
+        .rsplit_once('_')?;
+    index.parse::<u32>().ok().map(LocalFunctionIndex::from_u32)
+}
+
+/// Encode data initializers in the format of the data-initializer
+/// symbol (see `DylibEngine::set_data_initializers_symbol`).
+///
+/// The format is simple enough to be read without this crate: all the
+/// integers are little-endian, and it starts with the number of
+/// initializers as a `u32`, followed by each initializer as:
+///
+/// * the memory index, as a `u32`;
+/// * the base global index, as a `u32` (`u32::MAX` if there's none);
+/// * the offset, as a `u64`;
+/// * the length of the data, as a `u64`;
+/// * the data itself.
+pub fn encode_data_initializers(data_initializers: &[OwnedDataInitializer]) -> Vec<u8> {
+    let mut bytes = (data_initializers.len() as u32).to_le_bytes().to_vec();
+    for initializer in data_initializers {
+        let location = &initializer.location;
+        let base = location.base.map_or(u32::MAX, |base| base.as_u32());
+        bytes.extend_from_slice(&location.memory_index.as_u32().to_le_bytes());
+        bytes.extend_from_slice(&base.to_le_bytes());
+        bytes.extend_from_slice(&(location.offset as u64).to_le_bytes());
+        bytes.extend_from_slice(&(initializer.data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&initializer.data);
+    }
+    bytes
+}
+
+/// Decode data initializers encoded with [`encode_data_initializers`].
+///
+/// Trailing bytes are ignored, so this can be given the section data
+/// starting at the symbol. Returns `None` if the bytes are truncated.
+pub fn decode_data_initializers(mut bytes: &[u8]) -> Option<Vec<OwnedDataInitializer>> {
+    fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        if bytes.len() < len {
+            return None;
+        }
+        let (taken, rest) = bytes.split_at(len);
+        *bytes = rest;
+        Some(taken)
+    }
+    fn take_u32(bytes: &mut &[u8]) -> Option<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(take(bytes, 4)?);
+        Some(u32::from_le_bytes(buf))
+    }
+    fn take_u64(bytes: &mut &[u8]) -> Option<u64> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(take(bytes, 8)?);
+        Some(u64::from_le_bytes(buf))
+    }
+
+    let count = take_u32(&mut bytes)?;
+    let mut data_initializers = Vec::new();
+    for _ in 0..count {
+        let memory_index = MemoryIndex::from_u32(take_u32(&mut bytes)?);
+        let base = match take_u32(&mut bytes)? {
+            u32::MAX => None,
+            base => Some(GlobalIndex::from_u32(base)),
+        };
+        let offset = take_u64(&mut bytes)? as usize;
+        let len = take_u64(&mut bytes)? as usize;
+        let data = take(&mut bytes, len)?;
+        data_initializers.push(OwnedDataInitializer {
+            location: DataInitializerLocation {
+                memory_index,
+                base,
+                offset,
+            },
+            data: data.into(),
+        });
+    }
+    Some(data_initializers)
+}
+
+/// The `SymbolRegistry` naming the symbols of a module.
 pub struct ModuleMetadataSymbolRegistry<'a> {
+    /// The prefix of the symbols of the module.
     pub prefix: &'a String,
+    /// The naming scheme of the symbols.
+    pub naming: &'a SymbolNaming,
+}
+
+impl<'a> ModuleMetadataSymbolRegistry<'a> {
+    /// Get the symbol name of the trampoline of the `index`-th
+    /// component adapter of the module (see
+    /// `ModuleMetadata::component_adapters`).
+    pub fn component_adapter_symbol_name(&self, index: usize) -> String {
+        self.naming
+            .symbol_name(COMPONENT_ADAPTER_KIND, self.prefix, index)
+    }
 }
 
 impl ModuleMetadata {
+    /// The CPU features the code was compiled for.
+    pub fn cpu_features(&self) -> EnumSet<CpuFeature> {
+        EnumSet::from_u64_truncated(self.cpu_features)
+    }
+
+    /// Split the metadata into the compile info and the symbol registry.
     pub fn split<'a>(
         &'a mut self,
     ) -> (&'a mut CompileModuleInfo, ModuleMetadataSymbolRegistry<'a>) {
         let compile_info = &mut self.compile_info;
         let symbol_registry = ModuleMetadataSymbolRegistry {
             prefix: &self.prefix,
+            naming: &self.symbol_naming,
         };
         (compile_info, symbol_registry)
     }
 
+    /// Get the symbol registry naming the symbols of this module.
     pub fn get_symbol_registry<'a>(&'a self) -> ModuleMetadataSymbolRegistry<'a> {
         ModuleMetadataSymbolRegistry {
             prefix: &self.prefix,
+            naming: &self.symbol_naming,
         }
     }
 
+    /// Serialize the metadata: a header with the format version and the
+    /// engine version, the archive, and the position of its root as a
+    /// little-endian `u64`.
     pub fn serialize(&mut self) -> Result<Vec<u8>, CompileError> {
         let mut serializer = SharedSerializerAdapter::new(WriteSerializer::new(vec![]));
         let pos = serializer.serialize_value(self).map_err(to_compile_error)? as u64;
-        let mut serialized_data = serializer.into_inner().into_inner();
+        let archive = serializer.into_inner().into_inner();
+        let mut serialized_data = Vec::with_capacity(METADATA_HEADER_LEN + archive.len() + 8);
+        serialized_data.extend_from_slice(METADATA_MAGIC);
+        serialized_data.extend_from_slice(&METADATA_FORMAT_VERSION.to_le_bytes());
+        serialized_data.extend_from_slice(&[0; 4]);
+        debug_assert_eq!(serialized_data.len(), METADATA_PRODUCER_OFFSET);
+        serialized_data.extend_from_slice(METADATA_PRODUCER.as_bytes());
+        serialized_data.resize(METADATA_HEADER_LEN, 0);
+        serialized_data.extend_from_slice(&archive);
         serialized_data.extend_from_slice(&pos.to_le_bytes());
         Ok(serialized_data)
     }
 
+    /// Check the header of serialized metadata, returning the archive
+    /// following it.
+    ///
+    /// The archive can only be read by the engine version that wrote it,
+    /// so the metadata of artifacts compiled by another version (or by
+    /// a version predating the header) is rejected as incompatible.
+    fn check_header(metadata_slice: &[u8]) -> Result<&[u8], DeserializeError> {
+        if metadata_slice.len() < METADATA_HEADER_LEN || !metadata_slice.starts_with(METADATA_MAGIC)
+        {
+            return Err(DeserializeError::Incompatible(
+                "The metadata has no version header: the artifact was compiled by an older \
+                 version of Wasmer, and must be compiled again"
+                    .to_string(),
+            ));
+        }
+        let (header, archive) = metadata_slice.split_at(METADATA_HEADER_LEN);
+        let mut format_version = [0; 4];
+        format_version.copy_from_slice(&header[8..12]);
+        let format_version = u32::from_le_bytes(format_version);
+        let producer = String::from_utf8_lossy(&header[METADATA_PRODUCER_OFFSET..]);
+        let producer = producer.trim_end_matches('\0');
+        if format_version != METADATA_FORMAT_VERSION || producer != METADATA_PRODUCER {
+            return Err(DeserializeError::Incompatible(format!(
+                "The artifact was compiled by `{}` (metadata format {}), which is incompatible \
+                 with `{}` (metadata format {}): it must be compiled again",
+                producer, format_version, METADATA_PRODUCER, METADATA_FORMAT_VERSION
+            )));
+        }
+        Ok(archive)
+    }
+
+    /// Deserialize the metadata from a slice generated by `serialize`,
+    /// failing with `DeserializeError::Incompatible` if it was generated
+    /// by another version of the engine.
+    ///
+    /// # Safety
+    ///
+    /// The slice must be suitably aligned for the archive and contain
+    /// metadata serialized by this engine: the archive is not validated.
     pub unsafe fn deserialize(metadata_slice: &[u8]) -> Result<Self, DeserializeError> {
-        let archived = Self::archive_from_slice(metadata_slice)?;
+        let archived = Self::archive_from_slice(Self::check_header(metadata_slice)?)?;
         Self::deserialize_from_archive(archived)
     }
 
+    /// Deserialize the metadata from a slice that may not be suitably
+    /// aligned for the archive (e.g. when read from a file), by copying
+    /// it into an aligned buffer first.
+    ///
+    /// # Safety
+    ///
+    /// The slice must contain metadata serialized by this engine: the
+    /// archive is not validated.
+    pub unsafe fn deserialize_unaligned(metadata_slice: &[u8]) -> Result<Self, DeserializeError> {
+        #[repr(C, align(16))]
+        #[derive(Clone, Copy)]
+        struct Chunk([u8; 16]);
+
+        debug_assert!(std::mem::align_of::<ArchivedModuleMetadata>() <= 16);
+        let mut buffer = vec![Chunk([0; 16]); (metadata_slice.len() + 15) / 16];
+        let aligned =
+            std::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, metadata_slice.len());
+        aligned.copy_from_slice(metadata_slice);
+        Self::deserialize(aligned)
+    }
+
+    /// Deserialize the metadata from a slice generated by `serialize`,
+    /// without copying the data of its data initializers: they are
+    /// returned as slices of `metadata_slice`, and left out of the
+    /// metadata.
+    ///
+    /// Returns `None` if the slice is not suitably aligned for the
+    /// archive, in which case `deserialize_unaligned` must be used.
+    ///
+    /// # Safety
+    ///
+    /// The slice must contain metadata serialized by this engine: the
+    /// archive is not validated.
+    pub unsafe fn deserialize_borrowed(
+        metadata_slice: &[u8],
+    ) -> Result<Option<(Self, Vec<DataInitializer<'_>>)>, DeserializeError> {
+        let archive = Self::check_header(metadata_slice)?;
+        if archive.as_ptr() as usize % std::mem::align_of::<ArchivedModuleMetadata>() != 0 {
+            return Ok(None);
+        }
+        let archived = Self::archive_from_slice(archive)?;
+        let mut deserializer = SharedDeserializerAdapter::new(AllocDeserializer);
+        let corrupted = |e| DeserializeError::CorruptedBinary(format!("{:?}", e));
+        let data_initializers = archived
+            .data_initializers
+            .iter()
+            .map(|initializer| {
+                Ok(DataInitializer {
+                    location: RkyvDeserialize::deserialize(
+                        &initializer.location,
+                        &mut deserializer,
+                    )
+                    .map_err(corrupted)?,
+                    data: &*initializer.data,
+                })
+            })
+            .collect::<Result<Vec<_>, DeserializeError>>()?;
+        let metadata = Self {
+            compile_info: RkyvDeserialize::deserialize(&archived.compile_info, &mut deserializer)
+                .map_err(corrupted)?,
+            prefix: RkyvDeserialize::deserialize(&archived.prefix, &mut deserializer)
+                .map_err(corrupted)?,
+            data_initializers: Box::new([]),
+            function_body_lengths: RkyvDeserialize::deserialize(
+                &archived.function_body_lengths,
+                &mut deserializer,
+            )
+            .map_err(corrupted)?,
+            trampolines_length: archived.trampolines_length,
+            custom_sections_length: archived.custom_sections_length,
+            cpu_features: archived.cpu_features,
+            target_triple: RkyvDeserialize::deserialize(&archived.target_triple, &mut deserializer)
+                .map_err(corrupted)?,
+            component_adapters: RkyvDeserialize::deserialize(
+                &archived.component_adapters,
+                &mut deserializer,
+            )
+            .map_err(corrupted)?,
+            symbol_naming: RkyvDeserialize::deserialize(&archived.symbol_naming, &mut deserializer)
+                .map_err(corrupted)?,
+            c_abi_exports: RkyvDeserialize::deserialize(&archived.c_abi_exports, &mut deserializer)
+                .map_err(corrupted)?,
+            build_id: RkyvDeserialize::deserialize(&archived.build_id, &mut deserializer)
+                .map_err(corrupted)?,
+        };
+        Ok(Some((metadata, data_initializers)))
+    }
+
     unsafe fn archive_from_slice<'a>(
         metadata_slice: &'a [u8],
     ) -> Result<&'a ArchivedModuleMetadata, DeserializeError> {
-        let mut pos: [u8; 8] = Default::default();
-        pos.copy_from_slice(&metadata_slice[metadata_slice.len() - 8..metadata_slice.len()]);
-        let pos: u64 = u64::from_le_bytes(pos);
-        Ok(archived_value::<ModuleMetadata>(
-            &metadata_slice[..metadata_slice.len() - 8],
-            pos as usize,
-        ))
+        if metadata_slice.len() < 8 {
+            return Err(DeserializeError::CorruptedBinary(
+                "The metadata is too short".to_string(),
+            ));
+        }
+        let (archive, pos) = metadata_slice.split_at(metadata_slice.len() - 8);
+        let mut pos_bytes: [u8; 8] = Default::default();
+        pos_bytes.copy_from_slice(pos);
+        let pos = u64::from_le_bytes(pos_bytes) as usize;
+        if pos
+            .checked_add(std::mem::size_of::<ArchivedModuleMetadata>())
+            .map_or(true, |end| end > archive.len())
+        {
+            return Err(DeserializeError::CorruptedBinary(
+                "The metadata root is out of bounds".to_string(),
+            ));
+        }
+        Ok(archived_value::<ModuleMetadata>(archive, pos))
     }
 
+    /// Deserialize the metadata from its archived representation.
     pub fn deserialize_from_archive(
         archived: &ArchivedModuleMetadata,
     ) -> Result<Self, DeserializeError> {
@@ -94,56 +609,187 @@ impl ModuleMetadata {
 
 impl<'a> SymbolRegistry for ModuleMetadataSymbolRegistry<'a> {
     fn symbol_to_name(&self, symbol: Symbol) -> String {
-        match symbol {
-            Symbol::LocalFunction(index) => {
-                format!("wasmer_function_{}_{}", self.prefix, index.index())
-            }
-            Symbol::Section(index) => format!("wasmer_section_{}_{}", self.prefix, index.index()),
-            Symbol::FunctionCallTrampoline(index) => {
-                format!(
-                    "wasmer_trampoline_function_call_{}_{}",
-                    self.prefix,
-                    index.index()
-                )
-            }
+        let (kind, index) = match symbol {
+            Symbol::LocalFunction(index) => (FUNCTION_KIND, index.index()),
+            Symbol::Section(index) => (SECTION_KIND, index.index()),
+            Symbol::FunctionCallTrampoline(index) => (FUNCTION_CALL_TRAMPOLINE_KIND, index.index()),
             Symbol::DynamicFunctionTrampoline(index) => {
-                format!(
-                    "wasmer_trampoline_dynamic_function_{}_{}",
-                    self.prefix,
-                    index.index()
-                )
+                (DYNAMIC_FUNCTION_TRAMPOLINE_KIND, index.index())
             }
-        }
+        };
+        self.naming.symbol_name(kind, self.prefix, index)
     }
 
     fn name_to_symbol(&self, name: &str) -> Option<Symbol> {
-        if let Some(index) = name.strip_prefix(&format!("wasmer_function_{}_", self.prefix)) {
-            index
-                .parse::<u32>()
-                .ok()
-                .map(|index| Symbol::LocalFunction(LocalFunctionIndex::from_u32(index)))
-        } else if let Some(index) = name.strip_prefix(&format!("wasmer_section_{}_", self.prefix)) {
-            index
-                .parse::<u32>()
-                .ok()
-                .map(|index| Symbol::Section(SectionIndex::from_u32(index)))
-        } else if let Some(index) =
-            name.strip_prefix(&format!("wasmer_trampoline_function_call_{}_", self.prefix))
-        {
-            index
-                .parse::<u32>()
-                .ok()
-                .map(|index| Symbol::FunctionCallTrampoline(SignatureIndex::from_u32(index)))
-        } else if let Some(index) = name.strip_prefix(&format!(
-            "wasmer_trampoline_dynamic_function_{}_",
-            self.prefix
-        )) {
-            index
-                .parse::<u32>()
-                .ok()
-                .map(|index| Symbol::DynamicFunctionTrampoline(FunctionIndex::from_u32(index)))
+        let parse = |kind| self.naming.parse_symbol(name, kind, self.prefix);
+        if let Some(index) = parse(FUNCTION_KIND) {
+            Some(Symbol::LocalFunction(LocalFunctionIndex::from_u32(index)))
+        } else if let Some(index) = parse(SECTION_KIND) {
+            Some(Symbol::Section(SectionIndex::from_u32(index)))
+        } else if let Some(index) = parse(FUNCTION_CALL_TRAMPOLINE_KIND) {
+            Some(Symbol::FunctionCallTrampoline(SignatureIndex::from_u32(
+                index,
+            )))
+        } else if let Some(index) = parse(DYNAMIC_FUNCTION_TRAMPOLINE_KIND) {
+            Some(Symbol::DynamicFunctionTrampoline(FunctionIndex::from_u32(
+                index,
+            )))
         } else {
             None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_symbol_roundtrip() {
+        for prefix in &["", "abc", "with_underscores"] {
+            let index = LocalFunctionIndex::from_u32(42);
+            let name = function_symbol_name(prefix, index);
+            assert_eq!(parse_function_symbol(&name), Some(index));
+        }
+        assert_eq!(parse_function_symbol("wasmer_function_"), None);
+        assert_eq!(parse_function_symbol("wasmer_section__1"), None);
+        assert_eq!(parse_function_symbol("wasmer_function_abc_x"), None);
+    }
+
+    #[test]
+    fn metadata_header() {
+        let header = |format_version: u32, producer: &str| {
+            let mut bytes = METADATA_MAGIC.to_vec();
+            bytes.extend_from_slice(&format_version.to_le_bytes());
+            bytes.extend_from_slice(&[0; 4]);
+            bytes.extend_from_slice(producer.as_bytes());
+            bytes.resize(METADATA_HEADER_LEN, 0);
+            bytes.extend_from_slice(b"archive");
+            bytes
+        };
+        let bytes = header(METADATA_FORMAT_VERSION, METADATA_PRODUCER);
+        assert_eq!(
+            ModuleMetadata::check_header(&bytes).ok(),
+            Some(&b"archive"[..])
+        );
+        for bytes in &[
+            header(METADATA_FORMAT_VERSION + 1, METADATA_PRODUCER),
+            header(METADATA_FORMAT_VERSION, "wasmer-engine-dylib/0.0.1"),
+            b"archive without header".to_vec(),
+        ] {
+            assert!(matches!(
+                ModuleMetadata::check_header(bytes),
+                Err(DeserializeError::Incompatible(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn data_initializers_roundtrip() {
+        let data_initializers = vec![
+            OwnedDataInitializer {
+                location: DataInitializerLocation {
+                    memory_index: MemoryIndex::from_u32(0),
+                    base: None,
+                    offset: 16,
+                },
+                data: b"hello".to_vec().into_boxed_slice(),
+            },
+            OwnedDataInitializer {
+                location: DataInitializerLocation {
+                    memory_index: MemoryIndex::from_u32(1),
+                    base: Some(GlobalIndex::from_u32(2)),
+                    offset: 0,
+                },
+                data: Box::new([]),
+            },
+        ];
+        let bytes = encode_data_initializers(&data_initializers);
+        assert_eq!(
+            decode_data_initializers(&bytes).as_deref(),
+            Some(&data_initializers[..])
+        );
+        assert!(decode_data_initializers(&bytes[..bytes.len() - 1]).is_none());
+        assert_eq!(
+            decode_data_initializers(&[bytes.as_slice(), &[0]].concat()).as_deref(),
+            Some(&data_initializers[..])
+        );
+    }
+
+    #[test]
+    fn component_adapter_symbols() {
+        let prefix = "abc".to_string();
+        let name = component_adapter_symbol_name(&prefix, 3);
+        assert_eq!(name, "wasmer_component_adapter_abc_3");
+        let naming = SymbolNaming::default();
+        let symbol_registry = ModuleMetadataSymbolRegistry {
+            prefix: &prefix,
+            naming: &naming,
+        };
+        assert_eq!(symbol_registry.name_to_symbol(&name), None);
+        assert_eq!(parse_function_symbol(&name), None);
+    }
+
+    #[test]
+    fn symbol_naming_validation() {
+        let naming = |prefix: &str, separator: &str| SymbolNaming {
+            prefix: prefix.to_string(),
+            separator: separator.to_string(),
+        };
+        SymbolNaming::default().validate().unwrap();
+        naming("module", ".").validate().unwrap();
+        naming("", "$").validate().unwrap();
+        assert!(naming("module", "").validate().is_err());
+        assert!(naming("my module", "_").validate().is_err());
+        assert!(naming("module", "::").validate().is_err());
+    }
+
+    #[test]
+    fn symbol_naming() {
+        let prefix = "abc".to_string();
+        let default_naming = SymbolNaming::default();
+        let naming = SymbolNaming {
+            prefix: "module".to_string(),
+            separator: ".".to_string(),
+        };
+        let symbols = [
+            Symbol::LocalFunction(LocalFunctionIndex::from_u32(1)),
+            Symbol::Section(SectionIndex::from_u32(2)),
+            Symbol::FunctionCallTrampoline(SignatureIndex::from_u32(3)),
+            Symbol::DynamicFunctionTrampoline(FunctionIndex::from_u32(4)),
+        ];
+        let default_registry = ModuleMetadataSymbolRegistry {
+            prefix: &prefix,
+            naming: &default_naming,
+        };
+        let registry = ModuleMetadataSymbolRegistry {
+            prefix: &prefix,
+            naming: &naming,
+        };
+        let names = symbols
+            .iter()
+            .map(|symbol| registry.symbol_to_name(symbol.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "module.function.abc.1",
+                "module.section.abc.2",
+                "module.trampoline.function.call.abc.3",
+                "module.trampoline.dynamic.function.abc.4",
+            ]
+        );
+        for (symbol, name) in symbols.iter().zip(&names) {
+            assert_eq!(registry.name_to_symbol(name), Some(symbol.clone()));
+            assert_eq!(default_registry.name_to_symbol(name), None);
+        }
+        assert_eq!(
+            default_registry.symbol_to_name(symbols[0].clone()),
+            function_symbol_name(&prefix, LocalFunctionIndex::from_u32(1))
+        );
+        assert_eq!(
+            registry.component_adapter_symbol_name(5),
+            "module.component.adapter.abc.5"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Helpers for writing tests against the Dylib engine.
+
+use crate::{DylibArtifact, DylibEngine};
+use wasmer_compiler::{CompileError, WasmError};
+use wasmer_engine::Tunables;
+
+/// Compile a module written in the WebAssembly text format into a
+/// `DylibArtifact`.
+pub fn compile_wat(
+    engine: &DylibEngine,
+    wat: &str,
+    tunables: &dyn Tunables,
+) -> Result<DylibArtifact, CompileError> {
+    let bytes = wat::parse_str(wat).map_err(|e| {
+        CompileError::Wasm(WasmError::Generic(format!(
+            "Error when converting wat: {}",
+            e
+        )))
+    })?;
+    DylibArtifact::new(engine, &bytes, tunables)
+}
+
+#[cfg(feature = "cross-compile-tests")]
+pub use object::{RelocationEncoding, RelocationKind};
+
+/// A relocation of an object file, as read by the `object` crate.
+#[cfg(feature = "cross-compile-tests")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectRelocation {
+    /// The name of the section the relocation applies to.
+    pub section: String,
+    /// The size of that section.
+    pub section_size: u64,
+    /// The offset of the relocation in the section.
+    pub offset: u64,
+    /// The name of the symbol or section targeted by the relocation.
+    pub target: String,
+    /// The kind of the relocation.
+    pub kind: RelocationKind,
+    /// The encoding of the relocated value.
+    pub encoding: RelocationEncoding,
+    /// The size of the relocated value in bits, or 0 if it's implied by
+    /// the kind.
+    pub size: u8,
+}
+
+/// Read the relocations of all the sections of an object file (e.g. an
+/// artifact compiled with `DylibEngine::set_object_only`), for any
+/// target.
+#[cfg(feature = "cross-compile-tests")]
+pub fn object_relocations(bytes: &[u8]) -> Result<Vec<ObjectRelocation>, CompileError> {
+    use object::{Object, ObjectSection, ObjectSymbol, RelocationTarget};
+
+    let to_compile_error = |e: object::Error| CompileError::Codegen(e.to_string());
+    let file = object::File::parse(bytes).map_err(to_compile_error)?;
+    let mut relocations = Vec::new();
+    for section in file.sections() {
+        let section_name = section.name().map_err(to_compile_error)?;
+        for (offset, relocation) in section.relocations() {
+            let target = match relocation.target() {
+                RelocationTarget::Symbol(index) => file
+                    .symbol_by_index(index)
+                    .and_then(|symbol| symbol.name().map(str::to_string)),
+                RelocationTarget::Section(index) => file
+                    .section_by_index(index)
+                    .and_then(|section| section.name().map(str::to_string)),
+                _ => Ok(String::new()),
+            }
+            .map_err(to_compile_error)?;
+            relocations.push(ObjectRelocation {
+                section: section_name.to_string(),
+                section_size: section.size(),
+                offset,
+                target,
+                kind: relocation.kind(),
+                encoding: relocation.encoding(),
+                size: relocation.size(),
+            });
+        }
+    }
+    Ok(relocations)
+}
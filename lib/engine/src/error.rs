@@ -33,6 +33,10 @@ pub enum DeserializeError {
     /// The provided binary is corrupted
     #[error("corrupted binary: {0}")]
     CorruptedBinary(String),
+    /// The binary doesn't match its embedded content hash or signature,
+    /// or lacks them when they are required
+    #[error("integrity check failed: {0}")]
+    IntegrityCheckFailed(String),
     /// The binary was valid, but we got an error when
     /// trying to allocate the required resources.
     #[error(transparent)]
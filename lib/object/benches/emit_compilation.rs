@@ -0,0 +1,87 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::str::FromStr;
+use wasmer_compiler::{
+    Compilation, CompiledFunction, FunctionBody, Relocation, RelocationKind, RelocationTarget,
+    Symbol, SymbolRegistry, Triple,
+};
+use wasmer_object::{emit_compilation, get_object_for_target};
+use wasmer_types::entity::{EntityRef, PrimaryMap, SecondaryMap};
+use wasmer_types::LocalFunctionIndex;
+
+/// The number of functions of the module.
+const FUNCTIONS: usize = 20_000;
+/// One function in this many calls another one.
+const RELOCATION_SPACING: usize = 100;
+
+struct BenchSymbolRegistry;
+
+impl SymbolRegistry for BenchSymbolRegistry {
+    fn symbol_to_name(&self, symbol: Symbol) -> String {
+        match symbol {
+            Symbol::LocalFunction(index) => format!("function_{}", index.index()),
+            Symbol::Section(index) => format!("section_{}", index.index()),
+            Symbol::FunctionCallTrampoline(index) => format!("call_trampoline_{}", index.index()),
+            Symbol::DynamicFunctionTrampoline(index) => {
+                format!("dynamic_trampoline_{}", index.index())
+            }
+        }
+    }
+
+    fn name_to_symbol(&self, _name: &str) -> Option<Symbol> {
+        None
+    }
+}
+
+/// A big module where few functions have relocations.
+fn sparse_compilation() -> Compilation {
+    let functions = (0..FUNCTIONS)
+        .map(|index| {
+            let relocations = if index % RELOCATION_SPACING == 0 {
+                vec![Relocation {
+                    kind: RelocationKind::X86CallPCRel4,
+                    reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::new(0)),
+                    offset: 1,
+                    addend: -4,
+                }]
+            } else {
+                vec![]
+            };
+            CompiledFunction {
+                body: FunctionBody {
+                    // `call 0; ret`
+                    body: vec![0xe8, 0, 0, 0, 0, 0xc3],
+                    unwind_info: None,
+                },
+                relocations,
+                jt_offsets: SecondaryMap::new(),
+                frame_info: Default::default(),
+            }
+        })
+        .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+    Compilation::new(
+        functions,
+        PrimaryMap::new(),
+        PrimaryMap::new(),
+        PrimaryMap::new(),
+        None,
+        None,
+    )
+}
+
+pub fn emit_sparse_relocations(c: &mut Criterion) {
+    let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+    c.bench_function("emit a module with sparse relocations", |b| {
+        b.iter_with_setup(sparse_compilation, |compilation| {
+            let mut obj = get_object_for_target(&triple).unwrap();
+            emit_compilation(&mut obj, compilation, &BenchSymbolRegistry, &triple).unwrap();
+            black_box(obj)
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = emit_sparse_relocations
+}
+criterion_main!(benches);
@@ -11,6 +11,21 @@ pub enum ObjectError {
     /// The object was provided a not-supported architecture
     #[error("Architecture {0} not supported")]
     UnsupportedArchitecture(String),
+    /// A relocation can't be represented in the target object format
+    #[error("Relocation not supported: {0}")]
+    UnsupportedRelocation(String),
+    /// A relocation is outside of the data of the symbol it applies to
+    #[error("Relocation out of bounds: {0}")]
+    RelocationOutOfBounds(String),
+    /// Two symbols of the object have the same name
+    #[error("Duplicate symbol `{0}`")]
+    DuplicateSymbol(String),
+    /// A symbol referenced in the object was not defined
+    #[error("Missing symbol `{0}`")]
+    MissingSymbol(String),
+    /// A section alignment is not a power of two
+    #[error("Invalid alignment {0}: not a power of two")]
+    InvalidAlignment(u64),
     /// The object was provided an unknown endianness
     #[error("Unknown Endianness")]
     UnknownEndianness,
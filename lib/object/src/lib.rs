@@ -24,4 +24,8 @@ mod error;
 mod module;
 
 pub use crate::error::ObjectError;
-pub use crate::module::{emit_compilation, emit_data, get_object_for_target};
+pub use crate::module::{
+    emit_alias, emit_call_wrapper, emit_compilation, emit_compilation_with_options,
+    emit_compilation_with_relocation_records, emit_data, get_object_for_target, EmitOptions,
+    RelocationModel, RelocationRecord,
+};
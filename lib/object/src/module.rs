@@ -1,13 +1,17 @@
 use crate::error::ObjectError;
 use object::write::{
-    Object, Relocation, StandardSection, StandardSegment, Symbol as ObjSymbol, SymbolSection,
+    Object, Relocation, StandardSection, StandardSegment, Symbol as ObjSymbol, SymbolId,
+    SymbolSection,
 };
 use object::{
-    elf, RelocationEncoding, RelocationKind, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+    elf, macho, RelocationEncoding, RelocationKind, SectionKind, SymbolFlags, SymbolKind,
+    SymbolScope,
 };
+use std::collections::{HashMap, HashSet};
 use wasmer_compiler::{
-    Architecture, BinaryFormat, Compilation, CustomSectionProtection, Endianness,
-    RelocationKind as Reloc, RelocationTarget, SectionIndex, Symbol, SymbolRegistry, Triple,
+    Architecture, BinaryFormat, CodeOffset, Compilation, CustomSectionProtection, Endianness,
+    OperatingSystem, RelocationKind as Reloc, RelocationTarget, SectionIndex, Symbol,
+    SymbolRegistry, Triple,
 };
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::LocalFunctionIndex;
@@ -43,7 +47,9 @@ pub fn get_object_for_target(triple: &Triple) -> Result<Object, ObjectError> {
     };
     let obj_architecture = match triple.architecture {
         Architecture::X86_64 => object::Architecture::X86_64,
+        Architecture::X86_32(_) => object::Architecture::I386,
         Architecture::Aarch64(_) => object::Architecture::Aarch64,
+        Architecture::Arm(_) => object::Architecture::Arm,
         architecture => {
             return Err(ObjectError::UnsupportedArchitecture(format!(
                 "{}",
@@ -51,10 +57,7 @@ pub fn get_object_for_target(triple: &Triple) -> Result<Object, ObjectError> {
             )));
         }
     };
-    let obj_endianness = match triple
-        .endianness()
-        .map_err(|_| ObjectError::UnknownEndianness)?
-    {
+    let obj_endianness = match target_endianness(triple)? {
         Endianness::Little => object::Endianness::Little,
         Endianness::Big => object::Endianness::Big,
     };
@@ -66,6 +69,24 @@ pub fn get_object_for_target(triple: &Triple) -> Result<Object, ObjectError> {
     ))
 }
 
+/// Get the endianness of a target.
+///
+/// Custom triples may not specify their endianness, in which case it's
+/// inferred from the architecture when unambiguous.
+fn target_endianness(triple: &Triple) -> Result<Endianness, ObjectError> {
+    triple
+        .endianness()
+        .or_else(|_| match triple.architecture {
+            // Big-endian AArch64 (`aarch64_be`) always reports its
+            // endianness.
+            Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) => {
+                Ok(Endianness::Little)
+            }
+            _ => Err(()),
+        })
+        .map_err(|_| ObjectError::UnknownEndianness)
+}
+
 /// Write data into an existing object.
 ///
 /// # Usage
@@ -104,6 +125,183 @@ pub fn emit_data(
     Ok(())
 }
 
+/// Emit a dynamic symbol `name` into an existing object, aliasing the
+/// already emitted symbol `target`: both symbols have the same section,
+/// address, size and kind.
+///
+/// # Usage
+///
+/// ```rust
+/// # use wasmer_compiler::Triple;
+/// # use wasmer_object::ObjectError;
+/// use wasmer_object::{emit_alias, emit_data, get_object_for_target};
+///
+/// # fn emit_alias_into_object(triple: &Triple) -> Result<(), ObjectError> {
+/// let mut object = get_object_for_target(&triple)?;
+/// emit_data(&mut object, b"WASMER_METADATA", &[1, 2, 3], 1)?;
+/// emit_alias(&mut object, b"WASMER_METADATA_ALIAS", b"WASMER_METADATA")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn emit_alias(obj: &mut Object, name: &[u8], target: &[u8]) -> Result<(), ObjectError> {
+    let target_id = obj
+        .symbol_id(target)
+        .ok_or_else(|| ObjectError::MissingSymbol(String::from_utf8_lossy(target).into_owned()))?;
+    let target = obj.symbol(target_id);
+    let symbol = ObjSymbol {
+        name: name.to_vec(),
+        value: target.value,
+        size: target.size,
+        kind: target.kind,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: target.section,
+        flags: SymbolFlags::None,
+    };
+    add_unique_symbol(obj, symbol)?;
+
+    Ok(())
+}
+
+/// Emit a function `name` into an existing object, with the C calling
+/// convention of the target, calling the already emitted function
+/// `function` through its function call `trampoline`:
+///
+/// ```c
+/// void name(void *vmctx, uint128_t *values);
+/// ```
+///
+/// Like for the trampoline, `values` holds the arguments of the function
+/// when called, and its results on return. The wrapper only adds the
+/// address of `function` to the arguments, and tail-calls the
+/// trampoline.
+///
+/// Only x86_64 and AArch64 (ELF and Mach-O) targets are supported.
+pub fn emit_call_wrapper(
+    obj: &mut Object,
+    name: &[u8],
+    function: &[u8],
+    trampoline: &[u8],
+    triple: &Triple,
+) -> Result<(), ObjectError> {
+    let symbol_id = |obj: &Object, name: &[u8]| {
+        obj.symbol_id(name)
+            .ok_or_else(|| ObjectError::MissingSymbol(String::from_utf8_lossy(name).into_owned()))
+    };
+    let function = symbol_id(obj, function)?;
+    let trampoline = symbol_id(obj, trampoline)?;
+    let relative = |offset, encoding, symbol| Relocation {
+        offset,
+        size: 32,
+        kind: RelocationKind::Relative,
+        encoding,
+        symbol,
+        addend: -4,
+    };
+    let raw = |offset, kind, symbol| Relocation {
+        offset,
+        size: 32,
+        kind,
+        encoding: RelocationEncoding::Generic,
+        symbol,
+        addend: 0,
+    };
+    let (code, relocations): (&[u8], _) = match (triple.architecture, triple.binary_format) {
+        (Architecture::X86_64, _) if triple.operating_system == OperatingSystem::Windows => (
+            &[
+                0x49, 0x89, 0xd0, // mov r8, rdx
+                0x48, 0x8d, 0x15, 0, 0, 0, 0, // lea rdx, [rip + function]
+                0xe9, 0, 0, 0, 0, // jmp trampoline
+            ],
+            vec![
+                relative(6, RelocationEncoding::Generic, function),
+                relative(11, RelocationEncoding::X86Branch, trampoline),
+            ],
+        ),
+        (Architecture::X86_64, _) => (
+            &[
+                0x48, 0x89, 0xf2, // mov rdx, rsi
+                0x48, 0x8d, 0x35, 0, 0, 0, 0, // lea rsi, [rip + function]
+                0xe9, 0, 0, 0, 0, // jmp trampoline
+            ],
+            vec![
+                relative(6, RelocationEncoding::Generic, function),
+                relative(11, RelocationEncoding::X86Branch, trampoline),
+            ],
+        ),
+        (Architecture::Aarch64(_), BinaryFormat::Elf) => (
+            AARCH64_CALL_WRAPPER,
+            vec![
+                raw(
+                    4,
+                    RelocationKind::Elf(elf::R_AARCH64_ADR_PREL_PG_HI21),
+                    function,
+                ),
+                raw(
+                    8,
+                    RelocationKind::Elf(elf::R_AARCH64_ADD_ABS_LO12_NC),
+                    function,
+                ),
+                raw(12, RelocationKind::Elf(elf::R_AARCH64_JUMP26), trampoline),
+            ],
+        ),
+        (Architecture::Aarch64(_), BinaryFormat::Macho) => {
+            let macho_kind = |value, relative| RelocationKind::MachO { value, relative };
+            (
+                AARCH64_CALL_WRAPPER,
+                vec![
+                    raw(4, macho_kind(macho::ARM64_RELOC_PAGE21, true), function),
+                    raw(8, macho_kind(macho::ARM64_RELOC_PAGEOFF12, false), function),
+                    raw(
+                        12,
+                        macho_kind(macho::ARM64_RELOC_BRANCH26, true),
+                        trampoline,
+                    ),
+                ],
+            )
+        }
+        _ => {
+            return Err(ObjectError::UnsupportedArchitecture(format!(
+                "{} (call wrapper)",
+                triple
+            )))
+        }
+    };
+    let section_id = obj.section_id(StandardSection::Text);
+    let symbol_id = add_unique_symbol(
+        obj,
+        ObjSymbol {
+            name: name.to_vec(),
+            value: 0,
+            size: code.len() as _,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: SymbolSection::Section(section_id),
+            flags: SymbolFlags::None,
+        },
+    )?;
+    let offset = obj.add_symbol_data(symbol_id, section_id, code, 16);
+    for relocation in relocations {
+        let relocation = Relocation {
+            offset: offset + relocation.offset,
+            ..relocation
+        };
+        obj.add_relocation(section_id, relocation)
+            .map_err(ObjectError::Write)?;
+    }
+
+    Ok(())
+}
+
+/// The code of a call wrapper on AArch64 (see `emit_call_wrapper`).
+const AARCH64_CALL_WRAPPER: &[u8] = &[
+    0xe2, 0x03, 0x01, 0xaa, // mov x2, x1
+    0x01, 0x00, 0x00, 0x90, // adrp x1, function
+    0x21, 0x00, 0x00, 0x91, // add x1, x1, :lo12:function
+    0x00, 0x00, 0x00, 0x14, // b trampoline
+];
+
 /// Emit the compilation result into an existing object.
 ///
 /// # Usage
@@ -128,6 +326,205 @@ pub fn emit_compilation(
     compilation: Compilation,
     symbol_registry: &impl SymbolRegistry,
     triple: &Triple,
+) -> Result<(), ObjectError> {
+    emit_compilation_into(
+        obj,
+        compilation,
+        symbol_registry,
+        triple,
+        &EmitOptions::default(),
+        None,
+    )
+}
+
+/// Emit the compilation result into an existing object, like
+/// [`emit_compilation`], and return a record of every relocation
+/// written to the object.
+///
+/// The records can be compared with the output of `objdump -r` when
+/// porting to a new target.
+pub fn emit_compilation_with_relocation_records(
+    obj: &mut Object,
+    compilation: Compilation,
+    symbol_registry: &impl SymbolRegistry,
+    triple: &Triple,
+) -> Result<Vec<RelocationRecord>, ObjectError> {
+    let mut relocation_records = vec![];
+    emit_compilation_into(
+        obj,
+        compilation,
+        symbol_registry,
+        triple,
+        &EmitOptions::default(),
+        Some(&mut relocation_records),
+    )?;
+    Ok(relocation_records)
+}
+
+/// Options for [`emit_compilation_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct EmitOptions {
+    /// Emit the custom sections bigger than this many bytes in their
+    /// own object section, instead of appending them to the shared data
+    /// or text section.
+    ///
+    /// The linker then handles each of them as a separate input section
+    /// (on ELF and COFF; Mach-O has no such subsections). They are
+    /// never split further: the compiled code indexes into them at
+    /// runtime, so each must stay contiguous, and they are used in
+    /// place from the loaded shared object, so they can't be stored
+    /// compressed either.
+    pub large_custom_section_threshold: Option<usize>,
+    /// Record every relocation written to the object.
+    pub record_relocations: bool,
+    /// Give the symbols that are never looked up by name when loading
+    /// the shared object (those of the custom sections) the
+    /// `SymbolScope::Linkage` scope, so they are not exported.
+    ///
+    /// The trampolines are always exported, as they are looked up by
+    /// name, and so are the functions unless `exported_functions` is set.
+    pub hide_internal_symbols: bool,
+    /// Only give the local functions in this set the
+    /// `SymbolScope::Dynamic` scope, or `None` to export all of them.
+    ///
+    /// The other functions get the `SymbolScope::Linkage` scope, so they
+    /// are not exported and must be looked up in the symbol table of the
+    /// linked shared object instead.
+    pub exported_functions: Option<HashSet<LocalFunctionIndex>>,
+    /// The relocation model of the object (`RelocationModel::Pic` by
+    /// default).
+    pub relocation_model: RelocationModel,
+    /// The alignment of the functions, trampolines and executable custom
+    /// sections, e.g. the page size for loaders that require page-aligned
+    /// code, or `None` for the minimum alignment of the architecture.
+    ///
+    /// It must be a power of two, and is raised to the minimum alignment
+    /// of the architecture if lower.
+    pub text_alignment: Option<u64>,
+    /// The alignment of the read-only custom sections, like
+    /// `text_alignment`.
+    pub data_alignment: Option<u64>,
+    /// Check that every relocation is within the data of the function or
+    /// custom section it applies to, instead of leaving it to the linker
+    /// (or worse, silently patching the following symbol).
+    ///
+    /// The targets of the relocations are always checked to be defined.
+    pub verify_relocations: bool,
+}
+
+/// How the relocations of an object reference their targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationModel {
+    /// Position-independent code, to be linked into a shared object:
+    /// calls to libcalls go through the PLT and other references to
+    /// them through the GOT (on ELF).
+    Pic,
+    /// Code to be linked into a static executable, where there is
+    /// neither a PLT nor a GOT: the targets (local functions, libcalls
+    /// and custom sections) are only referenced by 64-bit absolute
+    /// relocations.
+    ///
+    /// Calls go through a stub in the text section jumping to the
+    /// absolute address of their target, and GOT-relative loads read
+    /// that address from the stub instead. Pointers already are 64-bit
+    /// absolute relocations, and other PC-relative references (e.g. a
+    /// `lea` of a custom section) compute an address, so they are left
+    /// for the static linker to resolve. Only x86_64 and AArch64 are
+    /// supported.
+    Static,
+}
+
+impl Default for RelocationModel {
+    fn default() -> Self {
+        Self::Pic
+    }
+}
+
+impl RelocationModel {
+    /// The arguments to pass to the linker (invoked as a C compiler
+    /// driver, like `cc`) when linking an object of this model.
+    pub fn linker_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Pic => &["-shared"],
+            Self::Static => &["-static", "-no-pie"],
+        }
+    }
+}
+
+/// Emit the compilation result into an existing object, like
+/// [`emit_compilation`], with the given options.
+///
+/// The relocation records are only returned if enabled in the options,
+/// and are empty otherwise.
+pub fn emit_compilation_with_options(
+    obj: &mut Object,
+    compilation: Compilation,
+    symbol_registry: &impl SymbolRegistry,
+    triple: &Triple,
+    options: &EmitOptions,
+) -> Result<Vec<RelocationRecord>, ObjectError> {
+    let mut relocation_records = vec![];
+    emit_compilation_into(
+        obj,
+        compilation,
+        symbol_registry,
+        triple,
+        options,
+        if options.record_relocations {
+            Some(&mut relocation_records)
+        } else {
+            None
+        },
+    )?;
+    Ok(relocation_records)
+}
+
+/// A relocation written to an object by
+/// [`emit_compilation_with_relocation_records`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelocationRecord {
+    /// The name of the symbol (function or custom section) containing
+    /// the relocation.
+    pub symbol: String,
+    /// The name of the section containing the relocation.
+    pub section: String,
+    /// The offset of the relocation in its section.
+    pub offset: u64,
+    /// The relocation kind.
+    pub kind: RelocationKind,
+    /// The relocation encoding.
+    pub encoding: RelocationEncoding,
+    /// The size of the relocated field, in bits.
+    pub size: u8,
+    /// The name of the target symbol.
+    pub target: String,
+    /// The relocation addend.
+    pub addend: i64,
+}
+
+fn symbol_name(obj: &Object, symbol_id: SymbolId) -> String {
+    String::from_utf8_lossy(&obj.symbol(symbol_id).name).into_owned()
+}
+
+/// Add a symbol to an object, failing if a symbol with the same name
+/// was already added (e.g. because of a collision in the naming
+/// scheme of the symbol registry).
+fn add_unique_symbol(obj: &mut Object, symbol: ObjSymbol) -> Result<SymbolId, ObjectError> {
+    if obj.symbol_id(&symbol.name).is_some() {
+        return Err(ObjectError::DuplicateSymbol(
+            String::from_utf8_lossy(&symbol.name).into_owned(),
+        ));
+    }
+    Ok(obj.add_symbol(symbol))
+}
+
+fn emit_compilation_into(
+    obj: &mut Object,
+    compilation: Compilation,
+    symbol_registry: &impl SymbolRegistry,
+    triple: &Triple,
+    options: &EmitOptions,
+    mut relocation_records: Option<&mut Vec<RelocationRecord>>,
 ) -> Result<(), ObjectError> {
     let function_bodies = compilation.get_function_bodies();
     let function_relocations = compilation.get_relocations();
@@ -141,9 +538,11 @@ pub fn emit_compilation(
     let align = match triple.architecture {
         Architecture::X86_64 => 1,
         // In Arm64 is recommended a 4-byte alignment
-        Architecture::Aarch64(_) => 4,
+        Architecture::Aarch64(_) | Architecture::Arm(_) => 4,
         _ => 1,
     };
+    let text_align = section_alignment(options.text_alignment, align)?;
+    let data_align = section_alignment(options.data_alignment, align)?;
 
     // Add sections
     let custom_section_ids = custom_sections
@@ -156,46 +555,67 @@ pub fn emit_compilation(
                     obj.add_section(segment, DWARF_SECTION_NAME.to_vec(), SectionKind::Debug);
                 obj.append_section_data(section_id, custom_section.bytes.as_slice(), align);
                 let section_name = symbol_registry.symbol_to_name(Symbol::Section(section_index));
-                let symbol_id = obj.add_symbol(ObjSymbol {
-                    name: section_name.into_bytes(),
-                    value: 0,
-                    size: custom_section.bytes.len() as _,
-                    kind: SymbolKind::Data,
-                    scope: SymbolScope::Compilation,
-                    weak: false,
-                    section: SymbolSection::Section(section_id),
-                    flags: SymbolFlags::None,
-                });
-                (section_id, symbol_id)
+                let symbol_id = add_unique_symbol(
+                    obj,
+                    ObjSymbol {
+                        name: section_name.into_bytes(),
+                        value: 0,
+                        size: custom_section.bytes.len() as _,
+                        kind: SymbolKind::Data,
+                        scope: SymbolScope::Compilation,
+                        weak: false,
+                        section: SymbolSection::Section(section_id),
+                        flags: SymbolFlags::None,
+                    },
+                )?;
+                Ok(symbol_id)
             } else {
                 let section_name = symbol_registry.symbol_to_name(Symbol::Section(section_index));
-                let (section_kind, standard_section) = match custom_section.protection {
+                let (section_kind, standard_section, align) = match custom_section.protection {
                     CustomSectionProtection::ReadExecute => {
-                        (SymbolKind::Text, StandardSection::Text)
+                        (SymbolKind::Text, StandardSection::Text, text_align)
+                    }
+                    CustomSectionProtection::Read => {
+                        (SymbolKind::Data, StandardSection::Data, data_align)
                     }
-                    CustomSectionProtection::Read => (SymbolKind::Data, StandardSection::Data),
                 };
-                let section_id = obj.section_id(standard_section);
-                let symbol_id = obj.add_symbol(ObjSymbol {
-                    name: section_name.into_bytes(),
-                    value: 0,
-                    size: custom_section.bytes.len() as _,
-                    kind: section_kind,
-                    scope: SymbolScope::Dynamic,
-                    weak: false,
-                    section: SymbolSection::Section(section_id),
-                    flags: SymbolFlags::None,
-                });
+                let is_large = options
+                    .large_custom_section_threshold
+                    .map_or(false, |threshold| custom_section.bytes.len() > threshold);
+                let section_id = if is_large {
+                    obj.add_subsection(standard_section, section_name.as_bytes(), &[], align)
+                        .0
+                } else {
+                    obj.section_id(standard_section)
+                };
+                let scope = if options.hide_internal_symbols {
+                    SymbolScope::Linkage
+                } else {
+                    SymbolScope::Dynamic
+                };
+                let symbol_id = add_unique_symbol(
+                    obj,
+                    ObjSymbol {
+                        name: section_name.into_bytes(),
+                        value: 0,
+                        size: custom_section.bytes.len() as _,
+                        kind: section_kind,
+                        scope,
+                        weak: false,
+                        section: SymbolSection::Section(section_id),
+                        flags: SymbolFlags::None,
+                    },
+                )?;
                 obj.add_symbol_data(
                     symbol_id,
                     section_id,
                     custom_section.bytes.as_slice(),
                     align,
                 );
-                (section_id, symbol_id)
+                Ok(symbol_id)
             }
         })
-        .collect::<PrimaryMap<SectionIndex, _>>();
+        .collect::<Result<PrimaryMap<SectionIndex, _>, ObjectError>>()?;
 
     // Add functions
     let function_symbol_ids = function_bodies
@@ -204,7 +624,38 @@ pub fn emit_compilation(
             let function_name =
                 symbol_registry.symbol_to_name(Symbol::LocalFunction(function_local_index));
             let section_id = obj.section_id(StandardSection::Text);
-            let symbol_id = obj.add_symbol(ObjSymbol {
+            let scope = match &options.exported_functions {
+                Some(exported_functions) if !exported_functions.contains(&function_local_index) => {
+                    SymbolScope::Linkage
+                }
+                _ => SymbolScope::Dynamic,
+            };
+            let symbol_id = add_unique_symbol(
+                obj,
+                ObjSymbol {
+                    name: function_name.into_bytes(),
+                    value: 0,
+                    size: function.body.len() as _,
+                    kind: SymbolKind::Text,
+                    scope,
+                    weak: false,
+                    section: SymbolSection::Section(section_id),
+                    flags: SymbolFlags::None,
+                },
+            )?;
+            obj.add_symbol_data(symbol_id, section_id, &function.body, text_align);
+            Ok(symbol_id)
+        })
+        .collect::<Result<PrimaryMap<LocalFunctionIndex, _>, ObjectError>>()?;
+
+    // Add function call trampolines
+    for (signature_index, function) in function_call_trampolines.into_iter() {
+        let function_name =
+            symbol_registry.symbol_to_name(Symbol::FunctionCallTrampoline(signature_index));
+        let section_id = obj.section_id(StandardSection::Text);
+        let symbol_id = add_unique_symbol(
+            obj,
+            ObjSymbol {
                 name: function_name.into_bytes(),
                 value: 0,
                 size: function.body.len() as _,
@@ -213,28 +664,9 @@ pub fn emit_compilation(
                 weak: false,
                 section: SymbolSection::Section(section_id),
                 flags: SymbolFlags::None,
-            });
-            obj.add_symbol_data(symbol_id, section_id, &function.body, align);
-            (section_id, symbol_id)
-        })
-        .collect::<PrimaryMap<LocalFunctionIndex, _>>();
-
-    // Add function call trampolines
-    for (signature_index, function) in function_call_trampolines.into_iter() {
-        let function_name =
-            symbol_registry.symbol_to_name(Symbol::FunctionCallTrampoline(signature_index));
-        let section_id = obj.section_id(StandardSection::Text);
-        let symbol_id = obj.add_symbol(ObjSymbol {
-            name: function_name.into_bytes(),
-            value: 0,
-            size: function.body.len() as _,
-            kind: SymbolKind::Text,
-            scope: SymbolScope::Dynamic,
-            weak: false,
-            section: SymbolSection::Section(section_id),
-            flags: SymbolFlags::None,
-        });
-        obj.add_symbol_data(symbol_id, section_id, &function.body, align);
+            },
+        )?;
+        obj.add_symbol_data(symbol_id, section_id, &function.body, text_align);
     }
 
     // Add dynamic function trampolines
@@ -242,93 +674,87 @@ pub fn emit_compilation(
         let function_name =
             symbol_registry.symbol_to_name(Symbol::DynamicFunctionTrampoline(func_index));
         let section_id = obj.section_id(StandardSection::Text);
-        let symbol_id = obj.add_symbol(ObjSymbol {
-            name: function_name.into_bytes(),
-            value: 0,
-            size: function.body.len() as _,
-            kind: SymbolKind::Text,
-            scope: SymbolScope::Dynamic,
-            weak: false,
-            section: SymbolSection::Section(section_id),
-            flags: SymbolFlags::None,
-        });
-        obj.add_symbol_data(symbol_id, section_id, &function.body, align);
+        let symbol_id = add_unique_symbol(
+            obj,
+            ObjSymbol {
+                name: function_name.into_bytes(),
+                value: 0,
+                size: function.body.len() as _,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Dynamic,
+                weak: false,
+                section: SymbolSection::Section(section_id),
+                flags: SymbolFlags::None,
+            },
+        )?;
+        obj.add_symbol_data(symbol_id, section_id, &function.body, text_align);
     }
 
     let mut all_relocations = Vec::new();
+    // The stubs of the targets of the relocations, for the
+    // `RelocationModel::Static` model.
+    let mut static_stubs = HashMap::new();
 
+    // Most functions of big modules have no relocations at all, so they
+    // are skipped before looking up their sections.
     for (function_local_index, relocations) in function_relocations.into_iter() {
-        let (section_id, symbol_id) = function_symbol_ids.get(function_local_index).unwrap();
-        all_relocations.push((*section_id, *symbol_id, relocations))
+        if relocations.is_empty() {
+            continue;
+        }
+        let symbol_id = function_symbol_ids[function_local_index];
+        all_relocations.push((symbol_id, relocations))
     }
 
     for (section_index, relocations) in custom_section_relocations.into_iter() {
+        if relocations.is_empty() {
+            continue;
+        }
         if !debug_index.map(|d| d == section_index).unwrap_or(false) {
             // Skip DWARF relocations just yet
-            let (section_id, symbol_id) = custom_section_ids.get(section_index).unwrap();
-            all_relocations.push((*section_id, *symbol_id, relocations));
+            let symbol_id = custom_section_ids[section_index];
+            all_relocations.push((symbol_id, relocations));
         }
     }
 
-    for (section_id, symbol_id, relocations) in all_relocations.into_iter() {
-        let (_symbol_id, section_offset) = obj.symbol_section_and_offset(symbol_id).unwrap();
+    // Functions and custom sections can be in different sections of the
+    // same kind (e.g. large custom sections), so relocations are added to
+    // the section each symbol was actually defined in.
+    for (symbol_id, relocations) in all_relocations.into_iter() {
+        let section_id = match obj.symbol(symbol_id).section {
+            SymbolSection::Section(section_id) => section_id,
+            _ => return Err(ObjectError::MissingSymbol(symbol_name(obj, symbol_id))),
+        };
+        let (_symbol_id, section_offset) = obj
+            .symbol_section_and_offset(symbol_id)
+            .ok_or_else(|| ObjectError::MissingSymbol(symbol_name(obj, symbol_id)))?;
 
         for r in relocations {
-            let (relocation_kind, relocation_encoding, relocation_size) = match r.kind {
-                Reloc::Abs4 => (RelocationKind::Absolute, RelocationEncoding::Generic, 32),
-                Reloc::Abs8 => (RelocationKind::Absolute, RelocationEncoding::Generic, 64),
-                Reloc::X86PCRel4 => (RelocationKind::Relative, RelocationEncoding::Generic, 32),
-                Reloc::X86CallPCRel4 => {
-                    (RelocationKind::Relative, RelocationEncoding::X86Branch, 32)
-                }
-                Reloc::X86CallPLTRel4 => (
-                    RelocationKind::PltRelative,
-                    RelocationEncoding::X86Branch,
-                    32,
-                ),
-                Reloc::X86GOTPCRel4 => {
-                    (RelocationKind::GotRelative, RelocationEncoding::Generic, 32)
-                }
-                // Reloc::X86PCRelRodata4 => {
-                // }
-                Reloc::Arm64Call => (
-                    RelocationKind::Elf(elf::R_AARCH64_CALL26),
-                    RelocationEncoding::Generic,
-                    32,
-                ),
-                Reloc::ElfX86_64TlsGd => (
-                    RelocationKind::Elf(elf::R_X86_64_TLSGD),
-                    RelocationEncoding::Generic,
-                    32,
-                ),
-                other => {
-                    return Err(ObjectError::UnsupportedArchitecture(format!(
-                        "{} (relocation: {}",
-                        triple.architecture, other
-                    )))
-                }
-            };
+            let (relocation_kind, relocation_encoding, relocation_size) =
+                relocation_for_target(r.kind, r.addend, triple)?;
 
+            if options.verify_relocations {
+                verify_relocation_bounds(obj, symbol_id, r.offset, relocation_size)?;
+            }
             let relocation_address = section_offset + r.offset as u64;
 
-            match r.reloc_target {
+            let (target_symbol, relocation_kind) = match r.reloc_target {
                 RelocationTarget::LocalFunc(index) => {
-                    let (_, target_symbol) = function_symbol_ids.get(index).unwrap();
-                    obj.add_relocation(
-                        section_id,
-                        Relocation {
-                            offset: relocation_address,
-                            size: relocation_size,
-                            kind: relocation_kind,
-                            encoding: relocation_encoding,
-                            symbol: *target_symbol,
-                            addend: r.addend,
-                        },
-                    )
-                    .map_err(ObjectError::Write)?;
+                    let target_symbol = function_symbol_ids.get(index).ok_or_else(|| {
+                        ObjectError::MissingSymbol(
+                            symbol_registry.symbol_to_name(Symbol::LocalFunction(index)),
+                        )
+                    })?;
+                    (*target_symbol, relocation_kind)
                 }
                 RelocationTarget::LibCall(libcall) => {
                     let libcall_fn_name = libcall.to_function_name().as_bytes();
+                    // A defined symbol with the name of a libcall would be
+                    // silently used in its place.
+                    if let Some(existing) = obj.symbol_id(libcall_fn_name) {
+                        if obj.symbol(existing).section != SymbolSection::Undefined {
+                            return Err(ObjectError::DuplicateSymbol(symbol_name(obj, existing)));
+                        }
+                    }
                     // We add the symols lazily as we see them
                     let target_symbol = obj.symbol_id(libcall_fn_name).unwrap_or_else(|| {
                         obj.add_symbol(ObjSymbol {
@@ -342,40 +768,989 @@ pub fn emit_compilation(
                             flags: SymbolFlags::None,
                         })
                     });
-                    obj.add_relocation(
-                        section_id,
-                        Relocation {
-                            offset: relocation_address,
-                            size: relocation_size,
-                            kind: relocation_kind,
-                            encoding: relocation_encoding,
-                            symbol: target_symbol,
-                            addend: r.addend,
-                        },
-                    )
-                    .map_err(ObjectError::Write)?;
+                    let relocation_kind = match options.relocation_model {
+                        RelocationModel::Pic => {
+                            libcall_relocation_kind(relocation_kind, relocation_encoding, triple)
+                        }
+                        RelocationModel::Static => relocation_kind,
+                    };
+                    (target_symbol, relocation_kind)
                 }
                 RelocationTarget::CustomSection(section_index) => {
-                    let (_, target_symbol) = custom_section_ids.get(section_index).unwrap();
-                    obj.add_relocation(
-                        section_id,
-                        Relocation {
-                            offset: relocation_address,
-                            size: relocation_size,
-                            kind: relocation_kind,
-                            encoding: relocation_encoding,
-                            symbol: *target_symbol,
-                            addend: r.addend,
-                        },
-                    )
-                    .map_err(ObjectError::Write)?;
+                    let target_symbol = custom_section_ids.get(section_index).ok_or_else(|| {
+                        ObjectError::MissingSymbol(
+                            symbol_registry.symbol_to_name(Symbol::Section(section_index)),
+                        )
+                    })?;
+                    (*target_symbol, relocation_kind)
                 }
                 RelocationTarget::JumpTable(_func_index, _jt) => {
                     // do nothing
+                    continue;
                 }
             };
+            let (target_symbol, relocation_kind, addend) = match options.relocation_model {
+                RelocationModel::Pic => (target_symbol, relocation_kind, r.addend),
+                RelocationModel::Static => {
+                    let is_branch = matches!(
+                        (relocation_kind, relocation_encoding),
+                        (_, RelocationEncoding::X86Branch)
+                            | (RelocationKind::Elf(elf::R_AARCH64_CALL26), _)
+                            | (
+                                RelocationKind::MachO {
+                                    value: macho::ARM64_RELOC_BRANCH26,
+                                    ..
+                                },
+                                _
+                            )
+                            | (RelocationKind::Elf(elf::R_ARM_CALL), _)
+                    );
+                    let is_got_load = relocation_kind == RelocationKind::GotRelative;
+                    if is_branch || is_got_load {
+                        let (stub_symbol, slot_offset) = static_stub(
+                            obj,
+                            &mut static_stubs,
+                            target_symbol,
+                            triple,
+                            &mut relocation_records,
+                        )?;
+                        // A branch jumps to the stub, which jumps to the
+                        // target, and a GOT-relative load reads the address
+                        // of the target from the stub.
+                        if is_got_load {
+                            (
+                                stub_symbol,
+                                RelocationKind::Relative,
+                                r.addend + slot_offset as i64,
+                            )
+                        } else if relocation_kind == RelocationKind::PltRelative {
+                            (stub_symbol, RelocationKind::Relative, r.addend)
+                        } else {
+                            (stub_symbol, relocation_kind, r.addend)
+                        }
+                    } else {
+                        (target_symbol, relocation_kind, r.addend)
+                    }
+                }
+            };
+            let relocation = Relocation {
+                offset: relocation_address,
+                size: relocation_size,
+                kind: relocation_kind,
+                encoding: relocation_encoding,
+                symbol: target_symbol,
+                addend,
+            };
+            validate_relocation(relocation.kind, relocation.encoding, triple)?;
+            if let Some(records) = &mut relocation_records {
+                records.push(RelocationRecord {
+                    symbol: symbol_name(obj, symbol_id),
+                    section: obj.section(section_id).name().unwrap_or("").to_string(),
+                    offset: relocation.offset,
+                    kind: relocation.kind,
+                    encoding: relocation.encoding,
+                    size: relocation.size,
+                    target: symbol_name(obj, target_symbol),
+                    addend: relocation.addend,
+                });
+            }
+            obj.add_relocation(section_id, relocation)
+                .map_err(ObjectError::Write)?;
         }
     }
 
     Ok(())
 }
+
+/// The object file relocation kind, encoding and size for a compiler
+/// relocation on the given target.
+///
+/// The addend is passed through as is: the `object` crate stores it in
+/// the relocation entry for formats with explicit addends (ELF), and
+/// writes it in the relocated bytes for formats with implicit addends
+/// (Mach-O, COFF), adjusting PC-relative addends as needed. This is only
+/// correct when the relocated bytes are a plain field, so relocations
+/// with an addend encoded in an instruction (like AArch64 branches on
+/// Mach-O) must have a zero addend.
+///
+/// Relocation sizes follow the target, not the host: 64-bit absolute
+/// relocations can't be represented on 32-bit targets.
+fn relocation_for_target(
+    kind: Reloc,
+    addend: i64,
+    triple: &Triple,
+) -> Result<(RelocationKind, RelocationEncoding, u8), ObjectError> {
+    let unsupported = || {
+        ObjectError::UnsupportedArchitecture(format!(
+            "{} (relocation: {}, addend: {})",
+            triple, kind, addend
+        ))
+    };
+    let pointer_width = triple.pointer_width().map_err(|_| unsupported())?.bits();
+    Ok(match (kind, triple.binary_format) {
+        (Reloc::Abs4, _) => (RelocationKind::Absolute, RelocationEncoding::Generic, 32),
+        (Reloc::Abs8, _) if pointer_width == 64 => {
+            (RelocationKind::Absolute, RelocationEncoding::Generic, 64)
+        }
+        (Reloc::X86PCRel4, _) => (RelocationKind::Relative, RelocationEncoding::Generic, 32),
+        (Reloc::X86CallPCRel4, _) => (RelocationKind::Relative, RelocationEncoding::X86Branch, 32),
+        (Reloc::X86CallPLTRel4, _) => (
+            RelocationKind::PltRelative,
+            RelocationEncoding::X86Branch,
+            32,
+        ),
+        (Reloc::X86GOTPCRel4, _) => (RelocationKind::GotRelative, RelocationEncoding::Generic, 32),
+        // Reloc::X86PCRelRodata4 => {
+        // }
+        (Reloc::Arm64Call, BinaryFormat::Elf) => (
+            RelocationKind::Elf(elf::R_AARCH64_CALL26),
+            RelocationEncoding::Generic,
+            32,
+        ),
+        // Mach-O has no explicit addends, and the addend of a branch
+        // can't be written into the instruction.
+        (Reloc::Arm64Call, BinaryFormat::Macho) if addend == 0 => (
+            RelocationKind::MachO {
+                value: macho::ARM64_RELOC_BRANCH26,
+                relative: true,
+            },
+            RelocationEncoding::Generic,
+            32,
+        ),
+        // 32-bit Arm ELF objects have implicit addends too, written into
+        // the branch instruction.
+        (Reloc::Arm32Call, BinaryFormat::Elf) if addend == 0 => (
+            RelocationKind::Elf(elf::R_ARM_CALL),
+            RelocationEncoding::Generic,
+            32,
+        ),
+        (Reloc::ElfX86_64TlsGd, BinaryFormat::Elf)
+            if triple.architecture == Architecture::X86_64 =>
+        {
+            (
+                RelocationKind::Elf(elf::R_X86_64_TLSGD),
+                RelocationEncoding::Generic,
+                32,
+            )
+        }
+        _ => return Err(unsupported()),
+    })
+}
+
+/// Check that a relocation of `size` bits at `offset` is within the data
+/// of the symbol it applies to.
+fn verify_relocation_bounds(
+    obj: &Object,
+    symbol_id: SymbolId,
+    offset: CodeOffset,
+    size: u8,
+) -> Result<(), ObjectError> {
+    let symbol_size = obj.symbol(symbol_id).size;
+    let end = offset as u64 + u64::from(size / 8);
+    if end <= symbol_size {
+        Ok(())
+    } else {
+        Err(ObjectError::RelocationOutOfBounds(format!(
+            "{}-bit relocation at offset {} of `{}`, which is {} bytes long",
+            size,
+            offset,
+            symbol_name(obj, symbol_id),
+            symbol_size
+        )))
+    }
+}
+
+/// The alignment of a section: the `requested` one, if any, but no less
+/// than the `minimum` alignment of the architecture.
+fn section_alignment(requested: Option<u64>, minimum: u64) -> Result<u64, ObjectError> {
+    match requested {
+        Some(align) if !align.is_power_of_two() => Err(ObjectError::InvalidAlignment(align)),
+        Some(align) => Ok(align.max(minimum)),
+        None => Ok(minimum),
+    }
+}
+
+/// Check that the kind and encoding of a relocation are legal for the
+/// binary format and architecture of the target.
+///
+/// The `object` crate doesn't validate all of them when writing the
+/// object, so an illegal relocation could otherwise silently be written
+/// and only fail when linking or loading the generated code.
+fn validate_relocation(
+    kind: RelocationKind,
+    encoding: RelocationEncoding,
+    triple: &Triple,
+) -> Result<(), ObjectError> {
+    let format_supports_kind = match (kind, triple.binary_format) {
+        (RelocationKind::Elf(_), format) => format == BinaryFormat::Elf,
+        (RelocationKind::MachO { .. }, format) => format == BinaryFormat::Macho,
+        (RelocationKind::Coff(_), format) => format == BinaryFormat::Coff,
+        _ => true,
+    };
+    let architecture_supports_encoding = match encoding {
+        RelocationEncoding::Generic => true,
+        RelocationEncoding::X86Signed
+        | RelocationEncoding::X86RipRelative
+        | RelocationEncoding::X86RipRelativeMovq
+        | RelocationEncoding::X86Branch => matches!(
+            triple.architecture,
+            Architecture::X86_64 | Architecture::X86_32(_)
+        ),
+        // No other encoding is emitted for the supported architectures.
+        _ => false,
+    };
+    if format_supports_kind && architecture_supports_encoding {
+        Ok(())
+    } else {
+        Err(ObjectError::UnsupportedRelocation(format!(
+            "{:?} relocation with {:?} encoding for {} ({} format)",
+            kind, encoding, triple.architecture, triple.binary_format
+        )))
+    }
+}
+
+/// The relocation kind to use for a reference to a libcall.
+///
+/// Libcalls are not defined in the generated object: they are resolved
+/// at load time (possibly from the host executable), so on ELF targets
+/// a PC-relative call to them can't be resolved by the static linker
+/// when building a shared object, and is routed through the PLT. Other
+/// references are left alone: going through the GOT would change what
+/// they compute (e.g. a `lea` would get the address of the GOT entry),
+/// so only the ones the compiler emitted as GOT-relative use it.
+fn libcall_relocation_kind(
+    kind: RelocationKind,
+    encoding: RelocationEncoding,
+    triple: &Triple,
+) -> RelocationKind {
+    match (triple.binary_format, kind, encoding) {
+        (BinaryFormat::Elf, RelocationKind::Relative, RelocationEncoding::X86Branch) => {
+            RelocationKind::PltRelative
+        }
+        _ => kind,
+    }
+}
+
+/// The stub of `target` in an object linked into a static executable
+/// (see `RelocationModel::Static`), added to the text section the first
+/// time it's needed, and the offset in the stub of the 64-bit absolute
+/// address of the target it jumps to.
+fn static_stub(
+    obj: &mut Object,
+    stubs: &mut HashMap<SymbolId, SymbolId>,
+    target: SymbolId,
+    triple: &Triple,
+    relocation_records: &mut Option<&mut Vec<RelocationRecord>>,
+) -> Result<(SymbolId, u64), ObjectError> {
+    let code: &[u8] = match triple.architecture {
+        // jmp *0(%rip)
+        Architecture::X86_64 => &[0xff, 0x25, 0x00, 0x00, 0x00, 0x00],
+        // ldr x16, #8; br x16
+        Architecture::Aarch64(_) => &[0x50, 0x00, 0x00, 0x58, 0x00, 0x02, 0x1f, 0xd6],
+        _ => {
+            return Err(ObjectError::UnsupportedRelocation(format!(
+                "static relocation model for {}",
+                triple.architecture
+            )))
+        }
+    };
+    let slot_offset = code.len() as u64;
+    if let Some(stub) = stubs.get(&target) {
+        return Ok((*stub, slot_offset));
+    }
+    let mut stub = code.to_vec();
+    stub.extend_from_slice(&[0; 8]);
+    let section_id = obj.section_id(StandardSection::Text);
+    let stub_symbol = add_unique_symbol(
+        obj,
+        ObjSymbol {
+            name: format!("{}$static_stub", symbol_name(obj, target)).into_bytes(),
+            value: 0,
+            size: stub.len() as _,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: SymbolSection::Section(section_id),
+            flags: SymbolFlags::None,
+        },
+    )?;
+    let stub_offset = obj.add_symbol_data(stub_symbol, section_id, &stub, 16);
+    let relocation = Relocation {
+        offset: stub_offset + slot_offset,
+        size: 64,
+        kind: RelocationKind::Absolute,
+        encoding: RelocationEncoding::Generic,
+        symbol: target,
+        addend: 0,
+    };
+    if let Some(records) = relocation_records {
+        records.push(RelocationRecord {
+            symbol: symbol_name(obj, stub_symbol),
+            section: obj.section(section_id).name().unwrap_or("").to_string(),
+            offset: relocation.offset,
+            kind: relocation.kind,
+            encoding: relocation.encoding,
+            size: relocation.size,
+            target: symbol_name(obj, target),
+            addend: relocation.addend,
+        });
+    }
+    obj.add_relocation(section_id, relocation)
+        .map_err(ObjectError::Write)?;
+    stubs.insert(target, stub_symbol);
+    Ok((stub_symbol, slot_offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use wasmer_compiler::{
+        CompiledFunction, CustomSection, FunctionBody, Relocation as CompilerRelocation,
+        SectionBody,
+    };
+    use wasmer_types::entity::{EntityRef, SecondaryMap};
+    use wasmer_vm::libcalls::LibCall;
+
+    #[test]
+    fn endianness_fallback() {
+        for (triple, endianness) in &[
+            ("x86_64-unknown-linux-gnu", Endianness::Little),
+            ("i686-unknown-linux-gnu", Endianness::Little),
+            ("aarch64-apple-darwin", Endianness::Little),
+            ("aarch64_be-unknown-linux-gnu", Endianness::Big),
+        ] {
+            let triple = Triple::from_str(triple).unwrap();
+            assert_eq!(target_endianness(&triple).unwrap(), *endianness);
+        }
+        assert!(matches!(
+            target_endianness(&Triple::unknown()),
+            Err(ObjectError::UnknownEndianness)
+        ));
+    }
+
+    #[test]
+    fn libcall_relocation_kinds() {
+        let elf = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        assert_eq!(
+            libcall_relocation_kind(
+                RelocationKind::Relative,
+                RelocationEncoding::X86Branch,
+                &elf
+            ),
+            RelocationKind::PltRelative
+        );
+        assert_eq!(
+            libcall_relocation_kind(RelocationKind::Relative, RelocationEncoding::Generic, &elf),
+            RelocationKind::Relative
+        );
+        assert_eq!(
+            libcall_relocation_kind(
+                RelocationKind::GotRelative,
+                RelocationEncoding::Generic,
+                &elf
+            ),
+            RelocationKind::GotRelative
+        );
+        assert_eq!(
+            libcall_relocation_kind(RelocationKind::Absolute, RelocationEncoding::Generic, &elf),
+            RelocationKind::Absolute
+        );
+
+        let macho = Triple::from_str("x86_64-apple-darwin").unwrap();
+        assert_eq!(
+            libcall_relocation_kind(
+                RelocationKind::Relative,
+                RelocationEncoding::X86Branch,
+                &macho
+            ),
+            RelocationKind::Relative
+        );
+    }
+
+    #[test]
+    fn relocation_addends() {
+        let aarch64_elf = Triple::from_str("aarch64-unknown-linux-gnu").unwrap();
+        let aarch64_macho = Triple::from_str("aarch64-apple-darwin").unwrap();
+        let x86_64_macho = Triple::from_str("x86_64-apple-darwin").unwrap();
+
+        // A call to an offset within a function has a nonzero addend,
+        // which ELF can represent.
+        assert!(relocation_for_target(Reloc::Arm64Call, 16, &aarch64_elf).is_ok());
+        assert!(relocation_for_target(Reloc::Arm64Call, 0, &aarch64_macho).is_ok());
+        // Mach-O can't encode it in the branch instruction.
+        assert!(relocation_for_target(Reloc::Arm64Call, 16, &aarch64_macho).is_err());
+        // PC-relative addends are adjusted by the `object` crate.
+        assert_eq!(
+            relocation_for_target(Reloc::X86CallPCRel4, -4, &x86_64_macho).unwrap(),
+            (RelocationKind::Relative, RelocationEncoding::X86Branch, 32)
+        );
+        assert!(relocation_for_target(Reloc::ElfX86_64TlsGd, 0, &x86_64_macho).is_err());
+    }
+
+    #[test]
+    fn android_arm_objects() {
+        let armv7_android = Triple::from_str("armv7-linux-androideabi").unwrap();
+        let obj = get_object_for_target(&armv7_android).unwrap();
+        assert_eq!(obj.architecture(), object::Architecture::Arm);
+
+        assert_eq!(
+            relocation_for_target(Reloc::Arm32Call, 0, &armv7_android).unwrap(),
+            (
+                RelocationKind::Elf(elf::R_ARM_CALL),
+                RelocationEncoding::Generic,
+                32
+            )
+        );
+        assert!(relocation_for_target(Reloc::Arm32Call, 8, &armv7_android).is_err());
+    }
+
+    #[test]
+    fn relocation_validation() {
+        let x86_64_elf = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let x86_64_coff = Triple::from_str("x86_64-pc-windows-msvc").unwrap();
+        let aarch64_macho = Triple::from_str("aarch64-apple-darwin").unwrap();
+
+        let plt = RelocationKind::PltRelative;
+        let branch = RelocationEncoding::X86Branch;
+        assert!(validate_relocation(plt, branch, &x86_64_elf).is_ok());
+        assert!(validate_relocation(plt, branch, &aarch64_macho).is_err());
+
+        let generic = RelocationEncoding::Generic;
+        let tls = RelocationKind::Elf(elf::R_X86_64_TLSGD);
+        assert!(validate_relocation(tls, generic, &x86_64_elf).is_ok());
+        assert!(validate_relocation(tls, generic, &aarch64_macho).is_err());
+
+        let call = RelocationKind::MachO {
+            value: macho::ARM64_RELOC_BRANCH26,
+            relative: true,
+        };
+        assert!(validate_relocation(call, generic, &aarch64_macho).is_ok());
+        assert!(validate_relocation(call, generic, &x86_64_coff).is_err());
+
+        let coff = RelocationKind::Coff(1);
+        assert!(validate_relocation(coff, generic, &x86_64_coff).is_ok());
+        assert!(validate_relocation(coff, generic, &x86_64_elf).is_err());
+    }
+
+    struct TestSymbolRegistry;
+
+    impl SymbolRegistry for TestSymbolRegistry {
+        fn symbol_to_name(&self, symbol: Symbol) -> String {
+            match symbol {
+                Symbol::LocalFunction(index) => format!("function_{}", index.index()),
+                Symbol::Section(index) => format!("section_{}", index.index()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn name_to_symbol(&self, _name: &str) -> Option<Symbol> {
+            None
+        }
+    }
+
+    #[test]
+    fn custom_section_options() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let custom_sections = [8, 64]
+            .iter()
+            .map(|size| CustomSection {
+                protection: CustomSectionProtection::Read,
+                bytes: SectionBody::new_with_vec(vec![0; *size]),
+                relocations: vec![],
+            })
+            .collect::<PrimaryMap<SectionIndex, _>>();
+        let compilation = Compilation::new(
+            PrimaryMap::new(),
+            custom_sections,
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            None,
+            None,
+        );
+        let mut obj = get_object_for_target(&triple).unwrap();
+        let options = EmitOptions {
+            large_custom_section_threshold: Some(16),
+            hide_internal_symbols: true,
+            ..EmitOptions::default()
+        };
+        emit_compilation_with_options(
+            &mut obj,
+            compilation,
+            &TestSymbolRegistry,
+            &triple,
+            &options,
+        )
+        .unwrap();
+
+        let data_section = obj.section_id(StandardSection::Data);
+        let symbol = |name: &[u8]| obj.symbol(obj.symbol_id(name).unwrap());
+        let section_of = |name: &[u8]| symbol(name).section;
+        assert_eq!(
+            section_of(b"section_0"),
+            SymbolSection::Section(data_section)
+        );
+        assert_ne!(
+            section_of(b"section_1"),
+            SymbolSection::Section(data_section)
+        );
+        assert_eq!(symbol(b"section_0").scope, SymbolScope::Linkage);
+    }
+
+    #[test]
+    fn relocations_in_multiple_sections() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        // Two large executable custom sections referencing each other, each
+        // in its own text section.
+        let custom_sections = [1, 0]
+            .iter()
+            .map(|target| CustomSection {
+                protection: CustomSectionProtection::ReadExecute,
+                bytes: SectionBody::new_with_vec(vec![0; 64]),
+                relocations: vec![CompilerRelocation {
+                    kind: Reloc::X86PCRel4,
+                    reloc_target: RelocationTarget::CustomSection(SectionIndex::new(*target)),
+                    offset: 8,
+                    addend: -4,
+                }],
+            })
+            .collect::<PrimaryMap<SectionIndex, _>>();
+        let compilation = Compilation::new(
+            PrimaryMap::new(),
+            custom_sections,
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            None,
+            None,
+        );
+        let mut obj = get_object_for_target(&triple).unwrap();
+        let options = EmitOptions {
+            large_custom_section_threshold: Some(16),
+            record_relocations: true,
+            ..EmitOptions::default()
+        };
+        let records = emit_compilation_with_options(
+            &mut obj,
+            compilation,
+            &TestSymbolRegistry,
+            &triple,
+            &options,
+        )
+        .unwrap();
+
+        let section_name =
+            |name: &str| match obj.symbol(obj.symbol_id(name.as_bytes()).unwrap()).section {
+                SymbolSection::Section(section_id) => {
+                    obj.section(section_id).name().unwrap().to_string()
+                }
+                section => panic!("unexpected section {:?}", section),
+            };
+        assert_ne!(section_name("section_0"), section_name("section_1"));
+        assert_eq!(records.len(), 2);
+        for record in &records {
+            assert_eq!(record.section, section_name(&record.symbol));
+            assert_eq!(record.offset, 8);
+        }
+    }
+
+    #[test]
+    fn exported_functions() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let function = CompiledFunction {
+            body: FunctionBody {
+                body: vec![0xc3],
+                unwind_info: None,
+            },
+            relocations: vec![],
+            jt_offsets: SecondaryMap::new(),
+            frame_info: Default::default(),
+        };
+        let functions = vec![function.clone(), function]
+            .into_iter()
+            .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+        let compilation = Compilation::new(
+            functions,
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            None,
+            None,
+        );
+        let mut obj = get_object_for_target(&triple).unwrap();
+        let options = EmitOptions {
+            exported_functions: Some(std::iter::once(LocalFunctionIndex::new(1)).collect()),
+            ..EmitOptions::default()
+        };
+        emit_compilation_with_options(
+            &mut obj,
+            compilation,
+            &TestSymbolRegistry,
+            &triple,
+            &options,
+        )
+        .unwrap();
+
+        let scope = |name: &[u8]| obj.symbol(obj.symbol_id(name).unwrap()).scope;
+        assert_eq!(scope(b"function_0"), SymbolScope::Linkage);
+        assert_eq!(scope(b"function_1"), SymbolScope::Dynamic);
+    }
+
+    #[test]
+    fn section_alignments() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let compilation = || {
+            let function = CompiledFunction {
+                body: FunctionBody {
+                    body: vec![0xc3],
+                    unwind_info: None,
+                },
+                relocations: vec![],
+                jt_offsets: SecondaryMap::new(),
+                frame_info: Default::default(),
+            };
+            let custom_section = CustomSection {
+                protection: CustomSectionProtection::Read,
+                bytes: SectionBody::new_with_vec(vec![0; 8]),
+                relocations: vec![],
+            };
+            Compilation::new(
+                vec![function.clone(), function].into_iter().collect(),
+                vec![custom_section.clone(), custom_section]
+                    .into_iter()
+                    .collect(),
+                PrimaryMap::new(),
+                PrimaryMap::new(),
+                None,
+                None,
+            )
+        };
+        let mut obj = get_object_for_target(&triple).unwrap();
+        let options = EmitOptions {
+            text_alignment: Some(4096),
+            data_alignment: Some(64),
+            ..EmitOptions::default()
+        };
+        emit_compilation_with_options(
+            &mut obj,
+            compilation(),
+            &TestSymbolRegistry,
+            &triple,
+            &options,
+        )
+        .unwrap();
+
+        let value = |name: &[u8]| obj.symbol(obj.symbol_id(name).unwrap()).value;
+        assert_eq!(value(b"function_0"), 0);
+        assert_eq!(value(b"function_1"), 4096);
+        assert_eq!(value(b"section_1") - value(b"section_0"), 64);
+
+        let mut obj = get_object_for_target(&triple).unwrap();
+        let options = EmitOptions {
+            text_alignment: Some(24),
+            ..EmitOptions::default()
+        };
+        assert!(matches!(
+            emit_compilation_with_options(
+                &mut obj,
+                compilation(),
+                &TestSymbolRegistry,
+                &triple,
+                &options,
+            ),
+            Err(ObjectError::InvalidAlignment(24))
+        ));
+    }
+
+    #[test]
+    fn relocation_bounds() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        // A 4-byte relocation at `offset` of an 8-byte function.
+        let compilation = |offset| {
+            let function = CompiledFunction {
+                body: FunctionBody {
+                    body: vec![0; 8],
+                    unwind_info: None,
+                },
+                relocations: vec![CompilerRelocation {
+                    kind: Reloc::X86PCRel4,
+                    reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::new(0)),
+                    offset,
+                    addend: -4,
+                }],
+                jt_offsets: SecondaryMap::new(),
+                frame_info: Default::default(),
+            };
+            Compilation::new(
+                std::iter::once(function).collect(),
+                PrimaryMap::new(),
+                PrimaryMap::new(),
+                PrimaryMap::new(),
+                None,
+                None,
+            )
+        };
+        let options = EmitOptions {
+            verify_relocations: true,
+            ..EmitOptions::default()
+        };
+        let emit = |offset, options: &EmitOptions| {
+            let mut obj = get_object_for_target(&triple).unwrap();
+            emit_compilation_with_options(
+                &mut obj,
+                compilation(offset),
+                &TestSymbolRegistry,
+                &triple,
+                options,
+            )
+        };
+
+        assert!(emit(4, &options).is_ok());
+        assert!(matches!(
+            emit(6, &options),
+            Err(ObjectError::RelocationOutOfBounds(message)) if message.contains("function_0")
+        ));
+        // Not checked by default.
+        assert!(emit(6, &EmitOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn static_relocation_model() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let call = |reloc_target| CompilerRelocation {
+            kind: Reloc::X86CallPLTRel4,
+            reloc_target,
+            offset: 1,
+            addend: -4,
+        };
+        let function = CompiledFunction {
+            body: FunctionBody {
+                // call; call; mov rax, [rip + ...]; ret
+                body: vec![
+                    0xe8, 0, 0, 0, 0, 0xe8, 0, 0, 0, 0, 0x48, 0x8b, 0x05, 0, 0, 0, 0, 0xc3,
+                ],
+                unwind_info: None,
+            },
+            relocations: vec![
+                call(RelocationTarget::LocalFunc(LocalFunctionIndex::new(0))),
+                CompilerRelocation {
+                    offset: 6,
+                    ..call(RelocationTarget::LibCall(LibCall::CeilF32))
+                },
+                CompilerRelocation {
+                    kind: Reloc::X86GOTPCRel4,
+                    offset: 13,
+                    ..call(RelocationTarget::LibCall(LibCall::CeilF32))
+                },
+            ],
+            jt_offsets: SecondaryMap::new(),
+            frame_info: Default::default(),
+        };
+        let kinds = |relocation_model| {
+            let compilation = Compilation::new(
+                std::iter::once(function.clone()).collect(),
+                PrimaryMap::new(),
+                PrimaryMap::new(),
+                PrimaryMap::new(),
+                None,
+                None,
+            );
+            let mut obj = get_object_for_target(&triple).unwrap();
+            let options = EmitOptions {
+                record_relocations: true,
+                relocation_model,
+                ..EmitOptions::default()
+            };
+            emit_compilation_with_options(
+                &mut obj,
+                compilation,
+                &TestSymbolRegistry,
+                &triple,
+                &options,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|record| (record.kind, record.size, record.target, record.addend))
+            .collect::<Vec<_>>()
+        };
+        let ceil = LibCall::CeilF32.to_function_name();
+        assert_eq!(
+            kinds(RelocationModel::Pic),
+            vec![
+                (
+                    RelocationKind::PltRelative,
+                    32,
+                    "function_0".to_string(),
+                    -4
+                ),
+                (RelocationKind::PltRelative, 32, ceil.to_string(), -4),
+                (RelocationKind::GotRelative, 32, ceil.to_string(), -4),
+            ]
+        );
+        // The targets are only referenced by 64-bit absolute relocations,
+        // from their stubs, and the GOT load reads the address of the
+        // libcall from its stub.
+        let function_stub = "function_0$static_stub".to_string();
+        let ceil_stub = format!("{}$static_stub", ceil);
+        assert_eq!(
+            kinds(RelocationModel::Static),
+            vec![
+                (RelocationKind::Absolute, 64, "function_0".to_string(), 0),
+                (RelocationKind::Relative, 32, function_stub, -4),
+                (RelocationKind::Absolute, 64, ceil.to_string(), 0),
+                (RelocationKind::Relative, 32, ceil_stub.clone(), -4),
+                (RelocationKind::Relative, 32, ceil_stub, 2),
+            ]
+        );
+        // Only the architectures with a stub support the model.
+        let triple = Triple::from_str("i686-unknown-linux-gnu").unwrap();
+        let mut obj = get_object_for_target(&triple).unwrap();
+        assert!(matches!(
+            emit_compilation_with_options(
+                &mut obj,
+                Compilation::new(
+                    std::iter::once(function).collect(),
+                    PrimaryMap::new(),
+                    PrimaryMap::new(),
+                    PrimaryMap::new(),
+                    None,
+                    None,
+                ),
+                &TestSymbolRegistry,
+                &triple,
+                &EmitOptions {
+                    relocation_model: RelocationModel::Static,
+                    ..EmitOptions::default()
+                },
+            ),
+            Err(ObjectError::UnsupportedRelocation(_))
+        ));
+        assert_eq!(
+            RelocationModel::Static.linker_args(),
+            ["-static", "-no-pie"]
+        );
+    }
+
+    /// A symbol registry giving all the functions the same name.
+    struct CollidingSymbolRegistry;
+
+    impl SymbolRegistry for CollidingSymbolRegistry {
+        fn symbol_to_name(&self, _symbol: Symbol) -> String {
+            "wasmer_function".to_string()
+        }
+
+        fn name_to_symbol(&self, _name: &str) -> Option<Symbol> {
+            None
+        }
+    }
+
+    #[test]
+    fn duplicate_symbols() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let function = CompiledFunction {
+            body: FunctionBody {
+                body: vec![0xc3],
+                unwind_info: None,
+            },
+            relocations: vec![],
+            jt_offsets: SecondaryMap::new(),
+            frame_info: Default::default(),
+        };
+        let functions = vec![function.clone(), function]
+            .into_iter()
+            .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+        let compilation = Compilation::new(
+            functions,
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            None,
+            None,
+        );
+        let mut obj = get_object_for_target(&triple).unwrap();
+        let result = emit_compilation(&mut obj, compilation, &CollidingSymbolRegistry, &triple);
+        assert!(matches!(
+            result,
+            Err(ObjectError::DuplicateSymbol(name)) if name == "wasmer_function"
+        ));
+    }
+
+    #[test]
+    fn relocation_sizes_follow_target_pointer_width() {
+        let i686 = Triple::from_str("i686-unknown-linux-gnu").unwrap();
+        let x86_64 = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+
+        let obj = get_object_for_target(&i686).unwrap();
+        assert_eq!(obj.architecture(), object::Architecture::I386);
+
+        assert_eq!(
+            relocation_for_target(Reloc::Abs4, 0, &i686).unwrap(),
+            (RelocationKind::Absolute, RelocationEncoding::Generic, 32)
+        );
+        assert!(relocation_for_target(Reloc::Abs8, 0, &i686).is_err());
+        assert!(relocation_for_target(Reloc::ElfX86_64TlsGd, 0, &i686).is_err());
+        assert_eq!(
+            relocation_for_target(Reloc::Abs8, 0, &x86_64).unwrap(),
+            (RelocationKind::Absolute, RelocationEncoding::Generic, 64)
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let mut obj = get_object_for_target(&triple).unwrap();
+        emit_data(&mut obj, b"data", &[0; 16], 8).unwrap();
+        emit_data(&mut obj, b"other", &[0; 8], 8).unwrap();
+        emit_alias(&mut obj, b"alias", b"other").unwrap();
+
+        let other = obj.symbol(obj.symbol_id(b"other").unwrap());
+        let alias = obj.symbol(obj.symbol_id(b"alias").unwrap());
+        assert_eq!(alias.value, other.value);
+        assert_eq!(alias.size, other.size);
+        assert_eq!(alias.section, other.section);
+        assert_eq!(alias.scope, SymbolScope::Dynamic);
+
+        assert!(matches!(
+            emit_alias(&mut obj, b"alias", b"data"),
+            Err(ObjectError::DuplicateSymbol(name)) if name == "alias"
+        ));
+        assert!(matches!(
+            emit_alias(&mut obj, b"dangling", b"missing"),
+            Err(ObjectError::MissingSymbol(name)) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn call_wrappers() {
+        for (triple, size) in &[
+            ("x86_64-unknown-linux-gnu", 15),
+            ("x86_64-pc-windows-msvc", 15),
+            ("aarch64-unknown-linux-gnu", 16),
+            ("aarch64-apple-darwin", 16),
+        ] {
+            let triple = Triple::from_str(triple).unwrap();
+            let mut obj = get_object_for_target(&triple).unwrap();
+            emit_data(&mut obj, b"function", &[0; 8], 1).unwrap();
+            emit_data(&mut obj, b"trampoline", &[0; 8], 1).unwrap();
+            emit_call_wrapper(&mut obj, b"wrapper", b"function", b"trampoline", &triple).unwrap();
+
+            let wrapper = obj.symbol(obj.symbol_id(b"wrapper").unwrap());
+            assert_eq!(wrapper.kind, SymbolKind::Text);
+            assert_eq!(wrapper.scope, SymbolScope::Dynamic);
+            assert_eq!(wrapper.size, *size);
+            assert!(obj.write().is_ok());
+
+            assert!(matches!(
+                emit_call_wrapper(&mut obj, b"other", b"missing", b"trampoline", &triple),
+                Err(ObjectError::MissingSymbol(name)) if name == "missing"
+            ));
+        }
+
+        let i686 = Triple::from_str("i686-unknown-linux-gnu").unwrap();
+        let mut obj = get_object_for_target(&i686).unwrap();
+        emit_data(&mut obj, b"function", &[0; 8], 1).unwrap();
+        assert!(matches!(
+            emit_call_wrapper(&mut obj, b"wrapper", b"function", b"function", &i686),
+            Err(ObjectError::UnsupportedArchitecture(_))
+        ));
+    }
+}
@@ -7,7 +7,7 @@
 use crate::vmcontext::VMSharedSignatureIndex;
 use loupe::MemoryUsage;
 use more_asserts::{assert_lt, debug_assert_lt};
-use std::collections::{hash_map, HashMap};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::convert::TryFrom;
 use std::sync::RwLock;
 use wasmer_types::FunctionType;
@@ -43,6 +43,42 @@ impl SignatureRegistry {
     /// Register a signature and return its unique index.
     pub fn register(&self, sig: &FunctionType) -> VMSharedSignatureIndex {
         let mut inner = self.inner.write().unwrap();
+        Self::register_locked(&mut inner, sig)
+    }
+
+    /// Register several signatures at once and return their unique
+    /// indices, in the same order.
+    ///
+    /// The registry lock is held for the whole batch, so registrations
+    /// happening concurrently on other threads can't interleave with it.
+    /// The registration is transactional: either all the signatures are
+    /// registered, or none of them is and `None` is returned (which only
+    /// happens if the registry would run out of indices).
+    pub fn register_all<'a>(
+        &self,
+        sigs: impl IntoIterator<Item = &'a FunctionType>,
+    ) -> Option<Vec<VMSharedSignatureIndex>> {
+        let sigs = sigs.into_iter().collect::<Vec<_>>();
+        let mut inner = self.inner.write().unwrap();
+        let new_sigs = sigs
+            .iter()
+            .copied()
+            .filter(|sig| !inner.signature2index.contains_key(*sig))
+            .collect::<HashSet<_>>()
+            .len();
+        // `VMSharedSignatureIndex::new(std::u32::MAX)` is reserved for
+        // `VMSharedSignatureIndex::default()`.
+        if inner.signature2index.len() + new_sigs >= std::u32::MAX as usize {
+            return None;
+        }
+        Some(
+            sigs.into_iter()
+                .map(|sig| Self::register_locked(&mut inner, sig))
+                .collect(),
+        )
+    }
+
+    fn register_locked(inner: &mut Inner, sig: &FunctionType) -> VMSharedSignatureIndex {
         let len = inner.signature2index.len();
         match inner.signature2index.entry(sig.clone()) {
             hash_map::Entry::Occupied(entry) => *entry.get(),
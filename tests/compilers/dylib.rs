@@ -0,0 +1,2748 @@
+//! Tests specific to the `Dylib` engine.
+
+use anyhow::Result;
+use wasmer::*;
+use wasmer_engine_dylib::DylibArtifact;
+
+#[cfg(windows)]
+#[compiler_test(dylib)]
+fn drop_unloads_library_before_removing_file(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.dll");
+    module.serialize_to_file(&path)?;
+
+    let deserialized = unsafe { Module::deserialize_from_file(&store, &path)? };
+    drop(deserialized);
+
+    // Windows refuses to remove a `.dll` that is still loaded: the
+    // engine keeps it loaded as long as it lives, and only then can it be
+    // removed.
+    assert!(std::fs::remove_file(&path).is_err());
+    drop(module);
+    drop(store);
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn call_function_after_dropping_module(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let wat = r#"(module
+        (table (export "table") 1 funcref)
+        (elem (i32.const 0) $run)
+        (func $run (export "run") (result i32) (i32.const 42)))"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run = instance.exports.get_function("run")?.clone();
+    let table = instance.exports.get_table("table")?.clone();
+    drop(instance);
+    drop(module);
+
+    // The functions only keep the engine alive, which keeps the code
+    // loaded once the artifact is dropped.
+    assert_eq!(run.call(&[])?.to_vec(), vec![Val::I32(42)]);
+    let funcref = match table.get(0) {
+        Some(Val::FuncRef(Some(funcref))) => funcref,
+        _ => panic!("the table holds the function"),
+    };
+    assert_eq!(funcref.call(&[])?.to_vec(), vec![Val::I32(42)]);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn module_size_limit(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let wasm = wat2wasm(br#"(module (func (export "run")))"#)?;
+
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_max_module_bytes(wasm.len() - 1);
+    let store = Store::new(&engine);
+    assert!(matches!(
+        Module::new(&store, &wasm),
+        Err(CompileError::Resource(_))
+    ));
+
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_max_module_bytes(wasm.len());
+    let store = Store::new(&engine);
+    Module::new(&store, &wasm)?;
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn relocation_limit(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // Direct calls to local functions are relocated.
+    let wasm = wat2wasm(
+        br#"(module
+            (func $f (result i32) (i32.const 42))
+            (func (export "run") (result i32) (call $f)))"#,
+    )?;
+
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .max_relocations(0)
+        .engine();
+    let store = Store::new(&engine);
+    assert!(matches!(
+        Module::new(&store, &wasm),
+        Err(CompileError::Resource(_))
+    ));
+
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .max_relocations(1 << 16)
+        .engine();
+    let store = Store::new(&engine);
+    Module::new(&store, &wasm)?;
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn read_metadata_without_loading(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(&store, r#"(module $name (func (export "run")))"#)?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    module.serialize_to_file(&path)?;
+
+    let metadata = unsafe { DylibArtifact::read_metadata(&path)? };
+    assert_eq!(metadata.compile_info.module.name, Some("name".to_string()));
+    assert_eq!(metadata.prefix, artifact.metadata().prefix);
+    assert_eq!(
+        metadata.function_body_lengths,
+        artifact.metadata().function_body_lengths
+    );
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn read_metadata_from_bytes(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(&store, r#"(module $name (func (export "run")))"#)?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+
+    let metadata = unsafe { DylibArtifact::read_metadata_from_bytes(&module.serialize()?)? };
+    assert_eq!(metadata.compile_info.module.name, Some("name".to_string()));
+    assert_eq!(metadata.prefix, artifact.metadata().prefix);
+
+    assert!(matches!(
+        unsafe { DylibArtifact::read_metadata_from_bytes(b"not an artifact") },
+        Err(DeserializeError::CorruptedBinary(_))
+    ));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn read_metadata_from_range(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(&store, r#"(module $name (func (export "run")))"#)?;
+    let bytes = module.serialize()?;
+
+    let range = DylibArtifact::metadata_range(&bytes)?;
+    assert!(range.end <= bytes.len() as u64);
+    // Only the bytes in the range are needed.
+    let fetched = bytes[range.start as usize..range.end as usize].to_vec();
+    let metadata = unsafe { DylibArtifact::read_metadata_from_range(&fetched)? };
+    let full_metadata = unsafe { DylibArtifact::read_metadata_from_bytes(&bytes)? };
+    assert_eq!(metadata.compile_info.module.name, Some("name".to_string()));
+    assert_eq!(metadata.prefix, full_metadata.prefix);
+
+    assert!(DylibArtifact::metadata_range(b"not an artifact").is_err());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn deserialize_rejects_truncated_artifact(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+    let serialized_bytes = module.serialize()?;
+
+    // The metadata can't be found in the truncated object, so it must be
+    // rejected before being loaded.
+    let truncated = &serialized_bytes[..serialized_bytes.len() / 2];
+    assert!(unsafe { Module::deserialize(&store, truncated) }.is_err());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn compilation_metrics(config: crate::Config) -> Result<()> {
+    use wasmer_types::entity::EntityRef;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_compilation_metrics(2);
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func (export "small"))
+            (func (export "big") (result i32) (i32.add (i32.const 1) (i32.const 2)))
+            (func (export "other") (param i32) (result i32) (local.get 0)))"#,
+    )?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    let metrics = artifact.compilation_metrics().unwrap();
+    // With more batches than functions, each function is timed on its own.
+    assert_eq!(metrics.slowest_functions.len(), 2);
+    for (functions, _) in &metrics.slowest_functions {
+        assert_eq!(functions.end.index(), functions.start.index() + 1);
+    }
+    assert!(metrics.slowest_functions[0].1 >= metrics.slowest_functions[1].1);
+
+    // The functions are split in as many batches as requested.
+    engine.set_compilation_metrics(usize::MAX);
+    engine.set_compilation_metrics_batches(2);
+    let module = Module::new(&store, r#"(module (func) (func) (func))"#)?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    let batches = &artifact.compilation_metrics().unwrap().slowest_functions;
+    let mut ranges = batches
+        .iter()
+        .map(|(functions, _)| (functions.start.index(), functions.end.index()))
+        .collect::<Vec<_>>();
+    ranges.sort();
+    assert_eq!(ranges, vec![(0, 2), (2, 3)]);
+    if cfg!(unix) {
+        let linker_usage = metrics.linker_usage.unwrap();
+        assert!(linker_usage.max_rss_bytes > 0);
+    } else {
+        assert!(metrics.linker_usage.is_none());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn hardened_link_profile(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_link_profile(wasmer_engine_dylib::LinkProfile::Hardened);
+    let store = Store::new(&engine);
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    module.serialize_to_file(&path)?;
+
+    let output = std::process::Command::new("readelf")
+        .args(&["-lW", "-d"])
+        .arg(&path)
+        .output()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let gnu_stack = output
+        .lines()
+        .find(|line| line.contains("GNU_STACK"))
+        .expect("missing GNU_STACK segment");
+    assert!(!gnu_stack.contains("RWE"), "{}", gnu_stack);
+    assert!(output.contains("GNU_RELRO"), "{}", output);
+    assert!(output.contains("BIND_NOW"), "{}", output);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn bulk_memory_passive_data(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory 1)
+            (data $passive "\2a")
+            (func (export "init") (result i32)
+                (memory.init $passive (i32.const 8) (i32.const 0) (i32.const 1))
+                (data.drop $passive)
+                (i32.load8_u (i32.const 8))))"#,
+    )?;
+    let serialized_bytes = module.serialize()?;
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let init: NativeFunc<(), i32> = instance.exports.get_native_function("init")?;
+    assert_eq!(init.call()?, 42);
+    // The segment has been dropped, so initializing from it again traps.
+    assert!(init.call().is_err());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn reference_types_externref_table(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (table $t 1 externref)
+            (func (export "grow") (result i32)
+                (table.grow $t (ref.null extern) (i32.const 2))
+                (drop)
+                (table.size $t))
+            (func (export "is_null") (param i32) (result i32)
+                (ref.is_null (table.get $t (local.get 0)))))"#,
+    )?;
+    let serialized_bytes = module.serialize()?;
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let grow: NativeFunc<(), i32> = instance.exports.get_native_function("grow")?;
+    let is_null: NativeFunc<i32, i32> = instance.exports.get_native_function("is_null")?;
+    assert_eq!(grow.call()?, 3);
+    assert_eq!(is_null.call(2)?, 1);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn concurrent_deserialization_shares_signatures(config: crate::Config) -> Result<()> {
+    use std::sync::Arc;
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func (export "a") (param i32) (result i32) (local.get 0))
+            (func (export "b") (param i64 f32))
+            (func (export "c") (result f64) (f64.const 1)))"#,
+    )?;
+    let serialized_bytes = Arc::new(module.serialize()?);
+
+    let threads = (0..8)
+        .map(|_| {
+            let store = store.clone();
+            let serialized_bytes = serialized_bytes.clone();
+            std::thread::spawn(move || {
+                let module = unsafe { Module::deserialize(&store, &serialized_bytes) }.unwrap();
+                module
+                    .artifact()
+                    .signatures()
+                    .values()
+                    .copied()
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+    let expected = module
+        .artifact()
+        .signatures()
+        .values()
+        .copied()
+        .collect::<Vec<_>>();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), expected);
+    }
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn detect_target(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+    let serialized_bytes = module.serialize()?;
+
+    assert!(DylibArtifact::detect_target(&serialized_bytes).is_some());
+    assert!(DylibArtifact::detect_target(b"\0asm\x01\0\0\0").is_none());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn custom_metadata_symbol(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_metadata_symbol("CUSTOM_METADATA");
+    let store = Store::new(&engine);
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+    let serialized_bytes = module.serialize()?;
+
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), ()> = instance.exports.get_native_function("run")?;
+    run.call()?;
+
+    // An engine looking for the default symbol can't find the metadata.
+    let default_store = config.store();
+    assert!(unsafe { Module::deserialize(&default_store, &serialized_bytes) }.is_err());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn engine_counters(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+    let serialized_bytes = module.serialize()?;
+    let _module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+    assert!(unsafe { Module::deserialize(&store, &serialized_bytes[..16]) }.is_err());
+
+    let counters = engine.counters();
+    assert_eq!(counters.compiles, 1);
+    assert_eq!(counters.deserializations_succeeded, 1);
+    assert_eq!(counters.deserializations_failed, 1);
+    assert_eq!(counters.link_failures, 0);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn export_required_symbols_only(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_export_required_symbols_only(true);
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "f" (func $f))
+            (func (export "run") (call $f)))"#,
+    )?;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    module.serialize_to_file(&path)?;
+
+    let output = std::process::Command::new("readelf")
+        .args(&["--dyn-syms", "-W"])
+        .arg(&path)
+        .output()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let exported = output
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| fields.len() >= 8 && fields[4] != "LOCAL" && fields[6] != "UND")
+        .map(|fields| fields[7].to_string())
+        .collect::<Vec<_>>();
+    assert!(exported.iter().any(|name| name == "WASMER_METADATA"));
+    for name in &exported {
+        assert!(
+            name == "WASMER_METADATA"
+                || name.starts_with("wasmer_function_")
+                || name.starts_with("wasmer_trampoline_"),
+            "unexpected exported symbol `{}`",
+            name
+        );
+    }
+
+    // The artifact can still be loaded.
+    let module = unsafe { Module::deserialize_from_file(&store, &path)? };
+    let imports = imports! {
+        "env" => {
+            "f" => Function::new_native(&store, || {}),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+    let run: NativeFunc<(), ()> = instance.exports.get_native_function("run")?;
+    run.call()?;
+    Ok(())
+}
+
+#[cfg(feature = "test-dylib")]
+#[compiler_test(dylib)]
+fn compile_wat_helper(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let artifact = wasmer_engine_dylib::testing::compile_wat(
+        &engine,
+        r#"(module (func (export "a")) (func (export "b")))"#,
+        &tunables,
+    )?;
+    assert_eq!(artifact.finished_functions().len(), 2);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn deferred_signature_registration(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .defer_signature_registration(true)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module (func (export "id") (param i32) (result i32) (local.get 0)))"#,
+    )?;
+    let signature = FunctionType::new(vec![Type::I32], vec![Type::I32]);
+    let instance = Instance::new(&module, &imports! {})?;
+    let id: NativeFunc<i32, i32> = instance.exports.get_native_function("id")?;
+    assert_eq!(id.call(42)?, 42);
+
+    // Once instantiated, the signatures map to the engine registry.
+    let signatures = module.artifact().signatures();
+    assert_eq!(signatures.len(), 1);
+    let index = *signatures.values().next().unwrap();
+    assert_eq!(engine.lookup_signature(index), Some(signature));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn relocation_records(config: crate::Config) -> Result<()> {
+    use wasmer_types::entity::EntityRef;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .record_relocations(true)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func $callee (result i32) (i32.const 1))
+            (func (export "run") (result i32) (call $callee)))"#,
+    )?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    // Compilers emitting the object file themselves don't record anything.
+    if let Some(records) = artifact.relocation_records() {
+        let callee = wasmer_engine_dylib::function_symbol_name("", LocalFunctionIndex::new(0));
+        assert!(records.iter().any(|record| record.target == callee));
+    }
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn resolve_address(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func (export "a") (result i32) (i32.const 1))
+            (func (export "b") (result i32) (i32.const 2)))"#,
+    )?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    for (index, function) in artifact.finished_functions().iter() {
+        let start = function.0 as *const u8;
+        assert_eq!(artifact.resolve_address(start), Some((index, 0)));
+        assert_eq!(
+            artifact.resolve_address(start.wrapping_add(1)),
+            Some((index, 1))
+        );
+    }
+    assert!(artifact.resolve_address(std::ptr::null()).is_none());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn function_ranges(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func (export "a") (result i32) (i32.const 1))
+            (func (export "b") (result i32) (i32.const 2)))"#,
+    )?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    let ranges = artifact.function_ranges();
+    assert_eq!(ranges.len(), 2);
+    for (index, start, length) in ranges {
+        assert!(length > 0);
+        assert_eq!(
+            artifact.resolve_address(start.wrapping_add(length - 1)),
+            Some((index, length - 1))
+        );
+    }
+
+    // The artifacts that are not loaded have no function ranges.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .object_only(true)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run")))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert!(artifact.function_ranges().is_empty());
+    Ok(())
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[compiler_test(dylib)]
+fn cross_compile_i686(config: crate::Config) -> Result<()> {
+    use std::str::FromStr;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // Only LLVM supports compiling to 32-bit x86.
+    if config.compiler != crate::Compiler::LLVM {
+        return Ok(());
+    }
+    let triple = Triple::from_str("i686-unknown-linux-gnu").unwrap();
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .target(Target::new(triple, CpuFeature::set()))
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory 1)
+            (global $g (mut i64) (i64.const 0))
+            (func (export "run") (param i64) (result i64)
+                (global.set $g (local.get 0))
+                (i64.load (i32.const 0))))"#,
+    )?;
+    let bytes = module.serialize()?;
+    // A 32-bit (`ELFCLASS32`) ELF object for `EM_386`.
+    assert!(bytes.starts_with(&[0x7f, b'E', b'L', b'F', 1]));
+    assert_eq!(&bytes[18..20], &[3, 0]);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn data_initializers_symbol(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .data_initializers_symbol("WASMER_DATA_INITIALIZERS")
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 16) "hello")
+            (data (i32.const 32) "world"))"#,
+    )?;
+    let serialized_bytes = module.serialize()?;
+
+    let data_initializers =
+        DylibArtifact::read_data_initializers(&serialized_bytes, "WASMER_DATA_INITIALIZERS")?;
+    let segments = data_initializers
+        .iter()
+        .map(|initializer| (initializer.location.offset, &*initializer.data))
+        .collect::<Vec<_>>();
+    assert_eq!(segments, vec![(16, &b"hello"[..]), (32, &b"world"[..])]);
+
+    // The module still loads from its metadata.
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+    assert_eq!(&unsafe { memory.data_unchecked() }[32..37], b"world");
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn cancellable_compilation(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+    use wasmer_engine_dylib::CancellationToken;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+
+    let cancellation = CancellationToken::new();
+    let artifact = DylibArtifact::new_cancellable(&engine, &wasm, &tunables, &cancellation)?;
+    let store = Store::new(&engine);
+    let module = unsafe { Module::deserialize(&store, &artifact.serialize()?)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    cancellation.cancel();
+    assert!(matches!(
+        DylibArtifact::new_cancellable(&engine, &wasm, &tunables, &cancellation),
+        Err(CompileError::Cancelled)
+    ));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn object_writer(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+
+    let mut object = vec![];
+    let artifact = DylibArtifact::new_with_object_writer(&engine, &wasm, &tunables, &mut object)?;
+    assert!(DylibArtifact::detect_target(&object).is_some());
+    assert!(artifact.compile_info().module.exports.contains_key("run"));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn imported_memory_and_table(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "memory" (memory 1))
+            (import "env" "table" (table 1 funcref))
+            (type $get (func (result i32)))
+            (data (i32.const 8) "imported")
+            (func $answer (result i32) (i32.const 42))
+            (elem (i32.const 0) $answer)
+            (func (export "load") (result i32) (i32.load8_u (i32.const 8)))
+            (func (export "call") (result i32) (call_indirect (type $get) (i32.const 0))))"#,
+    )?;
+    let serialized_bytes = module.serialize()?;
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let table = Table::new(
+        &store,
+        TableType::new(ValType::FuncRef, 1, None),
+        Val::FuncRef(None),
+    )?;
+    let imports = imports! {
+        "env" => {
+            "memory" => memory.clone(),
+            "table" => table.clone(),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+
+    assert_eq!(&unsafe { memory.data_unchecked() }[8..16], b"imported");
+    let load: NativeFunc<(), i32> = instance.exports.get_native_function("load")?;
+    assert_eq!(load.call()?, i32::from(b'i'));
+    let call: NativeFunc<(), i32> = instance.exports.get_native_function("call")?;
+    assert_eq!(call.call()?, 42);
+    assert!(matches!(table.get(0), Some(Val::FuncRef(Some(_)))));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn debug_link_profile(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .link_profile(wasmer_engine_dylib::LinkProfile::Debug)
+        .export_required_symbols_only(true)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module (func (export "run") (param i32) (result i32) (i32.add (local.get 0) (i32.const 1))))"#,
+    )?;
+    let serialized_bytes = module.serialize()?;
+
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<i32, i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call(41)?, 42);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn serialize_to_file_is_atomic(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(&store, r#"(module (func (export "run")))"#)?;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    std::fs::write(&path, b"stale")?;
+    let threads = (0..4)
+        .map(|_| {
+            let module = module.clone();
+            let path = path.clone();
+            std::thread::spawn(move || module.serialize_to_file(&path))
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+
+    // Only the artifact is left in the directory, with no temporary file.
+    let entries = std::fs::read_dir(dir.path())?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(std::fs::read(&path)?, module.serialize()?);
+    unsafe { Module::deserialize_from_file(&store, &path)? };
+    Ok(())
+}
+
+#[cfg(target_arch = "x86_64")]
+#[compiler_test(dylib)]
+fn cpu_features(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let wat = r#"(module (func (export "run") (result i32) (i32.const 42)))"#;
+
+    // The host features can be used.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .cpu_features(CpuFeature::for_host())
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, wat)?;
+    let serialized_bytes = module.serialize()?;
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    // Code compiled for features missing on the host is refused.
+    let all_features = !CpuFeature::set();
+    if (all_features - CpuFeature::for_host()).is_empty() {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .cpu_features(all_features)
+        .engine();
+    let store = Store::new(&engine);
+    let serialized_bytes = Module::new(&store, wat)?.serialize()?;
+    assert!(matches!(
+        unsafe { Module::deserialize(&store, &serialized_bytes) },
+        Err(DeserializeError::Incompatible(_))
+    ));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn code_size(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "f" (func $f (param i32)))
+            (func (export "run") (param i32) (local.get 0) (call $f)))"#,
+    )?;
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    let function_body_lengths = &artifact.metadata().function_body_lengths;
+    assert!(function_body_lengths.values().all(|length| *length > 0));
+    assert!(artifact.code_size() > function_body_lengths.values().sum::<u64>());
+
+    let serialized_bytes = module.serialize()?;
+    let deserialized = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+    let deserialized_artifact: &DylibArtifact =
+        deserialized.artifact().as_ref().downcast_ref().unwrap();
+    assert_eq!(deserialized_artifact.code_size(), artifact.code_size());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[compiler_test(dylib)]
+fn deserialize_from_fd(config: crate::Config) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    artifact.serialize_to_file(&path)?;
+    let file = std::fs::File::open(&path)?;
+    let deserialized = unsafe { DylibArtifact::deserialize_from_fd(&engine, file.as_raw_fd())? };
+    assert_eq!(
+        deserialized.finished_functions().len(),
+        artifact.finished_functions().len()
+    );
+    // The file descriptor is still usable.
+    assert_eq!(file.metadata()?.len(), std::fs::metadata(&path)?.len());
+    drop(deserialized);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn object_only(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .object_only(true)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+
+    let object_path = artifact.path().to_path_buf();
+    assert_eq!(object_path.extension().unwrap(), "o");
+    assert!(artifact.finished_functions().is_empty());
+    assert_eq!(artifact.module_ref().functions.len(), 1);
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.o");
+    artifact.serialize_to_file(&path)?;
+    assert_eq!(std::fs::read(&path)?, std::fs::read(&object_path)?);
+
+    drop(artifact);
+    assert!(!object_path.exists());
+    assert!(path.exists());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn large_custom_section_threshold(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // Emit every custom section in its own object section.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .large_custom_section_threshold(0)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func (export "run") (param i32) (result f64)
+                (block (block (block
+                    (br_table 0 1 2 (local.get 0)))
+                    (return (f64.const 1.5)))
+                    (return (f64.const 2.5)))
+                (f64.const 3.5)))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<i32, f64> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call(0)?, 1.5);
+    assert_eq!(run.call(1)?, 2.5);
+    assert_eq!(run.call(2)?, 3.5);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn module_validator(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .module_validator(|module, _data| {
+            if module.functions.len() > 1 {
+                return Err(CompileError::Validate("too many functions".to_string()));
+            }
+            Ok(())
+        })
+        .engine();
+    let store = Store::new(&engine);
+    Module::new(&store, r#"(module (func (export "run")))"#)?;
+    let result = Module::new(
+        &store,
+        r#"(module (func (export "a")) (func (export "b")))"#,
+    );
+    assert!(matches!(
+        result,
+        Err(CompileError::Validate(message)) if message == "too many functions"
+    ));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn concurrent_instantiation(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // The signatures are registered lazily by the first instantiation,
+    // which the threads race for.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .defer_signature_registration(true)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (type $t (func (param i32) (result i32)))
+            (table 1 funcref)
+            (elem (i32.const 0) $double)
+            (func $double (type $t) (i32.mul (local.get 0) (i32.const 2)))
+            (func (export "run") (param i32) (result i32)
+                (call_indirect (type $t) (local.get 0) (i32.const 0))))"#,
+    )?;
+    let serialized_bytes = module.serialize()?;
+    let module = unsafe { Module::deserialize(&store, &serialized_bytes)? };
+
+    let threads = (0..8)
+        .map(|thread| {
+            let module = module.clone();
+            std::thread::spawn(move || -> Result<()> {
+                for i in 0..50 {
+                    let instance = Instance::new(&module, &imports! {})?;
+                    let run: NativeFunc<i32, i32> = instance.exports.get_native_function("run")?;
+                    let value = thread * 100 + i;
+                    assert_eq!(run.call(value)?, value * 2);
+                }
+                Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn same_module(config: crate::Config) -> Result<()> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_deterministic_prefixer(|bytes| {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+        format!("{:x}", hasher.finish())
+    });
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 1)))"#)?;
+    let other_wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 2)))"#)?;
+
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let same = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let other = DylibArtifact::new(&engine, &other_wasm, &tunables)?;
+    assert!(artifact.same_module(&same));
+    assert!(!artifact.same_module(&other));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn elf_soname(config: crate::Config) -> Result<()> {
+    use std::process::Command;
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // `readelf` is needed to read the soname.
+    if Command::new("readelf").arg("--version").output().is_err() {
+        return Ok(());
+    }
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run")))"#)?;
+    let dir = tempfile::tempdir()?;
+    let soname = |engine: &wasmer_engine_dylib::DylibEngine| -> Result<Option<String>> {
+        let artifact = DylibArtifact::new(engine, &wasm, &tunables)?;
+        let path = dir.path().join("module.so");
+        artifact.serialize_to_file(&path)?;
+        let output = Command::new("readelf").arg("-d").arg(&path).output()?;
+        Ok(String::from_utf8(output.stdout)?
+            .lines()
+            .find_map(|line| line.split("Library soname: [").nth(1))
+            .map(|soname| soname.trim_end_matches(']').to_string()))
+    };
+
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    engine.set_deterministic_prefixer(|_| "abc".to_string());
+    assert_eq!(soname(&engine)?.as_deref(), Some("libwasmer_abc.so"));
+
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .elf_soname("libcustom.so")
+        .engine();
+    assert_eq!(soname(&engine)?.as_deref(), Some("libcustom.so"));
+
+    // Arguments this long are passed to the linker in a response file.
+    let long_soname = format!("lib{}.so", "x".repeat(16 * 1024));
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .elf_soname(long_soname.as_str())
+        .engine();
+    assert_eq!(soname(&engine)?, Some(long_soname));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn has_frame_info(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert!(!artifact.has_frame_info());
+    artifact.register_frame_info();
+    // Singlepass doesn't emit unwind info.
+    if config.compiler != crate::Compiler::Singlepass {
+        assert!(artifact.has_frame_info());
+    }
+
+    // The sections of the artifacts loaded unchecked are not looked for.
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    artifact.serialize_to_file(&path)?;
+    let unchecked = unsafe { DylibArtifact::deserialize_from_file_unchecked(&engine, &path)? };
+    unchecked.register_frame_info();
+    assert!(!unchecked.has_frame_info());
+
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .object_only(true)
+        .engine();
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    artifact.register_frame_info();
+    assert!(!artifact.has_frame_info());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn metadata_bytes(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(
+        br#"(module
+            (memory 1)
+            (data (i32.const 0) "hello")
+            (func (export "run") (result i32) (i32.const 42)))"#,
+    )?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let metadata_bytes = artifact.metadata_bytes()?;
+
+    // The metadata is embedded as is in the shared object.
+    let shared_object = std::fs::read(artifact.path())?;
+    assert!(shared_object
+        .windows(metadata_bytes.len())
+        .any(|window| window == metadata_bytes.as_slice()));
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    artifact.serialize_to_file(&path)?;
+    let deserialized = unsafe { DylibArtifact::deserialize_from_file(&engine, &path)? };
+    assert_eq!(deserialized.metadata_bytes()?, metadata_bytes);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn dynamic_trampolines_without_imports(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert!(artifact.finished_dynamic_function_trampolines().is_empty());
+
+    let wasm = wat2wasm(
+        br#"(module
+            (import "env" "f" (func $f (result i32)))
+            (func (export "run") (result i32) (call $f)))"#,
+    )?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert_eq!(artifact.finished_dynamic_function_trampolines().len(), 1);
+
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module (func (export "run") (result i32) (i32.const 42)))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn portable_container(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+
+    let bytes = artifact.serialize_portable()?;
+    assert!(DylibArtifact::is_deserializable(&bytes));
+    let deserialized = unsafe { DylibArtifact::deserialize_portable(&engine, &bytes)? };
+    assert!(deserialized.same_module(&artifact));
+    let deserialized = unsafe { DylibArtifact::deserialize(&engine, &bytes)? };
+    assert!(deserialized.same_module(&artifact));
+
+    // The container is the magic header, the target triple (with its
+    // length) and the shared object.
+    let object_bytes = artifact.serialize()?;
+    let host_triple = Triple::host().to_string();
+    let (header, contents) = bytes.split_at(bytes.len() - object_bytes.len());
+    assert_eq!(contents, object_bytes.as_slice());
+    assert!(header.ends_with(host_triple.as_bytes()));
+
+    // A container for another target is rejected before being loaded.
+    let magic = &header[..header.len() - host_triple.len() - 4];
+    let other_triple = b"wasm32-unknown-unknown";
+    let mut other_bytes = magic.to_vec();
+    other_bytes.extend_from_slice(&(other_triple.len() as u32).to_le_bytes());
+    other_bytes.extend_from_slice(other_triple);
+    other_bytes.extend_from_slice(&object_bytes);
+    assert!(!DylibArtifact::is_deserializable(&other_bytes));
+    let result = unsafe { DylibArtifact::deserialize_portable(&engine, &other_bytes) };
+    assert!(matches!(result, Err(DeserializeError::Incompatible(_))));
+
+    // Nested containers are rejected, however deep.
+    let mut nested = header.repeat(100_000);
+    nested.extend_from_slice(&bytes);
+    assert!(!DylibArtifact::is_deserializable(&nested));
+    let result = unsafe { DylibArtifact::deserialize(&engine, &nested) };
+    assert!(matches!(result, Err(DeserializeError::CorruptedBinary(_))));
+
+    // Bare shared objects are not portable containers.
+    let result = unsafe { DylibArtifact::deserialize_portable(&engine, &object_bytes) };
+    assert!(matches!(result, Err(DeserializeError::Incompatible(_))));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn export_module_functions_only(config: crate::Config) -> Result<()> {
+    use std::process::Command;
+    use wasmer_engine::Artifact;
+    use wasmer_engine_dylib::function_symbol_name;
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::LocalFunctionIndex;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .export_module_functions_only(true)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(
+        br#"(module
+            (func $internal (result i32) (i32.const 42))
+            (func (export "run") (result i32) (call $internal)))"#,
+    )?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    artifact.serialize_to_file(&path)?;
+
+    // Only the exported function is in the dynamic symbol table.
+    if Command::new("readelf").arg("--version").output().is_ok() {
+        let output = Command::new("readelf")
+            .arg("--dyn-syms")
+            .arg("--wide")
+            .arg(&path)
+            .output()?;
+        let dynamic_symbols = String::from_utf8(output.stdout)?;
+        let internal = function_symbol_name("", LocalFunctionIndex::new(0));
+        let exported = function_symbol_name("", LocalFunctionIndex::new(1));
+        assert!(!dynamic_symbols
+            .split_whitespace()
+            .any(|symbol| symbol == internal));
+        assert!(dynamic_symbols
+            .split_whitespace()
+            .any(|symbol| symbol == exported));
+    }
+
+    // The internal function is still found when loading the artifact.
+    let store = Store::new(&engine);
+    let module = unsafe { Module::deserialize_from_file(&store, &path)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn serialize_then_drop(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    let bytes = artifact.serialize()?;
+    artifact.serialize_to_file(&path)?;
+
+    // The temporary file of the artifact is removed once the engine
+    // unloads it.
+    let temporary_path = artifact.path().to_path_buf();
+    drop(artifact);
+    assert!(temporary_path.exists());
+
+    for artifact in vec![
+        unsafe { DylibArtifact::deserialize(&engine, &bytes)? },
+        unsafe { DylibArtifact::deserialize_from_file(&engine, &path)? },
+    ] {
+        assert_eq!(artifact.finished_functions().len(), 1);
+    }
+    drop(engine);
+    assert!(!temporary_path.exists());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn shared_signature(config: crate::Config) -> Result<()> {
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::SignatureIndex;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm =
+        wat2wasm(br#"(module (func (export "id") (param i32) (result i32) (local.get 0)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+
+    let index = artifact
+        .shared_signature(SignatureIndex::new(0))
+        .expect("the signature is registered");
+    assert_eq!(
+        engine.lookup_signature(index),
+        Some(FunctionType::new(vec![Type::I32], vec![Type::I32]))
+    );
+    assert_eq!(artifact.shared_signature(SignatureIndex::new(1)), None);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn cleanup_stale_temp_files(config: crate::Config) -> Result<()> {
+    use std::process::Command;
+    use std::time::Duration;
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let dir = tempfile::tempdir()?;
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .load_fallback_dir(dir.path())
+        .engine();
+    let make_stale = |path: &std::path::Path| -> Result<()> {
+        let status = Command::new("touch")
+            .arg("-d")
+            .arg("2000-01-01")
+            .arg(path)
+            .status()?;
+        assert!(status.success());
+        Ok(())
+    };
+
+    let stale = dir.path().join("wasmer_dylib_stale.so");
+    std::fs::write(&stale, b"stale")?;
+    make_stale(&stale)?;
+    let fresh = dir.path().join("wasmer_dylib_fresh.so");
+    std::fs::write(&fresh, b"fresh")?;
+    let unrelated = dir.path().join("unrelated.so");
+    std::fs::write(&unrelated, b"unrelated")?;
+    make_stale(&unrelated)?;
+
+    // A stale file that is still loaded is kept.
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run")))"#)?;
+    let loaded = dir.path().join("wasmer_dylib_loaded.so");
+    DylibArtifact::new(&engine, &wasm, &tunables)?.serialize_to_file(&loaded)?;
+    let artifact = unsafe { DylibArtifact::deserialize_from_file(&engine, &loaded)? };
+    make_stale(&loaded)?;
+
+    engine.cleanup_stale_temp_files(Duration::from_secs(24 * 60 * 60))?;
+    assert!(!stale.exists());
+    assert!(fresh.exists());
+    assert!(unrelated.exists());
+    assert!(loaded.exists());
+    drop(artifact);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn malformed_module_error(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+
+    // A truncated module is malformed WebAssembly, not a failure of the
+    // compiler.
+    let truncated = &wasm[..wasm.len() - 1];
+    assert!(matches!(
+        DylibArtifact::new(&engine, truncated, &tunables),
+        Err(CompileError::Wasm(_))
+    ));
+    DylibArtifact::new(&engine, &wasm, &tunables)?;
+    Ok(())
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[compiler_test(dylib)]
+fn musl_target(config: crate::Config) -> Result<()> {
+    use std::process::Command;
+    use std::str::FromStr;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let triple = Triple::from_str("x86_64-unknown-linux-musl").unwrap();
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .target(Target::new(triple, CpuFeature::set()))
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module (func (export "run") (result i32) (i32.const 42)))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    // The shared object doesn't depend on the libc of the host.
+    if Command::new("readelf").arg("--version").output().is_ok() {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("module.so");
+        module.serialize_to_file(&path)?;
+        let output = Command::new("readelf").arg("-d").arg(&path).output()?;
+        assert!(!String::from_utf8(output.stdout)?.contains("(NEEDED)"));
+    }
+    Ok(())
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+#[compiler_test(dylib)]
+fn static_relocation_model(config: crate::Config) -> Result<()> {
+    use std::process::Command;
+    use wasmer_engine_dylib::{function_symbol_name, RelocationModel, RelocationRecord};
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::LocalFunctionIndex;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    if Command::new("cc").arg("--version").output().is_err() {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .relocation_model(RelocationModel::Static)
+        .record_relocations(true)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    // Besides calls between local functions, the other functions reference
+    // libcalls and custom sections (e.g. the trampolines of the imported
+    // functions), depending on the compiler.
+    let wasm = wat2wasm(
+        br#"(module
+            (import "env" "imported" (func $imported (param i32)))
+            (memory 1)
+            (table 1 funcref)
+            (elem (i32.const 0) $run)
+            (func $run (export "run") (result i32) (i32.const 42))
+            (func (export "call") (result i32) (call $run))
+            (func (export "call_indirect") (result i32)
+                (call_indirect (result i32) (i32.const 0)))
+            (func (export "call_imported") (param i32) (call $imported (local.get 0)))
+            (func (export "grow") (param i32) (result i32) (memory.grow (local.get 0)))
+            (func (export "ceil") (param f32) (result f32) (f32.ceil (local.get 0))))"#,
+    )?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert_eq!(artifact.path().extension().unwrap(), "o");
+
+    let prefix = &artifact.metadata().prefix;
+    let functions = (0..6)
+        .map(|index| function_symbol_name(prefix, LocalFunctionIndex::new(index)))
+        .collect::<Vec<_>>();
+    // Compilers emitting the object file themselves don't record anything.
+    if let Some(records) = artifact.relocation_records() {
+        // Nothing goes through the PLT or the GOT, and the functions are
+        // only referenced by 64-bit absolute relocations (from the stubs
+        // of the calls, if any).
+        let kind = |record: &RelocationRecord| format!("{:?}", record.kind);
+        assert!(records
+            .iter()
+            .all(|record| kind(record) != "PltRelative" && kind(record) != "GotRelative"));
+        assert!(records
+            .iter()
+            .filter(|record| functions.contains(&record.target))
+            .all(|record| kind(record) == "Absolute" && record.size == 64));
+        // So are the libcalls and custom sections.
+        assert!(records.iter().any(|record| {
+            kind(record) == "Absolute" && record.size == 64 && !functions.contains(&record.target)
+        }));
+    }
+
+    // Link the object into a static executable calling the first function
+    // (which doesn't use its `vmctx`). The libcalls are never called, but
+    // must be defined.
+    let dir = tempfile::tempdir()?;
+    let mut source = String::new();
+    if let Ok(output) = Command::new("nm").arg("-u").arg(artifact.path()).output() {
+        for line in String::from_utf8(output.stdout)?.lines() {
+            if let Some(symbol) = line.split_whitespace().last() {
+                source.push_str(&format!("void {}(void) {{}}\n", symbol));
+            }
+        }
+    }
+    source.push_str(&format!(
+        "extern int {0}(void *vmctx);\nint main(void) {{ return {0}(0) == 42 ? 0 : 1; }}\n",
+        functions[0]
+    ));
+    let main = dir.path().join("main.c");
+    std::fs::write(&main, source)?;
+    let executable = dir.path().join("main");
+    let status = Command::new("cc")
+        .arg(&main)
+        .arg(artifact.path())
+        .arg("-o")
+        .arg(&executable)
+        .args(RelocationModel::Static.linker_args())
+        .status()?;
+    assert!(status.success());
+    assert!(Command::new(&executable).status()?.success());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn target_triple(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert_eq!(artifact.target_triple(), Target::default().triple());
+
+    let bytes = artifact.serialize()?;
+    let deserialized = unsafe { DylibArtifact::deserialize(&engine, &bytes)? };
+    assert_eq!(deserialized.target_triple(), artifact.target_triple());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn text_alignment(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .text_alignment(4096)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(
+        br#"(module
+            (func (export "a") (result i32) (i32.const 1))
+            (func (export "b") (result i32) (i32.const 2)))"#,
+    )?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    for function in artifact.finished_functions().values() {
+        assert_eq!(function.0 as usize % 4096, 0);
+    }
+
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .text_alignment(24)
+        .engine();
+    assert!(DylibArtifact::new(&engine, &wasm, &tunables).is_err());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn many_symbols(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // Enough symbols for them to be read from the symbol table at once.
+    let mut wat = String::from("(module\n");
+    for index in 0..300 {
+        wat.push_str(&format!(
+            "(func (export \"f{}\") (result i32) (i32.const {}))\n",
+            index, index
+        ));
+    }
+    wat.push(')');
+    let store = config.store();
+    let module = Module::new(&store, &wat)?;
+    let bytes = module.serialize()?;
+    let deserialized = unsafe { Module::deserialize(&store, &bytes)? };
+    for module in &[module, deserialized] {
+        let instance = Instance::new(module, &imports! {})?;
+        for index in &[0, 150, 299] {
+            let f: NativeFunc<(), i32> = instance
+                .exports
+                .get_native_function(&format!("f{}", index))?;
+            assert_eq!(f.call()?, *index);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn build_id(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .build_id(true)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let build_id = artifact
+        .build_id()
+        .expect("the shared object has no build-id");
+    assert!(!build_id.is_empty());
+
+    let bytes = artifact.serialize()?;
+    let deserialized = unsafe { DylibArtifact::deserialize(&engine, &bytes)? };
+    assert_eq!(deserialized.build_id(), Some(build_id));
+    Ok(())
+}
+
+#[cfg(target_arch = "x86_64")]
+#[compiler_test(dylib)]
+fn replace_function(config: crate::Config) -> Result<()> {
+    use wasmer_compiler::FunctionBody;
+    use wasmer_engine::Artifact;
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::LocalFunctionIndex;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // `mov eax, 2; ret`
+    let body = || FunctionBody {
+        body: vec![0xb8, 2, 0, 0, 0, 0xc3],
+        unwind_info: None,
+    };
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .incremental_linking(true)
+        .engine();
+    let store = Store::new(&engine);
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 1)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let patched = artifact.replace_function(&engine, LocalFunctionIndex::new(0), body(), vec![])?;
+
+    let module = unsafe { Module::deserialize(&store, &patched.serialize()?)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 2);
+    assert!(artifact
+        .replace_function(&engine, LocalFunctionIndex::new(1), body(), vec![])
+        .is_err());
+
+    // The compiled code is not kept by default.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert!(artifact
+        .replace_function(&engine, LocalFunctionIndex::new(0), body(), vec![])
+        .is_err());
+    Ok(())
+}
+
+#[cfg(target_arch = "x86_64")]
+#[compiler_test(dylib)]
+fn verify_relocations(config: crate::Config) -> Result<()> {
+    use wasmer_compiler::{FunctionBody, Relocation, RelocationKind, RelocationTarget};
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::LocalFunctionIndex;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // `mov eax, 2; ret`, with a relocation past its end.
+    let body = FunctionBody {
+        body: vec![0xb8, 2, 0, 0, 0, 0xc3],
+        unwind_info: None,
+    };
+    let relocation = Relocation {
+        kind: RelocationKind::X86PCRel4,
+        reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::new(0)),
+        offset: 4,
+        addend: -4,
+    };
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .incremental_linking(true)
+        .verify_relocations(true)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 1)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let error = artifact
+        .replace_function(&engine, LocalFunctionIndex::new(0), body, vec![relocation])
+        .unwrap_err();
+    assert!(error.to_string().contains("Relocation out of bounds"));
+    Ok(())
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[compiler_test(dylib)]
+fn c_abi_exports(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+    use wasmer_types::FunctionIndex;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .c_abi_export_prefix("test_")
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(
+        br#"(module
+            (import "env" "f" (func $f))
+            (export "imported" (func $f))
+            (func (export "add-one") (param i32) (result i32)
+                (i32.add (local.get 0) (i32.const 1))))"#,
+    )?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    // Only the functions defined by the module get a wrapper.
+    assert_eq!(artifact.c_abi_exports().len(), 1);
+    let export = &artifact.c_abi_exports()[0];
+    assert_eq!(export.name, "add-one");
+    assert_eq!(export.function, FunctionIndex::from_u32(1));
+    assert_eq!(export.symbol, "test_add_one");
+
+    let bytes = artifact.serialize()?;
+    assert!(bytes
+        .windows(export.symbol.len())
+        .any(|window| window == export.symbol.as_bytes()));
+    let deserialized = unsafe { DylibArtifact::deserialize(&engine, &bytes)? };
+    assert_eq!(deserialized.c_abi_exports(), artifact.c_abi_exports());
+
+    // No wrappers by default.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert!(artifact.c_abi_exports().is_empty());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn validate_instantiable(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(
+        br#"(module
+            (import "env" "f" (func (param i32)))
+            (func (export "run") (result i32) (i32.const 42)))"#,
+    )?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    artifact.validate_instantiable()?;
+
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .object_only(true)
+        .engine();
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert!(matches!(
+        artifact.validate_instantiable(),
+        Err(InstantiationError::Link(LinkError::Trap(_)))
+    ));
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn symbol_naming(config: crate::Config) -> Result<()> {
+    use wasmer_engine_dylib::SymbolNaming;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let wat = r#"(module
+        (import "env" "f" (func $f (result i32)))
+        (func (export "run") (result i32) (call $f)))"#;
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .symbol_naming(SymbolNaming {
+            prefix: "module".to_string(),
+            separator: ".".to_string(),
+        })?
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, wat)?;
+    let serialized = module.serialize()?;
+
+    // A scheme whose symbols can't be named is refused.
+    let invalid = SymbolNaming {
+        prefix: "module".to_string(),
+        separator: String::new(),
+    };
+    assert!(
+        wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+            .symbol_naming(invalid.clone())
+            .is_err()
+    );
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    assert!(engine.set_symbol_naming(invalid).is_err());
+    let invalid = SymbolNaming {
+        prefix: "my module".to_string(),
+        separator: "_".to_string(),
+    };
+    assert!(engine.set_symbol_naming(invalid).is_err());
+
+    // The scheme is read back from the metadata by any engine.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let store = Store::new(&engine);
+    let module = unsafe { Module::deserialize(&store, &serialized)? };
+    let imports = imports! {
+        "env" => { "f" => Function::new_native(&store, || 42) },
+    };
+    let instance = Instance::new(&module, &imports)?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn split_debug_info(config: crate::Config) -> Result<()> {
+    use std::process::Command;
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // `objcopy` is needed to split the debug info.
+    if Command::new("objcopy").arg("--version").output().is_err() {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .link_profile(wasmer_engine_dylib::LinkProfile::Debug)
+        .split_debug_info(true)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let debug_info = artifact.debug_info_path().unwrap().to_path_buf();
+    assert!(debug_info.exists());
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    artifact.serialize_to_file(&path)?;
+    assert!(dir.path().join(debug_info.file_name().unwrap()).exists());
+    drop(artifact);
+
+    // The stripped `.so` is deserialized as usual.
+    let store = Store::new(&engine);
+    let module = unsafe { Module::deserialize_from_file(&store, &path)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    // The temporary debug info is removed with the code, once the
+    // engine unloads it.
+    assert!(debug_info.exists());
+    drop((run, instance, module, store, engine));
+    assert!(!debug_info.exists());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn artifact_cache(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .artifact_cache_capacity(1)
+        .engine();
+    engine.set_deterministic_prefixer(|bytes| format!("{:x}", bytes.len()));
+    let store = Store::new(&engine);
+    let wat = r#"(module (func (export "run") (result i32) (i32.const 42)))"#;
+    let other_wat =
+        r#"(module (func (export "run") (result i32) (i32.const 1) (i32.const 2) (i32.add)))"#;
+
+    let run = |module: &Module| -> Result<i32> {
+        let instance = Instance::new(module, &imports! {})?;
+        let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+        Ok(run.call()?)
+    };
+    let first = Module::new(&store, wat)?;
+    let second = Module::new(&store, wat)?;
+    assert_eq!(run(&first)?, 42);
+    assert_eq!(run(&second)?, 42);
+    let counters = engine.counters();
+    assert_eq!(counters.compiles, 1);
+    assert_eq!((counters.cache_hits, counters.cache_misses), (1, 1));
+
+    // The other module evicts the first one.
+    assert_eq!(run(&Module::new(&store, other_wat)?)?, 3);
+    assert_eq!(run(&Module::new(&store, wat)?)?, 42);
+    assert_eq!(engine.counters().compiles, 3);
+
+    engine.clear_artifact_cache();
+    Module::new(&store, wat)?;
+    let counters = engine.counters();
+    assert_eq!(counters.compiles, 4);
+    assert_eq!((counters.cache_hits, counters.cache_misses), (1, 4));
+
+    // The artifacts compiled with other settings or tunables aren't
+    // served for the same prefix.
+    let memory_wat = r#"(module (memory 1) (func (export "run") (result i32) (i32.const 42)))"#;
+    Module::new(&store, memory_wat)?;
+    let mut tunables = BaseTunables::for_target(&Target::default());
+    tunables.static_memory_bound = Pages(1);
+    Module::new(&Store::new_with_tunables(&engine, tunables), memory_wat)?;
+    assert_eq!(engine.counters().compiles, 6);
+    engine.set_text_alignment(64);
+    Module::new(&store, memory_wat)?;
+    assert_eq!(engine.counters().compiles, 7);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn android_target(config: crate::Config) -> Result<()> {
+    use std::str::FromStr;
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // Singlepass only supports x86_64.
+    if config.compiler == crate::Compiler::Singlepass {
+        return Ok(());
+    }
+    // Linking for Android needs the NDK.
+    let android_ndk = match std::env::var_os("ANDROID_NDK_HOME")
+        .or_else(|| std::env::var_os("ANDROID_NDK_ROOT"))
+    {
+        Some(android_ndk) => android_ndk,
+        None => return Ok(()),
+    };
+    let triple = Triple::from_str("aarch64-linux-android").unwrap();
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .target(Target::new(triple.clone(), CpuFeature::set()))
+        .android_ndk(android_ndk)
+        .engine();
+    let tunables = BaseTunables::for_target(&Target::new(triple.clone(), CpuFeature::set()));
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert_eq!(artifact.target_triple(), &triple);
+
+    // A 64-bit ELF shared object (`ET_DYN`) for AArch64 (`EM_AARCH64`).
+    let bytes = artifact.serialize()?;
+    assert!(DylibArtifact::detect_target(&bytes).is_some());
+    assert!(bytes.starts_with(&[0x7f, b'E', b'L', b'F', 2]));
+    assert_eq!(&bytes[16..20], &[3, 0, 183, 0]);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn new_and_serialize(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(br#"(module (func (export "run") (result i32) (i32.const 42)))"#)?;
+    let (artifact, bytes) = DylibArtifact::new_and_serialize(&engine, &wasm, &tunables)?;
+    assert_eq!(bytes, artifact.serialize()?);
+
+    let deserialized = unsafe { DylibArtifact::deserialize(&engine, &bytes)? };
+    assert_eq!(
+        deserialized.metadata().compile_info.module.exports.len(),
+        artifact.metadata().compile_info.module.exports.len()
+    );
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn disable_dynamic_lookup(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .dynamic_lookup(false)
+        .engine();
+    // The module uses no libcall, so all its symbols are resolved at link
+    // time.
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(1, 2)?, 3);
+    Ok(())
+}
+
+/// Whether the relocation is the one of a call between functions on
+/// `architecture`.
+#[cfg(feature = "cross-compile-tests")]
+fn is_call_relocation(
+    architecture: Architecture,
+    relocation: &wasmer_engine_dylib::testing::ObjectRelocation,
+) -> bool {
+    use wasmer_engine_dylib::testing::{RelocationEncoding, RelocationKind};
+
+    match architecture {
+        Architecture::X86_64 => {
+            relocation.size == 32
+                && matches!(
+                    relocation.kind,
+                    RelocationKind::Relative
+                        | RelocationKind::PltRelative
+                        | RelocationKind::MachO { relative: true, .. }
+                )
+        }
+        Architecture::Aarch64(_) => {
+            relocation.size == 26 || relocation.encoding == RelocationEncoding::AArch64Call
+        }
+        _ => false,
+    }
+}
+
+#[cfg(feature = "cross-compile-tests")]
+#[compiler_test(dylib)]
+fn cross_target_relocations(config: crate::Config) -> Result<()> {
+    use std::process::Command;
+    use std::str::FromStr;
+    use wasmer_engine::Artifact;
+    use wasmer_engine_dylib::testing::{object_relocations, RelocationKind};
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // Calls between functions and through a table, memory accesses,
+    // globals and float operations (libcalls on some targets).
+    let wasm = wat2wasm(
+        br#"(module
+            (type $t (func (param f32) (result f32)))
+            (memory 1)
+            (global $g (mut i32) (i32.const 0))
+            (table 1 funcref)
+            (elem (i32.const 0) $nearest)
+            (func $nearest (type $t) (f32.nearest (local.get 0)))
+            (func (export "run") (param f32) (result f32)
+                (global.set $g (i32.load (i32.const 0)))
+                (i32.store (i32.const 4) (global.get $g))
+                (f32.add
+                    (call $nearest (local.get 0))
+                    (call_indirect (type $t) (f32.floor (local.get 0)) (i32.const 0)))))"#,
+    )?;
+    // The Linux targets are linked with `lld` when it's available.
+    let can_link = ["clang", "ld.lld"].iter().all(|tool| {
+        Command::new(tool)
+            .arg("--version")
+            .output()
+            .map_or(false, |output| output.status.success())
+    });
+    let targets = [
+        "x86_64-unknown-linux-gnu",
+        "x86_64-apple-darwin",
+        "x86_64-pc-windows-msvc",
+        "aarch64-unknown-linux-gnu",
+        "aarch64-apple-darwin",
+    ];
+    for target in targets.iter() {
+        let triple = Triple::from_str(target).unwrap();
+        // Singlepass only supports x86_64.
+        if config.compiler == crate::Compiler::Singlepass
+            && triple.architecture != Architecture::X86_64
+        {
+            continue;
+        }
+        let target = Target::new(triple.clone(), CpuFeature::set());
+        let tunables = BaseTunables::for_target(&target);
+        // The relocations are only known when the engine emits the object
+        // file, which checking their bounds ensures.
+        let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+            .target(target.clone())
+            .verify_relocations(true)
+            .object_only(true)
+            .engine();
+        let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+        let relocations = object_relocations(&artifact.serialize()?)?;
+        assert!(!relocations.is_empty(), "{}: no relocations", triple);
+        for relocation in &relocations {
+            assert_ne!(
+                relocation.kind,
+                RelocationKind::Unknown,
+                "{}: {:?}",
+                triple,
+                relocation
+            );
+            let size = u64::from(relocation.size + 7) / 8;
+            assert!(
+                relocation.offset + size <= relocation.section_size,
+                "{}: out of bounds {:?}",
+                triple,
+                relocation
+            );
+        }
+        assert!(
+            relocations
+                .iter()
+                .any(|relocation| is_call_relocation(triple.architecture, relocation)),
+            "{}: no call relocation in {:?}",
+            triple,
+            relocations
+        );
+
+        if can_link && triple.operating_system == OperatingSystem::Linux {
+            let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+                .target(target.clone())
+                .engine();
+            let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+            let bytes = artifact.serialize()?;
+            assert!(bytes.starts_with(&[0x7f, b'E', b'L', b'F', 2]));
+            assert!(DylibArtifact::detect_target(&bytes).is_some());
+        }
+    }
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn load_module_versions(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // Both versions have the same functions (so the same symbol names,
+    // without a prefixer), with the exported one calling the other.
+    let version = |value: i32| {
+        format!(
+            r#"(module
+                (func $value (result i32) (i32.const {}))
+                (func (export "run") (result i32) (call $value)))"#,
+            value
+        )
+    };
+    let store = config.store();
+    let old = Module::new(&store, version(1))?;
+    let new = Module::new(&store, version(2))?;
+    let old_bytes = old.serialize()?;
+    let new_bytes = new.serialize()?;
+    let deserialized_old = unsafe { Module::deserialize(&store, &old_bytes)? };
+    let deserialized_new = unsafe { Module::deserialize(&store, &new_bytes)? };
+
+    let modules = [
+        (&old, 1),
+        (&new, 2),
+        (&deserialized_old, 1),
+        (&deserialized_new, 2),
+    ];
+    let instances = modules
+        .iter()
+        .map(|(module, _)| Instance::new(module, &imports! {}))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (instance, (_, value)) in instances.iter().zip(modules.iter()) {
+        let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+        assert_eq!(run.call()?, *value);
+    }
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn new_batch(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let tunables = BaseTunables::for_target(&Target::default());
+    let module = |value: i32| {
+        wat2wasm(
+            format!(
+                r#"(module (func (export "run") (result i32) (i32.const {})))"#,
+                value
+            )
+            .as_bytes(),
+        )
+        .map(|wasm| wasm.into_owned())
+    };
+    let wasms = vec![module(1)?, b"not wasm".to_vec(), module(3)?];
+    let modules = wasms.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+    let mut results = DylibArtifact::new_batch(&engine, &modules, &tunables, 2).collect::<Vec<_>>();
+    results.sort_by_key(|(index, _)| *index);
+    assert_eq!(
+        results.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert!(results[1].1.is_err());
+    for (index, result) in results {
+        if index == 1 {
+            continue;
+        }
+        let store = Store::new(&engine);
+        let module = unsafe { Module::deserialize(&store, &result?.serialize()?)? };
+        let instance = Instance::new(&module, &imports! {})?;
+        let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+        assert_eq!(run.call()?, index as i32 + 1);
+    }
+    Ok(())
+}
+
+/// Tunables that make the translation of every module but the first wait
+/// until a module was linked.
+#[cfg(target_os = "linux")]
+#[derive(loupe::MemoryUsage)]
+struct WaitForLinkTunables {
+    base: BaseTunables,
+    #[loupe(skip)]
+    linked: std::path::PathBuf,
+    #[loupe(skip)]
+    translated: std::sync::atomic::AtomicUsize,
+    #[loupe(skip)]
+    overlapped: std::sync::atomic::AtomicBool,
+}
+
+#[cfg(target_os = "linux")]
+impl Tunables for WaitForLinkTunables {
+    fn memory_style(&self, memory: &MemoryType) -> vm::MemoryStyle {
+        use std::sync::atomic::Ordering;
+        use std::time::{Duration, Instant};
+
+        if self.translated.fetch_add(1, Ordering::SeqCst) > 0 {
+            let start = Instant::now();
+            while !self.linked.exists() && start.elapsed() < Duration::from_secs(30) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            if self.linked.exists() {
+                self.overlapped.store(true, Ordering::SeqCst);
+            }
+        }
+        self.base.memory_style(memory)
+    }
+
+    fn table_style(&self, table: &TableType) -> vm::TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &vm::MemoryStyle,
+    ) -> std::result::Result<std::sync::Arc<dyn vm::Memory>, vm::MemoryError> {
+        self.base.create_host_memory(ty, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &vm::MemoryStyle,
+        vm_definition_location: std::ptr::NonNull<vm::VMMemoryDefinition>,
+    ) -> std::result::Result<std::sync::Arc<dyn vm::Memory>, vm::MemoryError> {
+        self.base
+            .create_vm_memory(ty, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &vm::TableStyle,
+    ) -> std::result::Result<std::sync::Arc<dyn vm::Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &vm::TableStyle,
+        vm_definition_location: std::ptr::NonNull<vm::VMTableDefinition>,
+    ) -> std::result::Result<std::sync::Arc<dyn vm::Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn new_batch_links_while_compiling(config: crate::Config) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::atomic::Ordering;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    // The linker marks the first module as linked, which the translation
+    // of the next modules waits for: it only happens if the engine isn't
+    // kept locked while they're compiled.
+    let dir = tempfile::tempdir()?;
+    let linked = dir.path().join("linked");
+    let linker = dir.path().join("linker.sh");
+    std::fs::write(
+        &linker,
+        format!(
+            "#!/bin/sh\ncc \"$@\" || exit 1\ntouch {}\n",
+            linked.display()
+        ),
+    )?;
+    std::fs::set_permissions(&linker, std::fs::Permissions::from_mode(0o755))?;
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .linker(linker.display().to_string())
+        .engine();
+    let tunables = WaitForLinkTunables {
+        base: BaseTunables::for_target(&Target::default()),
+        linked,
+        translated: Default::default(),
+        overlapped: Default::default(),
+    };
+    let wasms = (1..=3)
+        .map(|value| {
+            wat2wasm(
+                format!(
+                    r#"(module (memory 1) (func (export "run") (result i32) (i32.const {})))"#,
+                    value
+                )
+                .as_bytes(),
+            )
+            .map(|wasm| wasm.into_owned())
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let modules = wasms.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+    let mut results = DylibArtifact::new_batch(&engine, &modules, &tunables, 1);
+    // The first module is linked while the second one is compiled, so it's
+    // yielded before the others.
+    let (index, first) = results.next().unwrap();
+    assert_eq!(index, 0);
+    first?;
+    assert!(tunables.overlapped.load(Ordering::SeqCst));
+    for (_, result) in results {
+        result?;
+    }
+    Ok(())
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+#[compiler_test(dylib)]
+fn in_process_linking(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .in_process_linking(true)
+        .engine();
+    // The module calls a libcall and stores to its memory, so both the
+    // external symbols and the data of the object file are relocated.
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory 1)
+            (func $double (param i32) (result i32)
+                (i32.mul (local.get 0) (i32.const 2)))
+            (func (export "run") (param i32 f32) (result i32)
+                (i32.store (i32.const 8) (call $double (local.get 0)))
+                (i32.add
+                    (i32.load (i32.const 8))
+                    (i32.trunc_f32_s (f32.ceil (local.get 1))))))"#,
+    )?;
+    let serialized = module.serialize()?;
+    assert_eq!(&serialized[..4], b"\x7fELF");
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(i32, f32), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call(20, 1.5)?, 42);
+
+    // The serialized object file is loaded in process as well.
+    let module = unsafe { Module::deserialize(&store, &serialized)? };
+    // The stubs of the libcalls are mapped apart from the code, which
+    // is prefaulted one function at a time.
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    artifact.warm()?;
+    artifact.validate_instantiable()?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(i32, f32), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call(1, 0.5)?, 3);
+
+    // The engines linking the artifacts into shared objects don't load
+    // object files.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let store = Store::new(&engine);
+    assert!(matches!(
+        unsafe { Module::deserialize(&store, &serialized) },
+        Err(DeserializeError::Incompatible(_))
+    ));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[compiler_test(dylib)]
+fn custom_linker(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let wat = r#"(module (func (export "add") (param i32 i32) (result i32)
+        (i32.add (local.get 0) (local.get 1))))"#;
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .linker("gcc -O0")
+        .extra_link_args(vec!["-Wl,-z,now"])
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(1, 2)?, 3);
+
+    // The configured linker is used instead of the detected one.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .linker("wasmer-missing-linker")
+        .engine();
+    let store = Store::new(&engine);
+    assert!(Module::new(&store, wat).is_err());
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn emit_object(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let tunables = BaseTunables::for_target(&Target::default());
+    let wasm = wat2wasm(
+        br#"(module
+            (func (export "run") (result i32) (i32.const 1))
+            (func (export "other") (result i32) (i32.const 2)))"#,
+    )?;
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .keep_object_files(true)
+        .engine();
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    let object = artifact.emit_object()?;
+    let symbols = artifact.exported_function_symbols();
+    assert_eq!(symbols.len(), 2);
+    for (_, symbol) in &symbols {
+        assert!(object
+            .windows(symbol.len())
+            .any(|bytes| bytes == symbol.as_bytes()));
+    }
+    // The object file is not the shared object.
+    assert_ne!(object, artifact.serialize()?);
+
+    // The object file is not kept by default.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false)).engine();
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert!(artifact.emit_object().is_err());
+
+    // Artifacts that are not linked are their object file.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .object_only(true)
+        .engine();
+    let artifact = DylibArtifact::new(&engine, &wasm, &tunables)?;
+    assert_eq!(artifact.emit_object()?, artifact.serialize()?);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn cache_dir(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let dir = tempfile::tempdir()?;
+    let wat = r#"(module (func (export "run") (result i32) (i32.const 42)))"#;
+    let new_engine = || {
+        wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+            .cache_dir(dir.path())
+            .engine()
+    };
+    let cached_files = || -> Result<usize> { Ok(std::fs::read_dir(dir.path())?.count()) };
+
+    let engine = new_engine();
+    let store = Store::new(&engine);
+    Module::new(&store, wat)?;
+    assert_eq!(cached_files()?, 1);
+    assert_eq!(
+        (engine.counters().cache_hits, engine.counters().cache_misses),
+        (0, 1)
+    );
+
+    // Another engine loads the shared object from the directory.
+    let engine = new_engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, wat)?;
+    let counters = engine.counters();
+    assert_eq!((counters.cache_hits, counters.compiles), (1, 0));
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    // An engine with other settings compiles the module again, and so
+    // do other tunables.
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .cache_dir(dir.path())
+        .text_alignment(64)
+        .engine();
+    Module::new(&Store::new(&engine), wat)?;
+    assert_eq!(engine.counters().compiles, 1);
+    let engine = new_engine();
+    let memory_wat = r#"(module (memory 1) (func (export "run") (result i32) (i32.const 42)))"#;
+    Module::new(&Store::new(&engine), memory_wat)?;
+    let mut tunables = BaseTunables::for_target(&Target::default());
+    tunables.static_memory_bound = Pages(1);
+    Module::new(&Store::new_with_tunables(&engine, tunables), memory_wat)?;
+    assert_eq!(engine.counters().compiles, 2);
+
+    // With the artifact cache too, a compilation is one lookup.
+    let mut engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .cache_dir(dir.path())
+        .artifact_cache_capacity(1)
+        .engine();
+    engine.set_deterministic_prefixer(|bytes| format!("{:x}", bytes.len()));
+    let store = Store::new(&engine);
+    Module::new(&store, wat)?;
+    Module::new(&store, wat)?;
+    let counters = engine.counters();
+    assert_eq!((counters.cache_hits, counters.cache_misses), (1, 1));
+
+    // Another module is compiled, and the oldest shared object is
+    // evicted to fit in the maximum size.
+    let mut engine = new_engine();
+    engine.set_cache_dir_max_bytes(Some(1));
+    let store = Store::new(&engine);
+    Module::new(
+        &store,
+        r#"(module (func (export "run") (result i32) (i32.const 1)))"#,
+    )?;
+    assert_eq!(cached_files()?, 0);
+
+    engine.set_cache_dir_max_bytes(None);
+    Module::new(&store, wat)?;
+    assert_eq!(engine.clear_cache_dir()?, 1);
+    assert_eq!(cached_files()?, 0);
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn metadata_format_version(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let mut serialized = Module::new(&store, r#"(module (func (export "run")))"#)?.serialize()?;
+    unsafe { Module::deserialize(&store, &serialized)? };
+
+    // An artifact whose metadata has another format version is refused
+    // with a clear error.
+    let header = serialized
+        .windows(8)
+        .position(|bytes| bytes == b"WASMERMD")
+        .expect("the metadata has a header");
+    let format_version = wasmer_engine_dylib::METADATA_FORMAT_VERSION + 1;
+    serialized[header + 8..header + 12].copy_from_slice(&format_version.to_le_bytes());
+    match unsafe { Module::deserialize(&store, &serialized) } {
+        Err(DeserializeError::Incompatible(message)) => {
+            assert!(message.contains("metadata format"), "{}", message)
+        }
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("an incompatible artifact was loaded"),
+    }
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn deserialize_from_file_mapped(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 16) "hello")
+            (data (i32.const 1024) "world"))"#,
+    )?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    module.serialize_to_file(&path)?;
+
+    let module = unsafe { Module::deserialize_from_file(&store, &path)? };
+    let artifact: &DylibArtifact = module.artifact().as_ref().downcast_ref().unwrap();
+    // The data initializers borrowed from the mapped file are still
+    // reported by the artifact.
+    let data = artifact
+        .data_initializers()
+        .iter()
+        .map(|initializer| (initializer.location.offset, &*initializer.data))
+        .collect::<Vec<_>>();
+    assert_eq!(data, vec![(16, &b"hello"[..]), (1024, &b"world"[..])]);
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+    let view = memory.view::<u8>();
+    let read =
+        |offset: usize| -> Vec<u8> { view[offset..offset + 5].iter().map(|b| b.get()).collect() };
+    assert_eq!(read(16), b"hello");
+    assert_eq!(read(1024), b"world");
+
+    // The module is still the same as its reserialization.
+    let reloaded = unsafe { Module::deserialize(&store, &module.serialize()?)? };
+    let instance = Instance::new(&reloaded, &imports! {})?;
+    let view = instance.exports.get_memory("memory")?.view::<u8>();
+    let bytes: Vec<u8> = view[16..21].iter().map(|b| b.get()).collect();
+    assert_eq!(bytes, b"hello");
+    Ok(())
+}
+
+#[compiler_test(dylib)]
+fn integrity_check(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Dylib {
+        return Ok(());
+    }
+    use wasmer_engine_dylib::{artifact_verifying_key, INTEGRITY_KEY_LEN};
+
+    let signing_key = [1; INTEGRITY_KEY_LEN];
+    let expect_failure = |result: Result<Module, DeserializeError>, expected: &str| match result {
+        Err(DeserializeError::IntegrityCheckFailed(message)) => {
+            assert!(message.contains(expected), "{}", message)
+        }
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("an artifact failing the integrity check was loaded"),
+    };
+    let wat = r#"(module (func (export "run") (result i32) (i32.const 42)))"#;
+
+    let engine = wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+        .integrity_check(true)
+        .artifact_signing_key(&signing_key)
+        .engine();
+    let store = Store::new(&engine);
+    let serialized = Module::new(&store, wat)?.serialize()?;
+    let module = unsafe { Module::deserialize(&store, &serialized)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    // The sealed files are loaded, but not without their check.
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.so");
+    std::fs::write(&path, &serialized)?;
+    unsafe { Module::deserialize_from_file(&store, &path)? };
+    assert!(matches!(
+        unsafe { DylibArtifact::deserialize_from_file_unchecked(&engine, &path) },
+        Err(DeserializeError::IntegrityCheckFailed(_))
+    ));
+
+    // A tampered artifact is refused before it's loaded.
+    let mut tampered = serialized.clone();
+    let producer = tampered
+        .windows(6)
+        .position(|bytes| bytes == b"wasmer")
+        .expect("the metadata names its producer");
+    tampered[producer] = b'W';
+    expect_failure(unsafe { Module::deserialize(&store, &tampered) }, "hash");
+    let path = dir.path().join("tampered.so");
+    std::fs::write(&path, &tampered)?;
+    expect_failure(
+        unsafe { Module::deserialize_from_file(&store, &path) },
+        "hash",
+    );
+
+    // Artifacts without a hash are refused too.
+    let plain_store = config.store();
+    let plain = Module::new(&plain_store, wat)?.serialize()?;
+    unsafe { Module::deserialize(&plain_store, &plain)? };
+    expect_failure(
+        unsafe { Module::deserialize(&store, &plain) },
+        "no integrity",
+    );
+
+    // The signature is checked with the public key.
+    let verifying_engine = |signing_key: &[u8; INTEGRITY_KEY_LEN]| {
+        wasmer_engine_dylib::Dylib::new(config.compiler_config(false))
+            .artifact_verifying_key(&artifact_verifying_key(signing_key))
+            .engine()
+    };
+    unsafe { Module::deserialize(&Store::new(&verifying_engine(&signing_key)), &serialized)? };
+    expect_failure(
+        unsafe {
+            Module::deserialize(
+                &Store::new(&verifying_engine(&[2; INTEGRITY_KEY_LEN])),
+                &serialized,
+            )
+        },
+        "signature",
+    );
+    Ok(())
+}
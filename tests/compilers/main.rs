@@ -6,6 +6,8 @@
 extern crate compiler_test_derive;
 
 mod config;
+#[cfg(feature = "dylib")]
+mod dylib;
 mod imports;
 mod issues;
 mod metering;